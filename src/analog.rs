@@ -2,10 +2,13 @@
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 
-use crate::data::{get_analog_bytes_per_frame, get_point_bytes_per_frame, DataFormat};
-use crate::parameters::{Parameter, ParameterData, Parameters};
+use crate::data::{
+    get_analog_bytes_per_frame, get_point_bytes_per_frame, retain_indices, DataFormat,
+};
+use crate::options::ParseContext;
+use crate::parameters::{write_continued, Parameter, ParameterData, Parameters};
 use crate::processor::Processor;
-use crate::{C3dParseError, C3dWriteError};
+use crate::{C3dParseError, C3dWarningKind, C3dWriteError};
 use grid::Grid;
 
 /// Analog format describes whether the analog data is signed or unsigned.
@@ -19,8 +22,9 @@ pub enum AnalogFormat {
 impl AnalogFormat {
     pub(crate) fn from_parameters(
         parameters: &mut Parameters,
+        context: &mut ParseContext,
     ) -> Result<AnalogFormat, C3dParseError> {
-        let analog_format_parameter_data = parameters.remove("ANALOG", "FORMAT");
+        let analog_format_parameter_data = parameters.take("ANALOG", "FORMAT");
         match analog_format_parameter_data {
             Some(analog_format_parameter_data) => {
                 let analog_format_parameter_data: String =
@@ -28,7 +32,16 @@ impl AnalogFormat {
                 match analog_format_parameter_data.as_str() {
                     "SIGNED" => Ok(AnalogFormat::Signed),
                     "UNSIGNED" => Ok(AnalogFormat::Unsigned),
-                    _ => Ok(AnalogFormat::Signed),
+                    format => {
+                        context.warn_parameter(
+                            C3dWarningKind::InvalidParameter,
+                            parameters,
+                            "ANALOG",
+                            "FORMAT",
+                            format!("SIGNED instead of {:?}", format),
+                        );
+                        Ok(AnalogFormat::Signed)
+                    }
                 }
             }
             None => Ok(AnalogFormat::Signed),
@@ -54,15 +67,13 @@ impl AnalogOffset {
         parameters: &mut Parameters,
         format: &AnalogFormat,
     ) -> Result<AnalogOffset, C3dParseError> {
-        let offset = parameters.remove("ANALOG", "OFFSET");
-        let offset = match offset {
+        let offset: Vec<i16> = match parameters.take_continued("ANALOG", "OFFSET")? {
             Some(offset) => offset,
             None => return Ok(AnalogOffset::Signed(Vec::new())),
         };
         match format {
-            AnalogFormat::Signed => Ok(AnalogOffset::Signed(offset.as_ref().try_into()?)),
+            AnalogFormat::Signed => Ok(AnalogOffset::Signed(offset)),
             AnalogFormat::Unsigned => {
-                let offset: Vec<i16> = offset.as_ref().try_into()?;
                 let offset: Vec<u16> = offset.iter().map(|x| *x as u16).collect();
                 Ok(AnalogOffset::Unsigned(offset))
             }
@@ -86,6 +97,8 @@ pub struct Analog {
     pub offset: AnalogOffset,
     pub gen_scale: f32,
     pub bits: i16,
+    /// `ANALOG:GAIN` codes of the channels, see `Analog::voltage_range`.
+    pub gains: Vec<i16>,
 }
 
 impl PartialEq for Analog {
@@ -102,6 +115,7 @@ impl PartialEq for Analog {
             && self.offset == other.offset
             && self.gen_scale == other.gen_scale
             && self.bits == other.bits
+            && self.gains == other.gains
     }
 }
 
@@ -120,6 +134,7 @@ impl Default for Analog {
             offset: AnalogOffset::Signed(Vec::new()),
             gen_scale: 0.0,
             bits: 0,
+            gains: Vec::new(),
         }
     }
 }
@@ -156,6 +171,7 @@ impl ToString for Analog {
         string.push_str(&format!("Offset: {:?}\n", self.offset));
         string.push_str(&format!("Gen scale: {}\n", self.gen_scale));
         string.push_str(&format!("Bits: {}\n", self.bits));
+        string.push_str(&format!("Gains: {:?}\n", self.gains));
         string
     }
 }
@@ -172,26 +188,218 @@ impl Analog {
         analog
     }
 
-    pub(crate) fn parse(
+    /// Reads the ANALOG parameters and checks that they describe the
+    /// layout of the data section. Returns the number of analog channels used.
+    pub(crate) fn parse_parameters(
         &mut self,
-        data_bytes: &Vec<u8>,
         parameters: &mut Parameters,
+        format: &DataFormat,
+        frame_rate: f32,
+        context: &mut ParseContext,
+    ) -> Result<u16, C3dParseError> {
+        let mut analog_used = self.get_analog_parameters(parameters, frame_rate, context)?;
+        if context.lenient {
+            analog_used = self.apply_lenient_fallbacks(analog_used, parameters, context);
+        }
+        let analog_bytes_per_frame = get_analog_bytes_per_frame(format, self.samples_per_frame)?;
+        let bytes_per_analog_point = match self.samples_per_frame {
+            0 => 0,
+            _ => analog_bytes_per_frame / self.samples_per_frame as usize,
+        };
+        if analog_bytes_per_frame
+            != bytes_per_analog_point
+                * analog_used as usize
+                * self.samples_per_channel_per_frame as usize
+        {
+            return Err(C3dParseError::AnalogBytesPerFrameMismatch);
+        }
+        let offset_len = match &self.offset {
+            AnalogOffset::Signed(offset) => offset.len(),
+            AnalogOffset::Unsigned(offset) => offset.len(),
+        };
+        if analog_used > 0 && offset_len != self.scales.len() {
+            return Err(C3dParseError::AnalogOffsetScaleMismatch);
+        }
+        if analog_used as usize > offset_len {
+            return Err(C3dParseError::InsufficientAnalogOffsets);
+        }
+        Ok(analog_used)
+    }
+
+    /// Decodes and scales the analog samples of a single frame from the analog
+    /// portion of a data record. The returned grid has one row per sample.
+    pub(crate) fn parse_frame(
+        &self,
+        analog_frame_data: &[u8],
         processor: &Processor,
-        num_frames: usize,
         format: &DataFormat,
-        points_per_frame: usize,
-    ) -> Result<&mut Self, C3dParseError> {
-        let analog_used = self.get_analog_parameters(parameters)?;
-        self.parse_analog(
-            data_bytes,
-            processor,
-            num_frames,
-            format,
-            points_per_frame,
-            analog_used,
+        analog_used: u16,
+    ) -> Grid<f64> {
+        let mut analog_data = Grid::new(
+            self.samples_per_channel_per_frame as usize,
+            analog_used as usize,
+        );
+        let bytes_per_analog_point = get_analog_bytes_per_frame(format, 1).unwrap_or(0);
+        for j in 0..analog_data.rows() {
+            let start = j * bytes_per_analog_point * analog_used as usize;
+            let end = start + (bytes_per_analog_point * analog_used as usize);
+            let analog_slice = &analog_frame_data[start..end];
+            let temp_analog_data = match format {
+                DataFormat::Float => {
+                    parse_analog_data_float(analog_slice, analog_used as usize, processor)
+                }
+                DataFormat::Integer => parse_analog_data_int(
+                    analog_slice,
+                    analog_used as usize,
+                    processor,
+                    self.is_unsigned(),
+                ),
+            };
+            for (k, value) in temp_analog_data.into_iter().enumerate() {
+                analog_data[j][k] = self.calibrated_value(value as f64, k);
+            }
+        }
+        analog_data
+    }
+
+    /// Decodes only the channels at the given column indices of a single frame.
+    pub(crate) fn parse_frame_columns(
+        &self,
+        analog_frame_data: &[u8],
+        processor: &Processor,
+        format: &DataFormat,
+        analog_used: u16,
+        columns: &[usize],
+    ) -> Grid<f64> {
+        let rows = self.samples_per_channel_per_frame as usize;
+        let mut analog_data = Vec::with_capacity(rows * columns.len());
+        let bytes_per_analog_point = get_analog_bytes_per_frame(format, 1).unwrap_or(0);
+        for j in 0..rows {
+            let row_start = j * bytes_per_analog_point * analog_used as usize;
+            for column in columns {
+                let start = row_start + column * bytes_per_analog_point;
+                let analog_slice = &analog_frame_data[start..start + bytes_per_analog_point];
+                let value = match format {
+                    DataFormat::Float => parse_analog_data_float(analog_slice, 1, processor),
+                    DataFormat::Integer => {
+                        parse_analog_data_int(analog_slice, 1, processor, self.is_unsigned())
+                    }
+                }[0];
+                analog_data.push(self.calibrated_value(value as f64, *column));
+            }
+        }
+        Grid::from_vec(analog_data, columns.len())
+    }
+
+    /// Keeps the labels, scales and offsets of the channels at the given column indices.
+    pub(crate) fn retain_columns(&mut self, columns: &[usize]) {
+        self.labels = retain_indices(&self.labels, columns);
+        self.descriptions = retain_indices(&self.descriptions, columns);
+        self.units = retain_indices(&self.units, columns);
+        self.scales = retain_indices(&self.scales, columns);
+        if !self.gains.is_empty() {
+            self.gains = retain_indices(&self.gains, columns);
+        }
+        self.offset = match &self.offset {
+            AnalogOffset::Signed(offset) => AnalogOffset::Signed(retain_indices(offset, columns)),
+            AnalogOffset::Unsigned(offset) => {
+                AnalogOffset::Unsigned(retain_indices(offset, columns))
+            }
+        };
+        self.samples_per_frame =
+            (columns.len() * self.samples_per_channel_per_frame as usize) as u16;
+    }
+
+    fn is_unsigned(&self) -> bool {
+        matches!(self.offset, AnalogOffset::Unsigned(_))
+    }
+
+    fn channel_offset(&self, channel: usize) -> f64 {
+        match &self.offset {
+            AnalogOffset::Signed(offset) => offset.get(channel).map_or(0.0, |x| *x as f64),
+            AnalogOffset::Unsigned(offset) => offset.get(channel).map_or(0.0, |x| *x as f64),
+        }
+    }
+
+    fn channel_scale(&self, channel: usize) -> f64 {
+        self.scales.get(channel).map_or(1.0, |x| *x as f64) * self.gen_scale as f64
+    }
+
+    /// Converts a value stored in the file, in ADC counts, to engineering units
+    /// by removing the `ANALOG:OFFSET` of the channel and applying its `ANALOG:SCALE`
+    /// and `ANALOG:GEN_SCALE`.
+    pub fn calibrated_value(&self, raw: f64, channel: usize) -> f64 {
+        (raw - self.channel_offset(channel)) * self.channel_scale(channel)
+    }
+
+    /// The inverse of `calibrated_value`, the value in ADC counts that is stored in the file
+    /// for a value in engineering units.
+    pub fn raw_value(&self, value: f64, channel: usize) -> f64 {
+        let offset = self.channel_offset(channel);
+        let scale = self.channel_scale(channel);
+        // channels without a scale read as 0 whatever is stored
+        if scale == 0.0 {
+            return offset;
+        }
+        value / scale + offset
+    }
+
+    /// The samples of a channel in engineering units, as stored in `analog`.
+    pub fn calibrated(&self, channel: usize) -> Option<Vec<f64>> {
+        if channel >= self.analog.cols() {
+            return None;
+        }
+        Some(self.analog.iter_col(channel).copied().collect())
+    }
+
+    /// The samples of a channel in ADC counts, as they are stored in the file.
+    pub fn raw_counts(&self, channel: usize) -> Option<Vec<f64>> {
+        if channel >= self.analog.cols() {
+            return None;
+        }
+        Some(
+            self.analog
+                .iter_col(channel)
+                .map(|value| self.raw_value(*value, channel))
+                .collect(),
+        )
+    }
+
+    /// The samples of a channel as the voltage measured by the ADC, from the
+    /// voltage range of its `ANALOG:GAIN` and the resolution in `ANALOG:BITS`.
+    /// Returns `None` if the file does not describe the gain of the channel.
+    pub fn volts(&self, channel: usize) -> Option<Vec<f64>> {
+        if channel >= self.analog.cols() || self.bits <= 0 || self.bits > 16 {
+            return None;
+        }
+        let volts_per_count = self.voltage_range(channel)? / (1u32 << (self.bits - 1)) as f64;
+        let offset = self.channel_offset(channel);
+        Some(
+            self.analog
+                .iter_col(channel)
+                .map(|value| (self.raw_value(*value, channel) - offset) * volts_per_count)
+                .collect(),
         )
     }
 
+    /// The input range of a channel in volts from its `ANALOG:GAIN` code:
+    /// 1 is ±10 V, 2 is ±5 V, 3 is ±2.5 V, 4 is ±1.25 V and 5 is ±1 V.
+    pub fn voltage_range(&self, channel: usize) -> Option<f64> {
+        match self.gains.get(channel)? {
+            1 => Some(10.0),
+            2 => Some(5.0),
+            3 => Some(2.5),
+            4 => Some(1.25),
+            5 => Some(1.0),
+            _ => None,
+        }
+    }
+
+    /// The engineering unit of a channel from `ANALOG:UNITS`.
+    pub fn unit(&self, channel: usize) -> Option<&str> {
+        self.units.get(channel).map(|unit| unit.trim())
+    }
+
     pub(crate) fn write_parameters(
         &self,
         processor: &Processor,
@@ -206,18 +414,20 @@ impl Analog {
             false,
         )?);
         // "ANALOG", "LABELS"
-        bytes.extend(Parameter::strings(self.labels.clone()).write(
+        bytes.extend(write_continued(
+            &self.labels,
+            |labels| Ok(Parameter::strings(labels)),
             processor,
-            "LABELS".to_string(),
+            "LABELS",
             group_names_to_ids["ANALOG"],
-            false,
         )?);
         // "ANALOG", "DESCRIPTIONS"
-        bytes.extend(Parameter::strings(self.descriptions.clone()).write(
+        bytes.extend(write_continued(
+            &self.descriptions,
+            |descriptions| Ok(Parameter::strings(descriptions)),
             processor,
-            "DESCRIPTIONS".to_string(),
+            "DESCRIPTIONS",
             group_names_to_ids["ANALOG"],
-            false,
         )?);
         // "ANALOG", "GEN_SCALE"
         bytes.extend(Parameter::float(self.gen_scale).write(
@@ -227,19 +437,21 @@ impl Analog {
             false,
         )?);
         // "ANALOG", "UNITS"
-        bytes.extend(Parameter::strings(self.units.clone()).write(
+        bytes.extend(write_continued(
+            &self.units,
+            |units| Ok(Parameter::strings(units)),
             processor,
-            "UNITS".to_string(),
+            "UNITS",
             group_names_to_ids["ANALOG"],
-            false,
         )?);
         // "ANALOG", "SCALE"
         if self.scales.len() != 0 {
-            bytes.extend(Parameter::floats(self.scales.clone())?.write(
+            bytes.extend(write_continued(
+                &self.scales,
+                Parameter::floats,
                 processor,
-                "SCALE".to_string(),
+                "SCALE",
                 group_names_to_ids["ANALOG"],
-                false,
             )?);
         }
         // "ANALOG", "RATE"
@@ -275,76 +487,225 @@ impl Analog {
             group_names_to_ids["ANALOG"],
             false,
         )?);
+        // "ANALOG", "GAIN"
+        if !self.gains.is_empty() {
+            bytes.extend(write_continued(
+                &self.gains,
+                Parameter::integers,
+                processor,
+                "GAIN",
+                group_names_to_ids["ANALOG"],
+            )?);
+        }
         // "ANALOG", "OFFSET"
         let offset: Vec<i16> = match &self.offset {
             AnalogOffset::Signed(offset) => offset.iter().map(|x| *x as i16).collect(),
             AnalogOffset::Unsigned(offset) => offset.iter().map(|x| *x as i16).collect(),
         };
         if offset.len() != 0 {
-            bytes.extend(Parameter::integers(offset)?.write(
+            bytes.extend(write_continued(
+                &offset,
+                Parameter::integers,
                 processor,
-                "OFFSET".to_string(),
+                "OFFSET",
                 group_names_to_ids["ANALOG"],
-                false,
             )?);
         }
         Ok(bytes)
     }
 
+    /// Sets `ANALOG:SCALE` of every channel so that its largest value is stored as 32000,
+    /// and rounds the samples to the values that are written in the integer format.
+    /// Signed offsets are set to 0 and unsigned offsets to 32768.
+    /// Returns the largest change of a sample per channel.
+    pub(crate) fn convert_to_integer(&mut self) -> Vec<f64> {
+        let channels = self.analog.cols();
+        if self.gen_scale == 0.0 {
+            self.gen_scale = 1.0;
+        }
+        self.scales.resize(channels, 1.0);
+        self.offset = match &self.offset {
+            AnalogOffset::Signed(_) => AnalogOffset::Signed(vec![0; channels]),
+            AnalogOffset::Unsigned(_) => AnalogOffset::Unsigned(vec![32768; channels]),
+        };
+        let mut max_errors = vec![0.0; channels];
+        for channel in 0..channels {
+            let max_value = self
+                .analog
+                .iter_col(channel)
+                .filter(|value| value.is_finite())
+                .fold(0.0f64, |max, value| max.max(value.abs()));
+            if max_value > 0.0 {
+                self.scales[channel] = (max_value / 32000.0 / self.gen_scale as f64) as f32;
+            } else if self.scales[channel] == 0.0 {
+                self.scales[channel] = 1.0;
+            }
+            for row in 0..self.analog.rows() {
+                let value = self.analog[row][channel];
+                let raw = self.raw_value(value, channel).round();
+                let rounded = self.calibrated_value(raw, channel);
+                max_errors[channel] = f64::max(max_errors[channel], (rounded - value).abs());
+                self.analog[row][channel] = rounded;
+            }
+        }
+        max_errors
+    }
+
+    /// Undoes the scaling of `convert_to_integer` so that the samples are written in
+    /// engineering units, `ANALOG:SCALE` and `ANALOG:GEN_SCALE` are set to 1 and the
+    /// offsets to 0. The samples themselves are not changed.
+    pub(crate) fn convert_to_float(&mut self) {
+        let channels = self.analog.cols();
+        self.gen_scale = 1.0;
+        self.scales = vec![1.0; channels];
+        self.offset = match &self.offset {
+            AnalogOffset::Signed(_) => AnalogOffset::Signed(vec![0; channels]),
+            AnalogOffset::Unsigned(_) => AnalogOffset::Unsigned(vec![0; channels]),
+        };
+    }
+
     pub(crate) fn write_frame(
         &self,
         frame: usize,
         processor: &Processor,
         data_format: &DataFormat,
-    ) -> Vec<u8> {
+    ) -> Result<Vec<u8>, C3dWriteError> {
         let mut bytes = Vec::new();
 
         let start_row = frame * self.samples_per_channel_per_frame as usize;
         let end_row = start_row + self.samples_per_channel_per_frame as usize;
         if end_row > self.analog.size().0 {
-            return bytes;
+            return Ok(bytes);
         }
         for i in start_row..end_row {
             for (column, value) in self.analog.iter_row(i).enumerate() {
                 match data_format {
                     DataFormat::Float => {
-                        let offset = match &self.offset {
-                            AnalogOffset::Signed(offset) => offset[column] as f64,
-                            AnalogOffset::Unsigned(offset) => offset[column] as f64,
-                        };
-                        let value = value / self.scales[column] as f64 / self.gen_scale as f64
-                            + offset as f64;
+                        let value = self.raw_value(*value, column);
                         let value = processor.f32_to_bytes(value as f32);
                         bytes.extend_from_slice(&value);
                     }
-                    DataFormat::Integer => match &self.offset {
-                        AnalogOffset::Signed(offset) => {
-                            let offset = offset[column] as f64;
-                            let value = value / self.scales[column] as f64 / self.gen_scale as f64
-                                + offset as f64;
-                            let value = value.round() as i16;
-                            let value = processor.i16_to_bytes(value);
-                            bytes.extend_from_slice(&value);
+                    DataFormat::Integer => {
+                        let value = self.raw_value(*value, column).round();
+                        let range = match &self.offset {
+                            AnalogOffset::Signed(_) => i16::MIN as f64..=i16::MAX as f64,
+                            AnalogOffset::Unsigned(_) => u16::MIN as f64..=u16::MAX as f64,
+                        };
+                        if !range.contains(&value) {
+                            return Err(C3dWriteError::IntegerOverflow(format!(
+                                "analog channel {} in frame {} does not fit ANALOG:SCALE {}",
+                                column, frame, self.scales[column]
+                            )));
                         }
-                        AnalogOffset::Unsigned(offset) => {
-                            let offset = offset[column] as f64;
-                            let value = value / self.scales[column] as f64 / self.gen_scale as f64
-                                + offset as f64;
-                            let value = value.round() as u16;
-                            let value = processor.u16_to_bytes(value);
-                            bytes.extend_from_slice(&value);
+                        match &self.offset {
+                            AnalogOffset::Signed(_) => {
+                                bytes.extend_from_slice(&processor.i16_to_bytes(value as i16))
+                            }
+                            AnalogOffset::Unsigned(_) => {
+                                bytes.extend_from_slice(&processor.u16_to_bytes(value as u16))
+                            }
                         }
-                    },
+                    }
                 }
             }
         }
-        bytes
+        Ok(bytes)
+    }
+
+    /// Makes `ANALOG:USED`, the header and the scales and offsets agree with each other.
+    fn apply_lenient_fallbacks(
+        &mut self,
+        analog_used: u16,
+        parameters: &Parameters,
+        context: &mut ParseContext,
+    ) -> u16 {
+        if analog_used == 0 {
+            return analog_used;
+        }
+        let analog_used = match self.labels.len() {
+            num_labels if num_labels > 0 && (num_labels as u16) < analog_used => {
+                context.warn_parameter(
+                    C3dWarningKind::ParameterMismatch,
+                    parameters,
+                    "ANALOG",
+                    "USED",
+                    format!(
+                        "{} channels from ANALOG:LABELS instead of {}",
+                        num_labels, analog_used
+                    ),
+                );
+                num_labels as u16
+            }
+            _ => analog_used,
+        };
+        if self.samples_per_channel_per_frame == 0
+            && self.samples_per_frame.is_multiple_of(analog_used)
+        {
+            self.samples_per_channel_per_frame = self.samples_per_frame / analog_used;
+            context.warn_header(
+                C3dWarningKind::HeaderMismatch,
+                18,
+                format!(
+                    "{} analog samples per channel per frame",
+                    self.samples_per_channel_per_frame
+                ),
+            );
+        }
+        // some files store the number of channels in the header instead of
+        // the number of samples per frame
+        if self.samples_per_frame != analog_used * self.samples_per_channel_per_frame {
+            context.warn_header(
+                C3dWarningKind::HeaderMismatch,
+                4,
+                format!(
+                    "{} analog samples per frame instead of {}",
+                    analog_used * self.samples_per_channel_per_frame,
+                    self.samples_per_frame
+                ),
+            );
+            self.samples_per_frame = analog_used * self.samples_per_channel_per_frame;
+        }
+        let offset_len = match &self.offset {
+            AnalogOffset::Signed(offset) => offset.len(),
+            AnalogOffset::Unsigned(offset) => offset.len(),
+        };
+        if offset_len != self.scales.len() || (analog_used as usize) > offset_len {
+            if self.scales.len() != analog_used as usize {
+                context.warn_parameter(
+                    C3dWarningKind::ParameterMismatch,
+                    parameters,
+                    "ANALOG",
+                    "SCALE",
+                    format!("{} scales, padded with 1", analog_used),
+                );
+            }
+            if offset_len != analog_used as usize {
+                context.warn_parameter(
+                    C3dWarningKind::ParameterMismatch,
+                    parameters,
+                    "ANALOG",
+                    "OFFSET",
+                    format!("{} offsets, padded with 0", analog_used),
+                );
+            }
+            self.scales.resize(analog_used as usize, 1.0);
+            match &mut self.offset {
+                AnalogOffset::Signed(offset) => offset.resize(analog_used as usize, 0),
+                AnalogOffset::Unsigned(offset) => offset.resize(analog_used as usize, 0),
+            }
+        }
+        analog_used
     }
 
-    fn get_analog_parameters(&mut self, parameters: &mut Parameters) -> Result<u16, C3dParseError> {
-        let analog_format = AnalogFormat::from_parameters(parameters)?;
+    fn get_analog_parameters(
+        &mut self,
+        parameters: &mut Parameters,
+        frame_rate: f32,
+        context: &mut ParseContext,
+    ) -> Result<u16, C3dParseError> {
+        let analog_format = AnalogFormat::from_parameters(parameters, context)?;
         self.offset = AnalogOffset::from_parameters(parameters, &analog_format)?;
-        let used = parameters.remove("ANALOG", "USED");
+        let used = parameters.take("ANALOG", "USED");
         let mut is_none_or_zero = used.is_none();
         if !is_none_or_zero {
             let used = used.clone().unwrap();
@@ -359,35 +720,63 @@ impl Analog {
             self.scales = Vec::new();
             self.rate = 0.0;
             self.bits = 0;
+            self.gains = Vec::new();
             return Ok(0);
         } else {
-            self.labels = parameters
-                .remove_or_err("ANALOG", "LABELS")?
-                .as_ref()
-                .try_into()?;
-            self.descriptions = parameters
-                .remove("ANALOG", "DESCRIPTIONS")
-                .unwrap_or(Parameter::strings(vec![" ".to_string()]))
-                .as_ref()
-                .try_into()?;
+            self.labels = parameters.take_continued_or_err("ANALOG", "LABELS")?;
+            self.descriptions = match parameters.take_continued("ANALOG", "DESCRIPTIONS")? {
+                Some(descriptions) => descriptions,
+                None => {
+                    context.warn_parameter(
+                        C3dWarningKind::MissingParameter,
+                        parameters,
+                        "ANALOG",
+                        "DESCRIPTIONS",
+                        "a single blank description".to_string(),
+                    );
+                    Parameter::strings(vec![" ".to_string()])
+                        .as_ref()
+                        .try_into()?
+                }
+            };
             self.gen_scale = parameters
-                .remove_or_err("ANALOG", "GEN_SCALE")?
-                .as_ref()
-                .try_into()?;
-            self.units = parameters
-                .remove_or_err("ANALOG", "UNITS")?
+                .take_or_err("ANALOG", "GEN_SCALE")?
                 .as_ref()
                 .try_into()?;
-            self.scales = parameters
-                .remove_or_err("ANALOG", "SCALE")?
-                .as_ref()
-                .try_into()?;
-            self.rate = parameters
-                .remove_or_err("ANALOG", "RATE")?
-                .as_ref()
-                .try_into()?;
-            let bits = parameters.remove("ANALOG", "BITS");
+            self.units = parameters.take_continued_or_err("ANALOG", "UNITS")?;
+            self.scales = parameters.take_continued_or_err("ANALOG", "SCALE")?;
+            self.rate = match parameters.take("ANALOG", "RATE") {
+                Some(rate) => rate.as_ref().try_into()?,
+                None if context.lenient => {
+                    let rate = frame_rate * self.samples_per_channel_per_frame as f32;
+                    context.warn_parameter(
+                        C3dWarningKind::MissingParameter,
+                        parameters,
+                        "ANALOG",
+                        "RATE",
+                        format!("{} Hz from the header frame rate", rate),
+                    );
+                    rate
+                }
+                None => {
+                    return Err(C3dParseError::ParameterNotFound(
+                        "ANALOG".to_string(),
+                        "RATE".to_string(),
+                    ))
+                }
+            };
+            self.gains = parameters
+                .take_continued("ANALOG", "GAIN")?
+                .unwrap_or_default();
+            let bits = parameters.take("ANALOG", "BITS");
             if bits.is_none() {
+                context.warn_parameter(
+                    C3dWarningKind::MissingParameter,
+                    parameters,
+                    "ANALOG",
+                    "BITS",
+                    "12 bits".to_string(),
+                );
                 self.bits = 12;
             } else {
                 self.bits = match &bits.unwrap().data {
@@ -397,6 +786,7 @@ impl Analog {
                     _ => {
                         return Err(C3dParseError::InvalidParameterType(
                             "ANALOG:BITS".to_string(),
+                            None,
                         ))
                     }
                 };
@@ -405,9 +795,9 @@ impl Analog {
         Ok(used.unwrap().as_ref().try_into()?)
     }
 
-    fn parse_analog(
+    pub(crate) fn parse_analog(
         &mut self,
-        data_bytes: &Vec<u8>,
+        data_bytes: &[u8],
         processor: &Processor,
         num_frames: usize,
         format: &DataFormat,
@@ -423,63 +813,18 @@ impl Analog {
 
         let analog_bytes_per_frame = get_analog_bytes_per_frame(format, self.samples_per_frame)?;
         let bytes_per_frame = point_bytes_per_frame + analog_bytes_per_frame;
-        let bytes_per_analog_point = match self.samples_per_frame {
-            0 => 0,
-            _ => analog_bytes_per_frame / self.samples_per_frame as usize,
-        };
-        if analog_bytes_per_frame
-            != bytes_per_analog_point
-                * analog_used as usize
-                * self.samples_per_channel_per_frame as usize
-        {
-            return Err(C3dParseError::AnalogBytesPerFrameMismatch);
-        }
         for i in 0..num_frames {
             let start = i * bytes_per_frame as usize;
             let end = start + bytes_per_frame as usize;
             let analog_frame_data = &data_bytes[start + point_bytes_per_frame as usize..end];
-            for j in 0..self.samples_per_channel_per_frame {
-                let start = j as usize * bytes_per_analog_point * analog_used as usize;
-                let end = start + (bytes_per_analog_point * analog_used as usize);
-                let analog_slice = &analog_frame_data[start as usize..end as usize];
-                let temp_analog_data = match format {
-                    DataFormat::Float => {
-                        parse_analog_data_float(analog_slice, analog_used as usize, processor)
-                    }
-                    DataFormat::Integer => {
-                        parse_analog_data_int(analog_slice, analog_used as usize, processor)
-                    }
-                };
-                for k in 0..analog_used as usize {
-                    let row = i * self.samples_per_channel_per_frame as usize + j as usize;
-                    analog_data[row][k] = temp_analog_data[k] as f64;
+            let frame_data = self.parse_frame(analog_frame_data, processor, format, analog_used);
+            for j in 0..frame_data.rows() {
+                let row = i * self.samples_per_channel_per_frame as usize + j;
+                for k in 0..frame_data.cols() {
+                    analog_data[row][k] = frame_data[j][k];
                 }
             }
         }
-        let offset_len = match &self.offset {
-            AnalogOffset::Signed(offset) => offset.len(),
-            AnalogOffset::Unsigned(offset) => offset.len(),
-        };
-        if analog_used > 0 && offset_len != self.scales.len() {
-            return Err(C3dParseError::AnalogOffsetScaleMismatch);
-        }
-        if analog_used as usize <= offset_len {
-            for i in 0..analog_data.cols() {
-                let col_iter = analog_data.iter_col_mut(i);
-                match &self.offset {
-                    AnalogOffset::Signed(offset) => col_iter.for_each(|x| {
-                        *x -= offset[i] as f64;
-                        *x *= self.scales[i] as f64 * self.gen_scale as f64;
-                    }),
-                    AnalogOffset::Unsigned(offset) => col_iter.for_each(|x| {
-                        *x -= offset[i] as f64;
-                        *x *= self.scales[i] as f64 * self.gen_scale as f64;
-                    }),
-                };
-            }
-        } else {
-            return Err(C3dParseError::InsufficientAnalogOffsets);
-        }
         self.analog = analog_data;
         Ok(self)
     }
@@ -505,13 +850,17 @@ fn parse_analog_data_int(
     analog_frame_data: &[u8],
     num_analog_channels: usize,
     processor: &Processor,
+    unsigned: bool,
 ) -> Vec<f32> {
     let mut analog_data = Vec::with_capacity(num_analog_channels);
     for i in 0..num_analog_channels {
         let start = i * 2;
         let end = start + 2;
         let analog_slice = analog_frame_data[start..end].try_into().unwrap();
-        let analog = processor.i16(analog_slice) as f32;
+        let analog = match unsigned {
+            true => processor.u16(analog_slice) as f32,
+            false => processor.i16(analog_slice) as f32,
+        };
         analog_data.push(analog);
     }
     analog_data
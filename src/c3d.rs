@@ -1,27 +1,36 @@
 //! Includes the C3d struct implementation and high-level functions for reading and writing C3D files.
 use crate::analog::Analog;
-use crate::data::DataFormat;
-use crate::forces::ForcePlatforms;
+use crate::data::{DataFormat, PrecisionLoss};
+use crate::forces::{ForcePlatforms, LabWrench, Wrench};
+use crate::gait;
 use crate::manufacturer::Manufacturer;
+use crate::options::{
+    ForceEventOptions, KinematicEventOptions, LoadOptions, ParseContext, WriteOptions, ZeroOptions,
+};
 use crate::parameters::Parameters;
 use crate::points::Points;
+use crate::reader::C3dReader;
 use crate::seg::Seg;
 
-use crate::events::Events;
+use crate::events::{Event, Events};
 use crate::processor::Processor;
-use crate::{C3dParseError, C3dWriteError};
+use crate::{C3dParseError, C3dWarning, C3dWriteError, ErrorLocation};
 
+use grid::Grid;
+
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom, Write};
-use std::path::PathBuf;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 
 use std::fmt::{Debug, Formatter};
 
 /// Represents a parsed C3D file.
 /// Each field contains the data from the corresponding section of the file.
+#[derive(Clone)]
 pub struct C3d {
     pub parameters: Parameters,
-    processor: Processor,
+    pub(crate) processor: Processor,
     pub points: Points,
     pub analog: Analog,
     pub events: Events,
@@ -29,6 +38,15 @@ pub struct C3d {
     pub seg: Seg,
     pub forces: ForcePlatforms,
     header_bytes: [u8; 512],
+    /// The typed structs right after parsing, see `record_parsed_parameters`.
+    parsed: Option<Box<ParsedTyped>>,
+}
+
+/// The typed structs of a parsed file without their data, and its number of frames.
+#[derive(Clone)]
+struct ParsedTyped {
+    c3d: C3d,
+    num_frames: usize,
 }
 
 impl PartialEq for C3d {
@@ -71,6 +89,7 @@ impl Default for C3d {
             seg: Seg::default(),
             forces: ForcePlatforms::default(),
             header_bytes: [0u8; 512],
+            parsed: None,
         }
     }
 }
@@ -103,30 +122,148 @@ impl C3d {
     /// <https://users.rust-lang.org/t/pathbuf-and-path-why-not-string/28777>
     pub fn load_path(file_path: PathBuf) -> Result<C3d, C3dParseError> {
         let c3d = C3d::new();
-        let (c3d, mut file) = c3d.open_file(file_path)?;
-        let (c3d, header_bytes, parameter_bytes, _) = c3d.parse_basic_info(&mut file)?;
-        Ok(c3d
-            .parse_header(&header_bytes)?
-            .parse_parameters(&header_bytes, &parameter_bytes)?
-            .parse_data(file)?)
+        let (_, file) = c3d.open_file(file_path)?;
+        C3d::from_reader(file)
+    }
+
+    /// Parses a C3D file from a file path string through a read-only memory map,
+    /// avoiding a copy of the file contents.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this or any other process,
+    /// while it is being parsed. See `load_mmap_path`.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn load_mmap(file_name: &str) -> Result<C3d, C3dParseError> {
+        C3d::load_mmap_path(PathBuf::from(file_name))
+    }
+
+    /// Parses a C3D file from a file path through a read-only memory map.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this or any other process,
+    /// while it is being parsed. Changes to the file are visible through the map,
+    /// and reading pages removed by truncation aborts the process.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn load_mmap_path(file_path: PathBuf) -> Result<C3d, C3dParseError> {
+        let c3d = C3d::new();
+        let (_, file) = c3d.open_file(file_path)?;
+        // SAFETY: the caller guarantees that the file is not changed while it is mapped.
+        let bytes = unsafe { memmap2::Mmap::map(&file) }.map_err(C3dParseError::ReadError)?;
+        C3d::from_bytes(&bytes)
     }
 
     /// Parses a C3D file from a byte slice.
     pub fn from_bytes(bytes: &[u8]) -> Result<C3d, C3dParseError> {
         let (c3d, header_bytes, parameter_bytes, data_start_block_index) =
             C3d::new().parse_basic_info_from_bytes(bytes)?;
+        let mut context = ParseContext::default();
         Ok(c3d
             .parse_header(&header_bytes)?
-            .parse_parameters(&header_bytes, &parameter_bytes)?
-            .parse_data_from_bytes(bytes, data_start_block_index)?)
+            .parse_parameters(&header_bytes, &parameter_bytes, &mut context)?
+            .parse_data_from_bytes(bytes, data_start_block_index, &mut context)?)
+    }
+
+    /// Parses a C3D file from any source that implements `Read` and `Seek`,
+    /// such as an in-memory cursor or an entry in an archive.
+    /// The source is read from its current position.
+    pub fn from_reader<R: Read + Seek>(mut reader: R) -> Result<C3d, C3dParseError> {
+        let (c3d, header_bytes, parameter_bytes, _) = C3d::new().parse_basic_info(&mut reader)?;
+        let mut context = ParseContext::default();
+        c3d.parse_header(&header_bytes)?
+            .parse_parameters(&header_bytes, &parameter_bytes, &mut context)?
+            .parse_data(reader, &mut context)
+    }
+
+    /// Parses the parts of a C3D file selected by `options` from a file path string.
+    /// Only the requested frames are read from the file, and only the requested
+    /// markers and analog channels are decoded.
+    ///
+    /// # Examples
+    /// ```
+    /// use c3dio::prelude::*;
+    ///
+    /// let options = LoadOptions::new().frames(10..20).point_labels(&["LASI", "RASI"]);
+    /// let c3d = C3d::load_with("tests/data/short.c3d", &options).unwrap();
+    /// assert_eq!(c3d.points.size(), (10, 2));
+    /// assert_eq!(c3d.points.first_frame, 11);
+    /// ```
+    pub fn load_with(file_name: &str, options: &LoadOptions) -> Result<C3d, C3dParseError> {
+        C3d::load_path_with(PathBuf::from(file_name), options)
+    }
+
+    /// Parses the parts of a C3D file selected by `options` from a file path.
+    pub fn load_path_with(file_path: PathBuf, options: &LoadOptions) -> Result<C3d, C3dParseError> {
+        let c3d = C3d::new();
+        let (_, file) = c3d.open_file(file_path)?;
+        C3d::from_reader_with(BufReader::new(file), options)
+    }
+
+    /// Parses the parts of a C3D file selected by `options` from any source
+    /// that implements `Read` and `Seek`.
+    /// Like `from_reader`, the source is read from its current position.
+    pub fn from_reader_with<R: Read + Seek>(
+        reader: R,
+        options: &LoadOptions,
+    ) -> Result<C3d, C3dParseError> {
+        let (c3d, _) = C3d::from_reader_with_diagnostics(reader, options)?;
+        Ok(c3d)
+    }
+
+    /// Parses a C3D file like `load_with` and also returns a warning for every
+    /// correction that was applied to inconsistent or missing values in the file.
+    ///
+    /// # Examples
+    /// ```
+    /// use c3dio::prelude::*;
+    ///
+    /// let (c3d, warnings) =
+    ///     C3d::load_with_diagnostics("tests/data/short.c3d", &LoadOptions::new()).unwrap();
+    /// for warning in &warnings {
+    ///     println!("{}", warning);
+    /// }
+    /// ```
+    pub fn load_with_diagnostics(
+        file_name: &str,
+        options: &LoadOptions,
+    ) -> Result<(C3d, Vec<C3dWarning>), C3dParseError> {
+        C3d::load_path_with_diagnostics(PathBuf::from(file_name), options)
+    }
+
+    /// Parses a C3D file from a file path and returns the warnings, see `load_with_diagnostics`.
+    pub fn load_path_with_diagnostics(
+        file_path: PathBuf,
+        options: &LoadOptions,
+    ) -> Result<(C3d, Vec<C3dWarning>), C3dParseError> {
+        let c3d = C3d::new();
+        let (_, file) = c3d.open_file(file_path)?;
+        C3d::from_reader_with_diagnostics(BufReader::new(file), options)
+    }
+
+    /// Parses a C3D file from any source that implements `Read` and `Seek`
+    /// and returns the warnings, see `load_with_diagnostics`.
+    /// The source is read from its current position.
+    pub fn from_reader_with_diagnostics<R: Read + Seek>(
+        reader: R,
+        options: &LoadOptions,
+    ) -> Result<(C3d, Vec<C3dWarning>), C3dParseError> {
+        let mut context = ParseContext::new(options);
+        let c3d = C3dReader::with_context(reader, &mut context)?.load(options)?;
+        Ok((c3d, context.warnings))
     }
 
     /// Parses a C3D file with just the header data.
     pub fn load_header(file_name: PathBuf) -> Result<C3d, C3dParseError> {
         let c3d = C3d::new();
-        let (c3d, mut file) = c3d.open_file(file_name)?;
-        let (c3d, header_bytes, _, _) = c3d.parse_basic_info(&mut file)?;
-        Ok(c3d.parse_header(&header_bytes)?)
+        let (_, file) = c3d.open_file(file_name)?;
+        C3d::header_from_reader(file)
+    }
+
+    /// Parses just the header data from any source that implements `Read` and `Seek`.
+    pub fn header_from_reader<R: Read + Seek>(mut reader: R) -> Result<C3d, C3dParseError> {
+        let (c3d, header_bytes, _, _) = C3d::new().parse_basic_info(&mut reader)?;
+        c3d.parse_header(&header_bytes)
     }
 
     /// Parses a C3D file with just the header and parameter data.
@@ -135,49 +272,104 @@ impl C3d {
     /// The `Parameters` struct can be accessed via the `parameters` field.
     pub fn load_parameters(file_name: PathBuf) -> Result<C3d, C3dParseError> {
         let c3d = C3d::new();
-        let (c3d, mut file) = c3d.open_file(file_name)?;
-        let (c3d, header_bytes, parameter_bytes, _) = c3d.parse_basic_info(&mut file)?;
-        Ok(c3d
-            .parse_header(&header_bytes)?
-            .parse_parameters(&header_bytes, &parameter_bytes)?)
+        let (_, file) = c3d.open_file(file_name)?;
+        C3d::parameters_from_reader(file)
     }
 
-    pub fn new() -> C3d {
-        C3d::default()
+    /// Parses just the header and parameter data from any source that implements `Read` and `Seek`.
+    pub fn parameters_from_reader<R: Read + Seek>(mut reader: R) -> Result<C3d, C3dParseError> {
+        let (c3d, header_bytes, parameter_bytes, _) = C3d::new().parse_basic_info(&mut reader)?;
+        c3d.parse_header(&header_bytes)?.parse_parameters(
+            &header_bytes,
+            &parameter_bytes,
+            &mut ParseContext::default(),
+        )
     }
 
-    fn force_analog_data(&self, force_plate: usize, frame: usize) -> Option<[f32; 8]> {
-        if self.forces.len() <= force_plate {
-            return None;
-        }
-        let channels = self.forces[force_plate].channels;
-        let mut analog = [0f32; 8];
-        for i in 0..8 {
-            let channel_index = channels[i];
-            if channel_index == 0 {
-                analog[i] = 0.0;
-                continue;
-            }
-            if self.analog.cols() <= (channel_index - 1) as usize
-                || self.analog.rows() <= frame * self.analog.samples_per_channel_per_frame as usize
-            {
-                return None;
-            }
-            analog[i] = self.analog[frame * self.analog.samples_per_channel_per_frame as usize]
-                [(channel_index - 1) as usize] as f32;
-        }
-        Some(analog)
+    pub fn new() -> C3d {
+        C3d::default()
     }
 
+    /// The force of a force platform at the first analog sample of a frame.
     pub fn force(&self, force_plate: usize, frame: usize) -> Option<[f32; 3]> {
-        let analog = self.force_analog_data(force_plate, frame)?;
-        self.forces.force_from_analog(analog, force_plate)
+        let force = self.frame_wrench(force_plate, frame)?.force;
+        Some([force[0] as f32, force[1] as f32, force[2] as f32])
     }
 
+    /// The centre of pressure of a force platform at the first analog sample of a frame,
+    /// relative to the centre of its working surface.
     pub fn center_of_pressure(&self, force_plate: usize, frame: usize) -> Option<[f32; 2]> {
-        let analog = self.force_analog_data(force_plate, frame)?;
+        let center_of_pressure = self.frame_wrench(force_plate, frame)?.center_of_pressure;
+        Some([center_of_pressure[0] as f32, center_of_pressure[1] as f32])
+    }
+
+    /// The force, moment, centre of pressure and free moment of a force platform
+    /// at one analog sample.
+    pub fn wrench(&self, force_plate: usize, sample: usize) -> Option<Wrench> {
+        self.forces.wrench(&self.analog, force_plate, sample)
+    }
+
+    /// The wrench of a force platform at every analog sample.
+    pub fn wrenches(&self, force_plate: usize) -> Option<Vec<Wrench>> {
+        self.forces.wrenches(&self.analog, force_plate)
+    }
+
+    /// The force, centre of pressure and moment of a force platform in the laboratory
+    /// coordinate system for every point frame, aligned with the rows of `points`.
+    /// Returns `None` if the analog data does not cover exactly the point frames.
+    pub fn lab_wrenches(&self, force_plate: usize) -> Option<Vec<LabWrench>> {
         self.forces
-            .center_of_pressure_from_analog(analog, force_plate)
+            .lab_wrenches(&self.analog, force_plate, self.points.rows())
+    }
+
+    /// Removes the baseline of the force platform channels, averaged over the frames in
+    /// `FORCE_PLATFORM:ZERO` or the quiet window in `options`. Returns the offsets of
+    /// each plate channel, or `None` if the window does not contain any frames.
+    pub fn zero_force_platforms(&mut self, options: &ZeroOptions) -> Option<Vec<Vec<f64>>> {
+        self.forces.zero(&mut self.analog, options)
+    }
+
+    /// Adds the foot strikes and foot offs found from the vertical force of each force
+    /// platform to `events`, see `gait::force_events`. Returns the number of events added.
+    /// The existing events keep their order, see `Events::insert_at_time`.
+    pub fn detect_force_events(&mut self, options: &ForceEventOptions) -> usize {
+        self.add_detected_events(gait::force_events(self, options))
+    }
+
+    /// Adds the foot strikes and foot offs found from the heel, toe and sacrum markers
+    /// to `events`, see `gait::kinematic_events`. Returns the number of events added.
+    /// The existing events keep their order, see `Events::insert_at_time`.
+    pub fn detect_kinematic_events(&mut self, options: &KinematicEventOptions) -> usize {
+        self.add_detected_events(gait::kinematic_events(self, options))
+    }
+
+    /// Inserts `event` at the zero-based `row` of the point data, the frame number
+    /// `Points::first_frame + row`, see `Events::insert_at_frame`.
+    /// Returns the index of the event.
+    pub fn insert_event_at_row(&mut self, event: Event, row: usize) -> usize {
+        self.events.supports_events_labels = true;
+        self.events.insert_at_frame(
+            event,
+            self.points.first_frame as usize + row,
+            self.points.frame_rate,
+        )
+    }
+
+    fn add_detected_events(&mut self, detected: Vec<Event>) -> usize {
+        if !detected.is_empty() {
+            self.events.supports_events_labels = true;
+        }
+        let added = detected.len();
+        for event in detected {
+            let time = event.time;
+            self.events.insert_at_time(event, time);
+        }
+        added
+    }
+
+    fn frame_wrench(&self, force_plate: usize, frame: usize) -> Option<Wrench> {
+        let sample = frame * self.analog.samples_per_channel_per_frame as usize;
+        self.wrench(force_plate, sample)
     }
 
     fn open_file(self, file_path: PathBuf) -> Result<(C3d, File), C3dParseError> {
@@ -185,13 +377,13 @@ impl C3d {
         Ok((self, file))
     }
 
-    fn parse_basic_info(
+    pub(crate) fn parse_basic_info<R: Read + Seek>(
         mut self,
-        file: &mut File,
+        reader: &mut R,
     ) -> Result<(C3d, [u8; 512], Vec<u8>, usize), C3dParseError> {
-        let header_bytes = read_header_bytes(file)?;
+        let header_bytes = read_header_bytes(reader)?;
         let (processor, parameter_bytes, data_start_block_index) =
-            read_parameter_bytes(file, &header_bytes)?;
+            read_parameter_bytes(reader, &header_bytes)?;
         self.processor = processor;
         Ok((self, header_bytes, parameter_bytes, data_start_block_index))
     }
@@ -206,6 +398,9 @@ impl C3d {
         let header_bytes: [u8; 512] = bytes[0..512].try_into().unwrap();
 
         let parameter_start_block_index = header_bytes[0] as usize;
+        if parameter_start_block_index < 2 {
+            return Err(C3dParseError::InvalidParameterStartBlock(None).at(ErrorLocation::new(0)));
+        }
 
         if bytes.len() < 512 * (parameter_start_block_index) {
             return Err(C3dParseError::InsufficientBlocks("parameter".to_string()));
@@ -216,9 +411,13 @@ impl C3d {
             .unwrap();
 
         self.processor =
-            Processor::from_parameter_start_block(parameter_start_block.try_into().unwrap())?;
+            Processor::from_parameter_start_block(parameter_start_block.try_into().unwrap())
+                .map_err(|e| e.at(ErrorLocation::new(blocks_to_skip + 3)))?;
         let data_start_block_index =
             self.processor.u16([header_bytes[16], header_bytes[17]]) as usize;
+        if data_start_block_index <= parameter_start_block_index {
+            return Err(C3dParseError::InvalidDataStartBlock(None).at(ErrorLocation::new(16)));
+        }
 
         if bytes.len() < 512 * (data_start_block_index) {
             return Err(C3dParseError::InsufficientBlocks("data".to_string()));
@@ -231,106 +430,153 @@ impl C3d {
         Ok((self, header_bytes, parameter_bytes, data_start_block_index))
     }
 
-    fn parse_header(mut self, header_bytes: &[u8; 512]) -> Result<C3d, C3dParseError> {
+    pub(crate) fn parse_header(mut self, header_bytes: &[u8; 512]) -> Result<C3d, C3dParseError> {
         self.points = Points::parse_header(&header_bytes, &self.processor);
         self.analog = Analog::parse_header(&header_bytes, &self.processor);
         self.header_bytes = header_bytes.clone();
         Ok(self)
     }
 
-    fn parse_parameters(
+    pub(crate) fn parse_parameters(
         mut self,
         header_bytes: &[u8; 512],
         parameter_bytes: &Vec<u8>,
+        context: &mut ParseContext,
     ) -> Result<C3d, C3dParseError> {
-        self.parameters = Parameters::parse_parameter_blocks(parameter_bytes, &self.processor)?;
+        context.parameter_start = 512 * (header_bytes[0] as usize).saturating_sub(1);
+        self.parameters = Parameters::parse_parameter_blocks(parameter_bytes, &self.processor)
+            .map_err(|e| e.offset_by(context.parameter_start))?;
+        self.parameters.set_name_matching(context.name_matching);
         self.events = Events::from_header_and_parameters(
             &header_bytes,
             &mut self.parameters,
             &self.processor,
-        )?;
-        self.manufacturer = Manufacturer::from_parameters(&mut self.parameters)?;
-        self.seg = Seg::from_parameters(&mut self.parameters)?;
-        self.forces = ForcePlatforms::from_parameters(&mut self.parameters)?;
+            context,
+        )
+        .map_err(|e| context.locate(e, &self.parameters, &["EVENT", "EVENT_CONTEXT"]))?;
+        self.manufacturer = Manufacturer::from_parameters(&mut self.parameters)
+            .map_err(|e| context.locate(e, &self.parameters, &["MANUFACTURER"]))?;
+        self.seg = Seg::from_parameters(&mut self.parameters)
+            .map_err(|e| context.locate(e, &self.parameters, &["SEG"]))?;
+        self.forces = ForcePlatforms::from_parameters(&mut self.parameters, context)
+            .map_err(|e| context.locate(e, &self.parameters, &["FORCE_PLATFORM"]))?;
         Ok(self)
     }
 
-    fn parse_data(self, file: File) -> Result<C3d, C3dParseError> {
-        let data_bytes = read_data_bytes(file)?;
-        self.parse_data_bytes(data_bytes)
+    fn parse_data<R: Read>(
+        self,
+        reader: R,
+        context: &mut ParseContext,
+    ) -> Result<C3d, C3dParseError> {
+        let data_bytes = read_data_bytes(reader)?;
+        self.parse_data_bytes(&data_bytes, context)
     }
 
     fn parse_data_from_bytes(
         self,
         bytes: &[u8],
         data_start_block_index: usize,
+        context: &mut ParseContext,
     ) -> Result<C3d, C3dParseError> {
         let data_start_byte = 512 * (data_start_block_index - 1);
         if bytes.len() < data_start_byte {
             return Err(C3dParseError::InsufficientBlocks("data".to_string()));
         }
-        self.parse_data_bytes(bytes[data_start_byte..].to_vec())
+        self.parse_data_bytes(&bytes[data_start_byte..], context)
     }
 
-    fn parse_data_bytes(mut self, data_bytes: Vec<u8>) -> Result<C3d, C3dParseError> {
-        let (_, num_frames) = self.points.parse(
-            &data_bytes,
-            &mut self.parameters,
-            &self.processor,
-            self.analog.samples_per_frame,
-        )?;
-        self.analog.parse(
-            &data_bytes,
-            &mut self.parameters,
+    fn parse_data_bytes(
+        mut self,
+        data_bytes: &[u8],
+        context: &mut ParseContext,
+    ) -> Result<C3d, C3dParseError> {
+        // the analog parameters are parsed first, since the number of frames
+        // depends on the number of analog samples per frame
+        let analog_used = self
+            .analog
+            .parse_parameters(
+                &mut self.parameters,
+                &self.points.format,
+                self.points.frame_rate,
+                context,
+            )
+            .map_err(|e| context.locate(e, &self.parameters, &["ANALOG"]))?;
+        let (_, num_frames) = self
+            .points
+            .parse(
+                data_bytes,
+                &mut self.parameters,
+                &self.processor,
+                self.analog.samples_per_frame,
+                context,
+            )
+            .map_err(|e| context.locate(e, &self.parameters, &["POINT", "TRIAL"]))?;
+        self.analog.parse_analog(
+            data_bytes,
             &self.processor,
             num_frames,
             &self.points.format,
-            self.points.cols(),
+            self.points.markers_per_frame(),
+            analog_used,
         )?;
+        self.record_parsed_parameters(self.num_frames());
         Ok(self)
     }
 
+    fn num_frames(&self) -> usize {
+        match self.points.rows() == 0
+            && self.analog.rows() > 0
+            && self.analog.samples_per_channel_per_frame != 0
+        {
+            true => self.analog.rows() / self.analog.samples_per_channel_per_frame as usize,
+            false => self.points.rows(),
+        }
+    }
+
     /// A function to write a C3D header to bytes.
-    fn write_header(&self, data_start_block_index: u16) -> Result<[u8; 512], C3dWriteError> {
+    pub(crate) fn write_header(
+        &self,
+        data_start_block_index: u16,
+        processor: Processor,
+    ) -> Result<[u8; 512], C3dWriteError> {
         let mut header_bytes = [0u8; 512];
         header_bytes[0] = 2;
         header_bytes[1] = 80;
-        let temp = self.processor.u16_to_bytes(self.points.cols() as u16);
+        let temp = processor.u16_to_bytes(self.points.cols() as u16);
         header_bytes[2] = temp[0];
         header_bytes[3] = temp[1];
-        let temp = self
-            .processor
-            .u16_to_bytes(self.analog.samples_per_frame as u16);
+        let temp = processor.u16_to_bytes(self.analog.samples_per_frame as u16);
         header_bytes[4] = temp[0];
         header_bytes[5] = temp[1];
-        let temp = self.processor.u16_to_bytes(self.points.first_frame);
+        // frame numbers beyond 16 bits are written to TRIAL:ACTUAL_START_FIELD and
+        // TRIAL:ACTUAL_END_FIELD, the header range is shifted down to end at 65535 so that
+        // it still spans POINT:FRAMES, or starts at the first frame when the count is too long
+        let (first_frame, last_frame) =
+            header_frame_range(self.points.first_frame, self.points.last_frame);
+        let temp = processor.u16_to_bytes(first_frame);
         header_bytes[6] = temp[0];
         header_bytes[7] = temp[1];
-        let temp = self.processor.u16_to_bytes(self.points.last_frame);
+        let temp = processor.u16_to_bytes(last_frame);
         header_bytes[8] = temp[0];
         header_bytes[9] = temp[1];
-        let temp = self
-            .processor
-            .u16_to_bytes(self.points.max_interpolation_gap);
+        let temp = processor.u16_to_bytes(self.points.max_interpolation_gap);
         header_bytes[10] = temp[0];
         header_bytes[11] = temp[1];
         let temp = match self.points.format {
-            DataFormat::Float => self.processor.f32_to_bytes(-self.points.scale_factor),
-            DataFormat::Integer => self.processor.f32_to_bytes(self.points.scale_factor),
+            DataFormat::Float => processor.f32_to_bytes(-self.points.scale_factor),
+            DataFormat::Integer => processor.f32_to_bytes(self.points.scale_factor),
         };
         header_bytes[12] = temp[0];
         header_bytes[13] = temp[1];
         header_bytes[14] = temp[2];
         header_bytes[15] = temp[3];
-        let temp = self.processor.u16_to_bytes(data_start_block_index);
+        let temp = processor.u16_to_bytes(data_start_block_index);
         header_bytes[16] = temp[0];
         header_bytes[17] = temp[1];
-        let temp = self
-            .processor
-            .u16_to_bytes(self.analog.samples_per_channel_per_frame);
+        let temp = processor.u16_to_bytes(self.analog.samples_per_channel_per_frame);
         header_bytes[18] = temp[0];
         header_bytes[19] = temp[1];
-        let temp = self.processor.f32_to_bytes(self.points.frame_rate as f32);
+        let temp = processor.f32_to_bytes(self.points.frame_rate as f32);
         header_bytes[20] = temp[0];
         header_bytes[21] = temp[1];
         header_bytes[22] = temp[2];
@@ -342,19 +588,21 @@ impl C3d {
             header_bytes[25 + i * 2] = self.header_bytes[25 + i * 2];
         }
         let temp = match self.events.supports_events_labels {
-            true => self.processor.u16_to_bytes(12345),
-            false => self.processor.u16_to_bytes(0),
+            true => processor.u16_to_bytes(12345),
+            false => processor.u16_to_bytes(0),
         };
         match self.events.supports_events_labels {
             true => {
                 header_bytes[298] = temp[0];
                 header_bytes[299] = temp[1];
-                let temp = self.processor.u16_to_bytes(self.events.len() as u16);
+                // the header holds the first 18 events, all of them are in the EVENT group
+                let num_header_events = self.events.len().min(18);
+                let temp = processor.u16_to_bytes(num_header_events as u16);
                 header_bytes[300] = temp[0];
                 header_bytes[301] = temp[1];
 
-                for i in 0..self.events.len() {
-                    let temp = self.processor.f32_to_bytes(self.events[i].time);
+                for i in 0..num_header_events {
+                    let temp = processor.f32_to_bytes(self.events[i].time);
                     header_bytes[304 + i * 4] = temp[0];
                     header_bytes[305 + i * 4] = temp[1];
                     header_bytes[306 + i * 4] = temp[2];
@@ -380,69 +628,148 @@ impl C3d {
         Ok(header_bytes)
     }
 
-    fn write_parameter_blocks(&self) -> Result<Vec<u8>, C3dWriteError> {
+    pub(crate) fn write_parameter_blocks(
+        &self,
+        num_frames: usize,
+        processor: Processor,
+    ) -> Result<Vec<u8>, C3dWriteError> {
+        let (section_header, min_blocks) = self.parameters.section_header();
         let mut parameter_bytes: Vec<u8> = Vec::new();
-        parameter_bytes.append(vec![0, 0, 0].as_mut());
-        parameter_bytes.push(match self.processor {
+        parameter_bytes.extend(section_header);
+        parameter_bytes.push(0);
+        parameter_bytes.push(match processor {
             Processor::Intel => 0x54,
             Processor::Dec => 0x55,
             Processor::SgiMips => 0x56,
         });
-        let (group_bytes, group_names_to_ids) = self.parameters.write_groups(&self.processor)?;
-        parameter_bytes.extend(group_bytes);
-
-        let num_frames = match self.points.rows() == 0
-            && self.analog.rows() > 0
-            && self.analog.samples_per_channel_per_frame != 0
-        {
-            true => self.analog.rows() / self.analog.samples_per_channel_per_frame as usize,
-            false => self.points.rows(),
-        };
-        parameter_bytes.extend(self.points.write_parameters(
-            &self.processor,
+        let group_names_to_ids = self.parameters.group_ids();
+        if let Some(parsed) = &self.parsed {
+            self.parameters.record_parsed_typed(
+                &parsed.c3d.processor,
+                &group_names_to_ids,
+                || {
+                    parsed.c3d.write_typed_parameters(
+                        parsed.num_frames,
+                        parsed.c3d.processor,
+                        &group_names_to_ids,
+                    )
+                },
+            )?;
+        }
+        let typed_records =
+            self.write_typed_parameters(num_frames, processor, &group_names_to_ids)?;
+        parameter_bytes.extend(self.parameters.write_records(
+            &processor,
             &group_names_to_ids,
+            &typed_records,
+        )?);
+
+        let num_blocks = parameter_bytes.len().div_ceil(512).max(min_blocks);
+        parameter_bytes[2] = num_blocks as u8;
+        Ok(parameter_bytes)
+    }
+
+    /// The parameter records written by the typed structs such as `Points`.
+    fn write_typed_parameters(
+        &self,
+        num_frames: usize,
+        processor: Processor,
+        group_names_to_ids: &HashMap<String, usize>,
+    ) -> Result<Vec<u8>, C3dWriteError> {
+        let mut typed_records = Vec::new();
+        typed_records.extend(self.points.write_parameters(
+            &processor,
+            group_names_to_ids,
             num_frames,
         )?);
-        parameter_bytes.extend(
+        typed_records.extend(
             self.analog
-                .write_parameters(&self.processor, &group_names_to_ids)?,
-        );
-        parameter_bytes.extend(self.forces.write(&self.processor, &group_names_to_ids)?);
-        parameter_bytes.extend(self.events.write(&self.processor, &group_names_to_ids)?);
-        parameter_bytes.extend(
-            self.manufacturer
-                .write(&self.processor, &group_names_to_ids)?,
-        );
-        parameter_bytes.extend(self.seg.write(&self.processor, &group_names_to_ids)?);
-        parameter_bytes.extend(
-            self.parameters
-                .write_parameters(&self.processor, &group_names_to_ids)?,
+                .write_parameters(&processor, group_names_to_ids)?,
         );
+        typed_records.extend(self.forces.write(&processor, group_names_to_ids)?);
+        typed_records.extend(self.events.write(&processor, group_names_to_ids)?);
+        typed_records.extend(self.manufacturer.write(&processor, group_names_to_ids)?);
+        typed_records.extend(self.seg.write(&processor, group_names_to_ids)?);
+        Ok(typed_records)
+    }
 
-        let num_blocks = parameter_bytes.len() / 512 + 1;
-        parameter_bytes[2] = num_blocks as u8;
-        Ok(parameter_bytes)
+    /// Keeps the typed structs of the parsed file with `num_frames` frames, so that the
+    /// parameters they do not change are written as they were read.
+    /// The parameters are only encoded on the first write.
+    pub(crate) fn record_parsed_parameters(&mut self, num_frames: usize) {
+        // the typed structs only write the number of columns of the data
+        let points = std::mem::replace(&mut self.points.points, Grid::new(0, 0));
+        let analog = std::mem::replace(&mut self.analog.analog, Grid::new(0, 0));
+        let mut c3d = C3d {
+            processor: self.processor,
+            points: self.points.clone(),
+            analog: self.analog.clone(),
+            events: self.events.clone(),
+            manufacturer: self.manufacturer.clone(),
+            seg: self.seg.clone(),
+            forces: self.forces.clone(),
+            ..C3d::default()
+        };
+        c3d.points.points = Grid::new(1, points.cols());
+        c3d.analog.analog = Grid::new(1, analog.cols());
+        self.points.points = points;
+        self.analog.analog = analog;
+        self.parsed = Some(Box::new(ParsedTyped { c3d, num_frames }));
     }
 
-    fn write_data(&self) -> Result<Vec<u8>, C3dWriteError> {
+    fn write_data(
+        &self,
+        num_frames: usize,
+        processor: Processor,
+    ) -> Result<Vec<u8>, C3dWriteError> {
         let mut data_bytes = Vec::new();
-        let num_frames = match self.points.rows() == 0
-            && self.analog.rows() > 0
-            && self.analog.samples_per_channel_per_frame != 0
-        {
-            true => self.analog.rows() / self.analog.samples_per_channel_per_frame as usize,
-            false => self.points.rows(),
-        };
         for i in 0..num_frames {
-            data_bytes.extend(self.points.write_frame(i, &self.processor));
+            data_bytes.extend(self.points.write_frame(i, &processor)?);
             data_bytes.extend(
                 self.analog
-                    .write_frame(i, &self.processor, &self.points.format),
+                    .write_frame(i, &processor, &self.points.format)?,
             );
         }
         Ok(data_bytes)
     }
 
+    /// Converts the point and analog data to the integer format.
+    /// `POINT:SCALE` is set to the largest marker coordinate divided by 32000,
+    /// each `ANALOG:SCALE` to the largest sample of its channel divided by 32000,
+    /// and the data is rounded to the values that will be written.
+    ///
+    /// # Examples
+    /// ```
+    /// use c3dio::prelude::*;
+    /// use c3dio::DataFormat;
+    ///
+    /// let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    /// let loss = c3d.convert_to_integer();
+    /// assert!(loss.points < c3d.points.scale_factor);
+    /// assert_eq!(c3d.points.format, DataFormat::Integer);
+    /// ```
+    pub fn convert_to_integer(&mut self) -> PrecisionLoss {
+        PrecisionLoss {
+            points: self.points.convert_to_integer(),
+            analog: self.analog.convert_to_integer(),
+        }
+    }
+
+    /// Converts the point and analog data to the floating-point format, the inverse of
+    /// `convert_to_integer`. `POINT:SCALE` is kept to scale the residuals and is written
+    /// negative in the header, the analog samples are stored in engineering units with
+    /// `ANALOG:SCALE` and `ANALOG:GEN_SCALE` set to 1 and the offsets set to 0.
+    pub fn convert_to_float(&mut self) {
+        self.points.convert_to_float();
+        self.analog.convert_to_float();
+    }
+
+    /// The processor type the file was read with.
+    /// Files are written with it unless `WriteOptions::processor` is set.
+    pub fn processor(&self) -> Processor {
+        self.processor
+    }
+
     pub fn write(&self, file_name: &str) -> Result<&Self, C3dWriteError> {
         self.write_path(PathBuf::from(file_name))
     }
@@ -454,68 +781,135 @@ impl C3d {
     /// If the file path is not writable, an error will be returned.
     /// If the file path is not a valid UTF-8 string, an error will be returned.
     pub fn write_path(&self, file_name: PathBuf) -> Result<&Self, C3dWriteError> {
-        // Check if the file path is a directory.
-        if file_name.is_dir() {
-            return Err(C3dWriteError::InvalidFilePath(file_name));
-        }
-        // Check if file_name ends with ".c3d", ".C3D", ".c3D", or ".C3d".
-        let extension = file_name
-            .extension()
-            .unwrap()
-            .to_string_lossy()
-            .to_lowercase();
-        if !extension.eq("c3d") {
-            return Err(C3dWriteError::InvalidFileExtension(
-                file_name.to_string_lossy().to_string(),
-            ));
-        }
+        self.write_path_with(file_name, &WriteOptions::default())
+    }
+
+    /// Writes the C3D file to a file path string as set by `options`, see `WriteOptions`.
+    pub fn write_with(
+        &self,
+        file_name: &str,
+        options: &WriteOptions,
+    ) -> Result<&Self, C3dWriteError> {
+        self.write_path_with(PathBuf::from(file_name), options)
+    }
+
+    /// Writes the C3D file to a file path as set by `options`, see `write_path`.
+    pub fn write_path_with(
+        &self,
+        file_name: PathBuf,
+        options: &WriteOptions,
+    ) -> Result<&Self, C3dWriteError> {
+        validate_file_path(&file_name)?;
         let mut file = File::create(file_name.clone())
             .map_err(|e| C3dWriteError::WriteError(file_name.clone(), e))?;
-        let mut parameter_bytes = self.write_parameter_blocks()?;
-        if parameter_bytes.len() % 512 != 0 {
-            // add padding
-            let padding = 512 - parameter_bytes.len() % 512;
-            parameter_bytes.extend(vec![0u8; padding]);
-        }
-        let data_start_block_index = 2 + parameter_bytes.len() / 512;
-        let header_bytes = self.write_header(data_start_block_index as u16)?;
-        let data_bytes = self.write_data()?;
-
-        file.write_all(&header_bytes)
-            .map_err(|e| C3dWriteError::WriteHeaderError(e))?;
-        file.write_all(&parameter_bytes)
-            .map_err(|e| C3dWriteError::WriteParametersError(e))?;
-        file.write_all(&data_bytes)
-            .map_err(|e| C3dWriteError::WriteDataError(e))?;
+        self.write_to_with(&mut file, options)?;
         file.sync_all()
             .map_err(|e| C3dWriteError::WriteError(file_name.clone(), e))?;
         Ok(self)
     }
+
+    /// Writes the C3D file to any sink that implements `Write`,
+    /// such as a network stream or an entry in an archive.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<&Self, C3dWriteError> {
+        self.write_to_with(writer, &WriteOptions::default())
+    }
+
+    /// Writes the C3D file to any sink that implements `Write` as set by `options`.
+    pub fn write_to_with<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+    ) -> Result<&Self, C3dWriteError> {
+        let processor = options.processor.unwrap_or(self.processor);
+        let num_frames = self.num_frames();
+        let mut parameter_bytes = self.write_parameter_blocks(num_frames, processor)?;
+        // pad the parameter section to the number of blocks it declares
+        parameter_bytes.resize(parameter_bytes[2] as usize * 512, 0);
+        let data_start_block_index = 2 + parameter_bytes.len() / 512;
+        let header_bytes = self.write_header(data_start_block_index as u16, processor)?;
+        let data_bytes = self.write_data(num_frames, processor)?;
+
+        writer
+            .write_all(&header_bytes)
+            .map_err(C3dWriteError::WriteHeaderError)?;
+        writer
+            .write_all(&parameter_bytes)
+            .map_err(C3dWriteError::WriteParametersError)?;
+        writer
+            .write_all(&data_bytes)
+            .map_err(C3dWriteError::WriteDataError)?;
+        Ok(self)
+    }
+
+    /// Writes the C3D file to a byte vector in memory.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, C3dWriteError> {
+        self.to_bytes_with(&WriteOptions::default())
+    }
+
+    /// Writes the C3D file to a byte vector in memory as set by `options`.
+    pub fn to_bytes_with(&self, options: &WriteOptions) -> Result<Vec<u8>, C3dWriteError> {
+        let mut bytes = Vec::new();
+        self.write_to_with(&mut bytes, options)?;
+        Ok(bytes)
+    }
 }
 
-fn read_header_bytes(file: &mut File) -> Result<[u8; 512], C3dParseError> {
+/// Checks that a file path can be used to write a C3D file.
+pub(crate) fn validate_file_path(file_name: &Path) -> Result<(), C3dWriteError> {
+    // Check if the file path is a directory.
+    if file_name.is_dir() {
+        return Err(C3dWriteError::InvalidFilePath(file_name.to_path_buf()));
+    }
+    // Check if file_name ends with ".c3d", ".C3D", ".c3D", or ".C3d".
+    let extension = file_name
+        .extension()
+        .unwrap()
+        .to_string_lossy()
+        .to_lowercase();
+    if !extension.eq("c3d") {
+        return Err(C3dWriteError::InvalidFileExtension(
+            file_name.to_string_lossy().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn read_header_bytes<R: Read>(reader: &mut R) -> Result<[u8; 512], C3dParseError> {
     let mut header_bytes = [0u8; 512];
-    file.read_exact(&mut header_bytes)
+    reader
+        .read_exact(&mut header_bytes)
         .map_err(|e| C3dParseError::ReadError(e))?;
     Ok(header_bytes)
 }
 
-fn read_parameter_bytes(
-    file: &mut File,
+fn read_parameter_bytes<R: Read + Seek>(
+    reader: &mut R,
     header_bytes: &[u8; 512],
 ) -> Result<(Processor, Vec<u8>, usize), C3dParseError> {
     let parameter_start_block_index = header_bytes[0] as usize;
+    if parameter_start_block_index < 2 {
+        return Err(C3dParseError::InvalidParameterStartBlock(None).at(ErrorLocation::new(0)));
+    }
 
     let blocks_to_skip = parameter_start_block_index - 2;
-    file.seek(SeekFrom::Current((512 * blocks_to_skip) as i64))
+    reader
+        .seek(SeekFrom::Current((512 * blocks_to_skip) as i64))
         .map_err(|e| C3dParseError::ReadError(e))?;
 
     let mut parameter_start_block = [0u8; 512];
-    file.read_exact(&mut parameter_start_block)
+    reader
+        .read_exact(&mut parameter_start_block)
         .map_err(|e| C3dParseError::ReadError(e))?;
 
-    let processor = Processor::from_parameter_start_block(parameter_start_block)?;
+    let processor = Processor::from_parameter_start_block(parameter_start_block).map_err(|e| {
+        e.at(ErrorLocation::new(
+            512 * (parameter_start_block_index - 1) + 3,
+        ))
+    })?;
     let data_start_block_index = processor.u16([header_bytes[16], header_bytes[17]]) as usize;
+    if data_start_block_index <= parameter_start_block_index {
+        return Err(C3dParseError::InvalidDataStartBlock(None).at(ErrorLocation::new(16)));
+    }
 
     let mut parameter_bytes_tail =
         Vec::with_capacity((data_start_block_index - parameter_start_block_index - 1) * 512)
@@ -523,7 +917,8 @@ fn read_parameter_bytes(
 
     for _ in 0..(data_start_block_index - parameter_start_block_index - 1) {
         let mut block = [0u8; 512];
-        file.read_exact(&mut block)
+        reader
+            .read_exact(&mut block)
             .map_err(|e| C3dParseError::ReadError(e))?;
         parameter_bytes_tail.extend(block.iter());
     }
@@ -537,10 +932,28 @@ fn read_parameter_bytes(
     Ok((processor, parameter_bytes, data_start_block_index))
 }
 
-fn read_data_bytes(mut file: File) -> Result<Vec<u8>, C3dParseError> {
+fn read_data_bytes<R: Read>(mut reader: R) -> Result<Vec<u8>, C3dParseError> {
     let mut data: Vec<u8> = Vec::new();
 
-    file.read_to_end(&mut data)
+    reader
+        .read_to_end(&mut data)
         .map_err(|e| C3dParseError::ReadError(e))?;
     Ok(data)
 }
+
+/// Header first and last frame numbers for a recording, limited to 16 bits while keeping the
+/// range as long as the number of frames whenever that fits.
+fn header_frame_range(first_frame: u32, last_frame: u32) -> (u16, u16) {
+    // an empty recording ends the frame before it starts
+    if first_frame.checked_sub(1) == Some(last_frame) {
+        let first_frame = first_frame.min(u16::MAX as u32);
+        return (first_frame as u16, (first_frame - 1) as u16);
+    }
+    let num_frames = last_frame.saturating_sub(first_frame) + 1;
+    let last_frame = last_frame.max(first_frame).min(u16::MAX as u32);
+    let first_frame = match num_frames > u16::MAX as u32 {
+        true => first_frame.min(u16::MAX as u32),
+        false => (last_frame + 1).saturating_sub(num_frames),
+    };
+    (first_frame as u16, last_frame as u16)
+}
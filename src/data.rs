@@ -12,6 +12,16 @@ pub enum DataFormat {
     Integer,
 }
 
+/// The largest rounding errors of a conversion to the integer format,
+/// in the units of the data.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PrecisionLoss {
+    /// The largest change of a marker coordinate.
+    pub points: f32,
+    /// The largest change of a sample, per analog channel.
+    pub analog: Vec<f64>,
+}
+
 pub(crate) fn get_point_bytes_per_frame(point_format: &DataFormat, points_per_frame: usize) -> usize {
     let bytes_per_point = match point_format {
         DataFormat::Float => 16,
@@ -33,6 +43,14 @@ pub(crate) fn get_analog_bytes_per_frame(
     Ok(bytes_per_analog_point * analog_samples_per_frame as usize)
 }
 
+/// Collects the values at the given indices, skipping indices that are out of range.
+pub(crate) fn retain_indices<T: Clone>(values: &[T], indices: &[usize]) -> Vec<T> {
+    indices
+        .iter()
+        .filter_map(|index| values.get(*index).cloned())
+        .collect()
+}
+
 /// MarkerPoint contains both the points and residuals for a marker.
 /// The residuals are the average distance between the marker and the reconstructed point.
 /// Cameras is a bitfield of which cameras saw the marker.
@@ -1,7 +1,8 @@
 //! Includes event information from the C3D file header and parameter section.
-use crate::parameters::{Parameter, ParameterData, Parameters};
+use crate::options::ParseContext;
+use crate::parameters::{continuation_name, write_continued, Parameter, ParameterData, Parameters};
 use crate::processor::Processor;
-use crate::{C3dWriteError, C3dParseError};
+use crate::{C3dParseError, C3dWarningKind, C3dWriteError};
 use grid::Grid;
 use std::collections::HashMap;
 /// # Events
@@ -10,11 +11,8 @@ use std::collections::HashMap;
 /// The label is a 4-character string that can be used to identify the event.
 /// The label is optional, and if it is not present, the event is still marked
 /// with a time point.
-/// The events are stored in the C3D file header.
-
-/// The `Events` struct contains the events from the C3D file header.
-/// Event information can be included in the parameter section of the C3D file
-/// as well, that information is stored in the `Parameter` struct.
+/// The header holds the time, label and display flag of the first 18 events,
+/// the `EVENT` group in the parameter section can hold any number of events.
 use std::ops::{Deref, DerefMut};
 
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -32,22 +30,22 @@ impl EventContext {
     }
 
     pub(crate) fn from_parameters(parameters: &mut Parameters) -> Result<Self, C3dParseError> {
-        let used = parameters.remove("EVENT_CONTEXT", "USED");
+        let used = parameters.take("EVENT_CONTEXT", "USED");
         let used = match used {
             Some(parameter) => Some(parameter.as_ref().try_into()?),
             _ => None,
         };
-        let icon_ids = parameters.remove("EVENT_CONTEXT", "ICON_IDS");
+        let icon_ids = parameters.take("EVENT_CONTEXT", "ICON_IDS");
         let icon_ids = match icon_ids {
             Some(parameter) => Some(parameter.as_ref().try_into()?),
             _ => None,
         };
-        let labels = parameters.remove("EVENT_CONTEXT", "LABELS");
+        let labels = parameters.take("EVENT_CONTEXT", "LABELS");
         let labels = match labels {
             Some(parameter) => Some(parameter.as_ref().try_into()?),
             _ => None,
         };
-        let descriptions = parameters.remove("EVENT_CONTEXT", "DESCRIPTIONS");
+        let descriptions = parameters.take("EVENT_CONTEXT", "DESCRIPTIONS");
         let descriptions = match descriptions {
             Some(parameter) => Some(parameter.as_ref().try_into()?),
             _ => None,
@@ -62,17 +60,34 @@ impl EventContext {
     }
 }
 
+/// Event times are measured from frame 1, so frame `n` occurs at `(n - 1) / frame_rate` seconds.
+pub(crate) fn time_of_frame(frame: usize, frame_rate: f32) -> f32 {
+    (frame as f32 - 1.0) / frame_rate
+}
+
+/// The frame number closest to `time`, the inverse of `time_of_frame`.
+pub(crate) fn frame_of_time(time: f32, frame_rate: f32) -> usize {
+    (time * frame_rate).round().max(0.0) as usize + 1
+}
+
 fn get_colour_array(
     parameters: &mut Parameters,
     group_name: &str,
     parameter_name: &str,
 ) -> Option<Vec<[u8; 3]>> {
-    let parameter = parameters.remove(group_name, parameter_name)?;
+    // colours stored with another type stay in the parameter map untouched
+    if !matches!(
+        parameters.get(group_name, parameter_name)?.data,
+        ParameterData::Byte(_)
+    ) {
+        return None;
+    }
+    let parameter = parameters.take(group_name, parameter_name)?;
     match &parameter.data {
         ParameterData::Byte(data) => {
             if parameter.dimensions.len() == 2 {
                 let mut colours = Vec::new();
-                for row in 0..data.len() % 3 {
+                for row in 0..data.len() / 3 {
                     let mut colour = [0; 3];
                     colour[0] = data[row * 3];
                     colour[1] = data[row * 3 + 1];
@@ -88,12 +103,14 @@ fn get_colour_array(
     }
 }
 
-/// The `Events` struct contains the events from the C3D file header.
+/// The `Events` struct contains the events from the C3D file header and the `EVENT` group.
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct Events {
     pub supports_events_labels: bool,
     events: Vec<Event>,
     event_context: EventContext,
+    /// Whether the file had an `EVENT` group.
+    event_group: bool,
 }
 
 impl ToString for Events {
@@ -156,6 +173,22 @@ impl Event {
     pub fn new() -> Event {
         Event::default()
     }
+
+    /// The frame number closest to the event, frame 1 occurs at 0 seconds.
+    pub fn frame(&self, frame_rate: f32) -> usize {
+        frame_of_time(self.time, frame_rate)
+    }
+
+    /// The time as the `[minutes, seconds]` pair stored in `EVENT:TIMES`.
+    pub fn minutes_seconds(&self) -> [f32; 2] {
+        let minutes = (self.time / 60.0).floor();
+        [minutes, self.time - minutes * 60.0]
+    }
+
+    /// Sets the time from a `[minutes, seconds]` pair as stored in `EVENT:TIMES`.
+    pub fn set_minutes_seconds(&mut self, minutes_seconds: [f32; 2]) {
+        self.time = minutes_seconds[0] * 60.0 + minutes_seconds[1];
+    }
 }
 
 impl Events {
@@ -164,15 +197,13 @@ impl Events {
         Events::default()
     }
 
-    /// Returns the number of events in the C3D file header.
-    /// The maximum number of events is 18.
+    /// Returns the number of events, only the first 18 are stored in the header.
     pub fn num_events(&self) -> usize {
         self.events.len()
     }
 
     /// Returns the event at the specified index.
     /// The index must be less than the number of events.
-    /// The maximum number of events is 18.
     pub fn event(&self, index: usize) -> Option<&Event> {
         if index < self.events.len() {
             Some(&self.events[index])
@@ -181,38 +212,221 @@ impl Events {
         }
     }
 
+    /// The contexts the events can refer to, from the `EVENT_CONTEXT` group.
+    pub fn event_context(&self) -> &EventContext {
+        &self.event_context
+    }
+
+    /// Inserts `event` at `time` after the events that occur at or before it, and adds
+    /// its context to the `EVENT_CONTEXT` lists if it is not there yet.
+    /// Returns the index of the event.
+    /// The events are expected to be sorted by time, see `sort_by_time`.
+    pub fn insert_at_time(&mut self, mut event: Event, time: f32) -> usize {
+        event.time = time;
+        self.add_context(&event.context);
+        let index = self.events.partition_point(|other| other.time <= time);
+        self.events.insert(index, event);
+        index
+    }
+
+    /// Inserts `event` at the frame number `frame` like `insert_at_time`.
+    /// Frame numbers count from 1, the first frame of the data is `Points::first_frame`.
+    /// Frame 0 is invalid, it would be placed one frame before the start of the recording.
+    ///
+    /// # Examples
+    /// ```
+    /// use c3dio::prelude::*;
+    /// use c3dio::Event;
+    ///
+    /// let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    /// let mut event = Event::new();
+    /// event.label = "Foot Strike".to_string();
+    /// event.context = "Left".to_string();
+    /// let index = c3d.events.insert_at_frame(event, 31, c3d.points.frame_rate);
+    /// assert_eq!(c3d.events[index].frame(c3d.points.frame_rate), 31);
+    /// ```
+    pub fn insert_at_frame(&mut self, event: Event, frame: usize, frame_rate: f32) -> usize {
+        self.insert_at_time(event, time_of_frame(frame, frame_rate))
+    }
+
+    /// Removes the events for which `predicate` returns true and returns them in order.
+    /// Their contexts stay in the `EVENT_CONTEXT` lists, see `remove_context`.
+    pub fn remove_where<F: FnMut(&Event) -> bool>(&mut self, mut predicate: F) -> Vec<Event> {
+        let (removed, kept) = self.events.drain(..).partition(|event| predicate(event));
+        self.events = kept;
+        removed
+    }
+
+    /// Sorts the events by time, events at the same time keep their order.
+    pub fn sort_by_time(&mut self) {
+        self.events.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+
+    /// The events sorted by time, events at the same time keep their order.
+    pub fn sorted(&self) -> Vec<&Event> {
+        let mut events = self.events.iter().collect::<Vec<&Event>>();
+        events.sort_by(|a, b| a.time.total_cmp(&b.time));
+        events
+    }
+
+    /// The events from `start` to `end` seconds, inclusive.
+    pub fn between(&self, start: f32, end: f32) -> Vec<&Event> {
+        self.events
+            .iter()
+            .filter(|event| event.time >= start && event.time <= end)
+            .collect()
+    }
+
+    /// The events from the frame number `first_frame` to `last_frame`, inclusive.
+    pub fn between_frames(
+        &self,
+        first_frame: usize,
+        last_frame: usize,
+        frame_rate: f32,
+    ) -> Vec<&Event> {
+        self.events
+            .iter()
+            .filter(|event| (first_frame..=last_frame).contains(&event.frame(frame_rate)))
+            .collect()
+    }
+
+    /// The event closest to `time`, the first one if several are equally close.
+    pub fn nearest(&self, time: f32) -> Option<&Event> {
+        self.events
+            .iter()
+            .min_by(|a, b| (a.time - time).abs().total_cmp(&(b.time - time).abs()))
+    }
+
+    /// The events with `label`, ignoring case and surrounding spaces.
+    pub fn with_label(&self, label: &str) -> Vec<&Event> {
+        self.events
+            .iter()
+            .filter(|event| same_name(&event.label, label))
+            .collect()
+    }
+
+    /// The events with `context`, ignoring case and surrounding spaces.
+    pub fn with_context(&self, context: &str) -> Vec<&Event> {
+        self.events
+            .iter()
+            .filter(|event| same_name(&event.context, context))
+            .collect()
+    }
+
+    /// The events with `subject`, ignoring case and surrounding spaces.
+    pub fn with_subject(&self, subject: &str) -> Vec<&Event> {
+        self.events
+            .iter()
+            .filter(|event| same_name(&event.subject, subject))
+            .collect()
+    }
+
+    /// Adds `context` to the `EVENT_CONTEXT` lists with an empty description, icon 0
+    /// and a black colour, unless it is empty or already there.
+    /// Returns the index of the context.
+    pub fn add_context(&mut self, context: &str) -> Option<usize> {
+        if context.trim().is_empty() {
+            return None;
+        }
+        let event_context = &mut self.event_context;
+        let labels = event_context.labels.get_or_insert_with(Vec::new);
+        if let Some(index) = labels.iter().position(|label| same_name(label, context)) {
+            return Some(index);
+        }
+        // the lists are parallel, so every list that is present gets an entry
+        labels.push(context.trim().to_string());
+        let index = labels.len() - 1;
+        if let Some(descriptions) = &mut event_context.descriptions {
+            descriptions.resize(index + 1, String::new());
+        }
+        if let Some(icon_ids) = &mut event_context.icon_ids {
+            icon_ids.resize(index + 1, 0);
+        }
+        if let Some(colours) = &mut event_context.colours {
+            colours.resize(index + 1, [0; 3]);
+        }
+        event_context.used = Some(index as i16 + 1);
+        Some(index)
+    }
+
+    /// Removes `context` from all `EVENT_CONTEXT` lists. The events keep their context.
+    /// Returns false if the context is not in the lists.
+    pub fn remove_context(&mut self, context: &str) -> bool {
+        let event_context = &mut self.event_context;
+        let index = match event_context.labels.as_ref().and_then(|labels| {
+            labels.iter().position(|label| same_name(label, context))
+        }) {
+            Some(index) => index,
+            None => return false,
+        };
+        remove_entry(&mut event_context.labels, index);
+        remove_entry(&mut event_context.descriptions, index);
+        remove_entry(&mut event_context.icon_ids, index);
+        remove_entry(&mut event_context.colours, index);
+        let used = event_context.labels.as_ref().map_or(0, |labels| labels.len());
+        event_context.used = Some(used as i16);
+        true
+    }
+
+    /// Keeps the events that occur between the first and last frame, inclusive.
+    pub(crate) fn retain_frames(&mut self, first_frame: usize, last_frame: usize, frame_rate: f32) {
+        if frame_rate <= 0.0 {
+            return;
+        }
+        let half_frame = 0.5 / frame_rate;
+        let start = time_of_frame(first_frame, frame_rate) - half_frame;
+        let end = time_of_frame(last_frame, frame_rate) + half_frame;
+        self.events
+            .retain(|event| event.time >= start && event.time < end);
+    }
+
     pub(crate) fn from_header_and_parameters(
         header_block: &[u8; 512],
         parameters: &mut Parameters,
         processor: &Processor,
+        context: &mut ParseContext,
     ) -> Result<Events, C3dParseError> {
         let supports_events_labels =
             processor.u16([header_block[298], header_block[299]]) == 0x3039;
-        let num_time_events =
-            get_num_time_events(header_block, parameters, &processor, supports_events_labels)?;
+        let event_group = parameters
+            .get_group("EVENT")
+            .is_some_and(|group| !group.is_empty());
+        let (num_time_events, num_header_events) = get_num_time_events(
+            header_block,
+            parameters,
+            &processor,
+            supports_events_labels,
+            context,
+        )?;
 
         let mut events = Vec::<Event>::with_capacity(num_time_events);
 
         let times = get_times_array(parameters)?;
-        let labels = get_labels_array(parameters)?;
-        let contexts = get_contexts_array(parameters);
-        let descriptions = get_descriptions_array(parameters);
-        let subjects = get_subjects_array(parameters);
-        let icon_ids = get_icon_ids_array(parameters);
-        let generic_flags = get_generic_flags_array(parameters);
-
-        // event times start at byte 306
+        let labels: Vec<String> = parameters
+            .take_continued("EVENT", "LABELS")?
+            .unwrap_or_default();
+        let contexts: Vec<String> = get_optional_array(parameters, "CONTEXTS", context);
+        let descriptions: Vec<String> = get_optional_array(parameters, "DESCRIPTIONS", context);
+        let subjects: Vec<String> = get_optional_array(parameters, "SUBJECTS", context);
+        let icon_ids: Vec<i16> = get_optional_array(parameters, "ICON_IDS", context);
+        let generic_flags: Vec<i16> = get_optional_array(parameters, "GENERIC_FLAGS", context);
+
+        // events after the ones in the header are only stored in the parameters
         for event_num in 0..num_time_events {
-            let id = get_event_id(event_num, header_block)?;
-            let label = match supports_events_labels && labels.len() > event_num {
-                true => labels[event_num].clone(),
-                false => "".to_string(),
+            let in_header = event_num < num_header_events;
+            let id = match in_header {
+                true => get_event_id(event_num, header_block),
+                false => [0x00 as char; 4],
             };
-            let display_flag = match supports_events_labels {
+            let label = match labels.get(event_num) {
+                Some(label) => label.clone(),
+                None => "".to_string(),
+            };
+            let display_flag = match in_header {
                 true => get_display_flag(event_num, header_block),
                 false => true,
             };
-            let time = verify_time(event_num, header_block, &times, processor)?;
+            let time = get_event_time(event_num, in_header, header_block, &times, processor);
             let context = get_event_context(event_num, &contexts);
             let description = get_event_description(event_num, &descriptions);
             let subject = get_event_subject(event_num, &subjects);
@@ -234,111 +448,34 @@ impl Events {
             supports_events_labels,
             events,
             event_context: EventContext::from_parameters(parameters)?,
+            event_group,
         })
     }
 
+    /// Whether the `EVENT` group is written. Files without one keep their events only
+    /// in the header as long as the header can hold all of them.
+    fn writes_event_group(&self) -> bool {
+        let fit_in_header = self.supports_events_labels
+            && self.events.len() <= 18
+            && self.events.iter().all(|event| {
+                event.label.is_empty()
+                    && event.context.is_empty()
+                    && event.description.is_empty()
+                    && event.subject.is_empty()
+                    && event.icon_id == 0
+                    && event.generic_flag == 0
+            });
+        self.event_group || !fit_in_header
+    }
+
     pub(crate) fn write(
         &self,
         processor: &Processor,
         group_names_to_ids: &HashMap<String, usize>,
     ) -> Result<Vec<u8>, C3dWriteError> {
         let mut bytes = Vec::new();
-        bytes.extend(Parameter::integer(self.events.len() as i16).write(
-            processor,
-            "USED".to_string(),
-            group_names_to_ids["EVENT"],
-            false,
-        )?);
-        let times = self
-            .events
-            .iter()
-            .map(|event| event.time)
-            .collect::<Vec<f32>>();
-        if times.len() > 0 {
-            bytes.extend(Parameter::floats(times)?.write(
-                processor,
-                "TIMES".to_string(),
-                group_names_to_ids["EVENT"],
-                false,
-            )?);
-        }
-        let labels = self
-            .events
-            .iter()
-            .map(|event| event.label.clone())
-            .collect::<Vec<String>>();
-        if labels.len() > 0 {
-            bytes.extend(Parameter::strings(labels).write(
-                processor,
-                "LABELS".to_string(),
-                group_names_to_ids["EVENT"],
-                false,
-            )?);
-        }
-        let contexts = self
-            .events
-            .iter()
-            .map(|event| event.context.clone())
-            .collect::<Vec<String>>();
-        if contexts.len() > 0 {
-            bytes.extend(Parameter::strings(contexts).write(
-                processor,
-                "CONTEXTS".to_string(),
-                group_names_to_ids["EVENT"],
-                false,
-            )?);
-        }
-        let descriptions = self
-            .events
-            .iter()
-            .map(|event| event.description.clone())
-            .collect::<Vec<String>>();
-        if descriptions.len() > 0 {
-            bytes.extend(Parameter::strings(descriptions).write(
-                processor,
-                "DESCRIPTIONS".to_string(),
-                group_names_to_ids["EVENT"],
-                false,
-            )?);
-        }
-        let subjects = self
-            .events
-            .iter()
-            .map(|event| event.subject.clone())
-            .collect::<Vec<String>>();
-        if subjects.len() > 0 {
-            bytes.extend(Parameter::strings(subjects).write(
-                processor,
-                "SUBJECTS".to_string(),
-                group_names_to_ids["EVENT"],
-                false,
-            )?);
-        }
-        let icon_ids = self
-            .events
-            .iter()
-            .map(|event| event.icon_id)
-            .collect::<Vec<i16>>();
-        if icon_ids.len() > 0 {
-            bytes.extend(Parameter::integers(icon_ids)?.write(
-                processor,
-                "ICON_IDS".to_string(),
-                group_names_to_ids["EVENT"],
-                false,
-            )?);
-        }
-        let generic_flags = self
-            .events
-            .iter()
-            .map(|event| event.generic_flag)
-            .collect::<Vec<i16>>();
-        if generic_flags.len() > 0 {
-            bytes.extend(Parameter::integers(generic_flags)?.write(
-                processor,
-                "GENERIC_FLAGS".to_string(),
-                group_names_to_ids["EVENT"],
-                false,
-            )?);
+        if self.writes_event_group() {
+            bytes.extend(self.write_event_group(processor, group_names_to_ids["EVENT"])?);
         }
         let event_context_used = match &self.event_context.used {
             Some(used) => *used,
@@ -406,147 +543,209 @@ impl Events {
         }
         Ok(bytes)
     }
+
+    fn write_event_group(
+        &self,
+        processor: &Processor,
+        group_id: usize,
+    ) -> Result<Vec<u8>, C3dWriteError> {
+        let mut bytes = Vec::new();
+        bytes.extend(Parameter::integer(self.events.len() as i16).write(
+            processor,
+            "USED".to_string(),
+            group_id,
+            false,
+        )?);
+        // EVENT:TIMES stores each time as minutes and seconds
+        let times = self
+            .events
+            .iter()
+            .map(|event| event.minutes_seconds())
+            .collect::<Vec<[f32; 2]>>();
+        if times.len() > 0 {
+            let to_parameter = |times: Vec<[f32; 2]>| {
+                let columns = times.len() as u8;
+                let mut times = Parameter::floats(times.concat())?;
+                times.dimensions = vec![2, columns];
+                Ok(times)
+            };
+            bytes.extend(write_continued(
+                &times,
+                to_parameter,
+                processor,
+                "TIMES",
+                group_id,
+            )?);
+        }
+        let labels = self
+            .events
+            .iter()
+            .map(|event| event.label.clone())
+            .collect::<Vec<String>>();
+        let contexts = self
+            .events
+            .iter()
+            .map(|event| event.context.clone())
+            .collect::<Vec<String>>();
+        let descriptions = self
+            .events
+            .iter()
+            .map(|event| event.description.clone())
+            .collect::<Vec<String>>();
+        let subjects = self
+            .events
+            .iter()
+            .map(|event| event.subject.clone())
+            .collect::<Vec<String>>();
+        for (name, strings) in [
+            ("LABELS", labels),
+            ("CONTEXTS", contexts),
+            ("DESCRIPTIONS", descriptions),
+            ("SUBJECTS", subjects),
+        ] {
+            if strings.len() > 0 {
+                bytes.extend(write_continued(
+                    &strings,
+                    |strings| Ok(Parameter::strings(strings)),
+                    processor,
+                    name,
+                    group_id,
+                )?);
+            }
+        }
+        let icon_ids = self
+            .events
+            .iter()
+            .map(|event| event.icon_id)
+            .collect::<Vec<i16>>();
+        let generic_flags = self
+            .events
+            .iter()
+            .map(|event| event.generic_flag)
+            .collect::<Vec<i16>>();
+        for (name, integers) in [("ICON_IDS", icon_ids), ("GENERIC_FLAGS", generic_flags)] {
+            if integers.len() > 0 {
+                bytes.extend(write_continued(
+                    &integers,
+                    Parameter::integers,
+                    processor,
+                    name,
+                    group_id,
+                )?);
+            }
+        }
+        Ok(bytes)
+    }
 }
 
+/// Returns the number of events and how many of them are stored in the header.
+/// `EVENT:USED` takes precedence over the header, since the header can only hold 18 events.
 fn get_num_time_events(
     header_block: &[u8; 512],
     parameters: &mut Parameters,
     processor: &Processor,
     supports_events_labels: bool,
-) -> Result<usize, C3dParseError> {
-    match supports_events_labels {
-        true => {
-            let num_time_events = processor.i16([header_block[300], header_block[301]]);
-
-            if num_time_events > 18 {
-                return Err(C3dParseError::TooManyEvents(num_time_events));
-            }
-            let parameter_num_time_events = parameters.remove("EVENT", "USED");
-            if parameter_num_time_events.is_some() {
-                let parameter_num_time_events: i16 =
-                    parameter_num_time_events.unwrap().as_ref().try_into()?;
-                if parameter_num_time_events != num_time_events as i16 {
-                    return Ok(parameter_num_time_events as usize);
-                } else {
-                    return Ok(num_time_events as usize);
-                }
-            }
-        }
-        false => {
-            let parameter_num_time_events = parameters.remove("EVENT", "USED");
-            if parameter_num_time_events.is_some() {
-                let parameter_num_time_events: i16 =
-                    parameter_num_time_events.unwrap().as_ref().try_into()?;
-                return Ok(parameter_num_time_events as usize);
-            }
+    context: &mut ParseContext,
+) -> Result<(usize, usize), C3dParseError> {
+    let num_header_events = match supports_events_labels {
+        true => processor.i16([header_block[300], header_block[301]]).max(0),
+        false => 0,
+    };
+    let parameter_num_time_events: Option<i16> = match parameters.take("EVENT", "USED") {
+        Some(parameter) => Some(parameter.as_ref().try_into()?),
+        None => None,
+    };
+    let parameter_num_time_events = match parameter_num_time_events {
+        Some(used) => used.max(0),
+        None if num_header_events > 18 => {
+            return Err(C3dParseError::TooManyEvents(num_header_events))
         }
+        None => return Ok((num_header_events as usize, num_header_events as usize)),
+    };
+    if supports_events_labels && parameter_num_time_events.min(18) != num_header_events {
+        context.warn_parameter(
+            C3dWarningKind::HeaderMismatch,
+            parameters,
+            "EVENT",
+            "USED",
+            format!(
+                "{} events from EVENT:USED instead of {} from the header",
+                parameter_num_time_events, num_header_events
+            ),
+        );
     }
-    Ok(0)
+    let num_time_events = parameter_num_time_events as usize;
+    Ok((num_time_events, num_time_events.min(num_header_events.min(18) as usize)))
 }
 
+/// Reads the `[minutes, seconds]` pairs of `EVENT:TIMES` and its continuation parameters.
 fn get_times_array(parameters: &mut Parameters) -> Result<Vec<[f32; 2]>, C3dParseError> {
-    let parameter = parameters.remove("EVENT", "TIMES");
-    if parameter.is_none() {
-        Ok(Vec::new())
-    } else {
-        let parameter = parameter.unwrap();
+    let mut times = Vec::new();
+    let mut index = 0;
+    while let Some(parameter) = parameters.take("EVENT", &continuation_name("TIMES", index)) {
         match &parameter.data {
             ParameterData::Float(data) => {
                 if parameter.dimensions.len() == 2 && data.len() > 1 {
-                    let mut times = Vec::new();
-                    for row in 0..data.len() % 2 {
-                        let mut time = [0.0; 2];
-                        time[0] = data[row * 2];
-                        time[1] = data[row * 2 + 1];
-                        times.push(time);
+                    for row in 0..data.len() / 2 {
+                        times.push([data[row * 2], data[row * 2 + 1]]);
                     }
-                    Ok(times)
-                } else {
-                    Ok(Vec::new())
                 }
             }
-            _ => Err(C3dParseError::InvalidParameterType(
-                "EVENT ".to_string() + "TIMES",
-            )),
+            _ => {
+                return Err(C3dParseError::InvalidParameterType(
+                    "EVENT:TIMES".to_string(),
+                    None,
+                ))
+            }
         }
+        index += 1;
     }
+    Ok(times)
 }
-
-fn get_labels_array(parameters: &mut Parameters) -> Result<Vec<String>, C3dParseError> {
-    let labels = parameters.remove("EVENT", "LABELS");
-    if labels.is_none() {
-        Ok(Vec::new())
-    } else {
-        let labels = labels.unwrap();
-        Ok(labels.as_ref().try_into()?)
-    }
-}
-
-fn get_contexts_array(parameters: &mut Parameters) -> Vec<String> {
-    let contexts = parameters.remove("EVENT", "CONTEXTS");
-    if contexts.is_none() {
-        Vec::new()
-    } else {
-        let contexts = contexts.unwrap();
-        contexts.as_ref().try_into().unwrap_or(Vec::new())
-    }
-}
-
-fn get_descriptions_array(parameters: &mut Parameters) -> Vec<String> {
-    let descriptions = parameters.remove("EVENT", "DESCRIPTIONS");
-    if descriptions.is_none() {
-        Vec::new()
-    } else {
-        let descriptions = descriptions.unwrap();
-        descriptions.as_ref().try_into().unwrap_or(Vec::new())
-    }
-}
-
-fn get_subjects_array(parameters: &mut Parameters) -> Vec<String> {
-    let subjects = parameters.remove("EVENT", "SUBJECTS");
-    if subjects.is_none() {
-        Vec::new()
-    } else {
-        let subjects = subjects.unwrap();
-        subjects.as_ref().try_into().unwrap_or(Vec::new())
-    }
-}
-
-fn get_icon_ids_array(parameters: &mut Parameters) -> Vec<i16> {
-    let icon_ids = parameters.remove("EVENT", "ICON_IDS");
-    if icon_ids.is_none() {
-        Vec::new()
-    } else {
-        let icon_ids = icon_ids.unwrap();
-        icon_ids.as_ref().try_into().unwrap_or(Vec::new())
-    }
-}
-
-fn get_generic_flags_array(parameters: &mut Parameters) -> Vec<i16> {
-    let generic_flags = parameters.remove("EVENT", "GENERIC_FLAGS");
-    if generic_flags.is_none() {
-        Vec::new()
-    } else {
-        let generic_flags = generic_flags.unwrap();
-        generic_flags.as_ref().try_into().unwrap_or(Vec::new())
+/// Reads an optional EVENT array, an unreadable array is treated as missing.
+fn get_optional_array<T>(
+    parameters: &mut Parameters,
+    parameter_name: &str,
+    context: &mut ParseContext,
+) -> Vec<T>
+where
+    for<'a> Vec<T>: TryFrom<&'a Parameter, Error = C3dParseError>,
+{
+    match parameters.take_continued("EVENT", parameter_name) {
+        Ok(values) => values.unwrap_or_default(),
+        Err(_) => {
+            context.warn_parameter(
+                C3dWarningKind::InvalidParameter,
+                parameters,
+                "EVENT",
+                parameter_name,
+                "no values".to_string(),
+            );
+            Vec::new()
+        }
     }
 }
 
-fn verify_time(
+/// The time of an event from the header, or from `EVENT:TIMES` for events that are
+/// only stored in the parameters.
+fn get_event_time(
     event_num: usize,
+    in_header: bool,
     header_block: &[u8; 512],
-    _time: &Vec<[f32; 2]>,
+    times: &[[f32; 2]],
     processor: &Processor,
-) -> Result<f32, C3dParseError> {
-    let time_start = 304 + (event_num * 4);
-    Ok(processor.f32(header_block[time_start..time_start + 4].try_into().unwrap()))
-    // TODO: use time
+) -> f32 {
+    if in_header {
+        let time_start = 304 + (event_num * 4);
+        return processor.f32(header_block[time_start..time_start + 4].try_into().unwrap());
+    }
+    match times.get(event_num) {
+        Some([minutes, seconds]) => minutes * 60.0 + seconds,
+        None => 0.0,
+    }
 }
 
-fn get_event_id(event_num: usize, header_block: &[u8; 512]) -> Result<[char; 4], C3dParseError> {
-    if event_num > 18 {
-        return Ok([0x00 as char; 4]);
-    }
+fn get_event_id(event_num: usize, header_block: &[u8; 512]) -> [char; 4] {
     let label_start = 396 + (event_num * 4);
     let label_bytes: [u8; 4] = header_block[label_start..label_start + 4]
         .try_into()
@@ -555,7 +754,7 @@ fn get_event_id(event_num: usize, header_block: &[u8; 512]) -> Result<[char; 4],
         .iter()
         .map(|b| *b as char)
         .collect::<Vec<char>>();
-    Ok(label_chars.try_into().unwrap())
+    label_chars.try_into().unwrap()
 }
 
 fn get_display_flag(event_num: usize, header_block: &[u8; 512]) -> bool {
@@ -597,3 +796,15 @@ fn get_event_generic_flag(event_num: usize, generic_flags: &Vec<i16>) -> i16 {
     }
     generic_flags[event_num]
 }
+
+fn same_name(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+fn remove_entry<T>(list: &mut Option<Vec<T>>, index: usize) {
+    if let Some(list) = list {
+        if index < list.len() {
+            list.remove(index);
+        }
+    }
+}
@@ -1,11 +1,13 @@
 //! Contains force platform information in the form of the `ForcePlatforms` struct.
 //! Includes the C3d struct implementation and high-level functions for reading and writing C3D files.
+use crate::analog::Analog;
+use crate::options::{ParseContext, ZeroOptions};
 use crate::parameters::{Parameter, ParameterData, Parameters};
 use crate::processor::Processor;
-use crate::{C3dParseError, C3dWriteError};
+use crate::{C3dParseError, C3dWarningKind, C3dWriteError};
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
-use std::ops::{Index, IndexMut};
+use std::ops::{Index, IndexMut, Range};
 
 #[derive(Debug, Clone, Default, PartialEq)]
 pub struct ForcePlatforms {
@@ -46,8 +48,16 @@ pub struct ForcePlatform {
     pub plate_type: ForcePlatformType,
     pub corners: ForcePlatformCorners,
     pub origin: ForcePlatformOrigin,
-    pub channels: [u8; 8],
-    pub cal_matrix: Option<[[f32; 6]; 6]>,
+    /// The analog channel of each platform output, counted from 1, 0 if unused.
+    /// `FORCE_PLATFORM:CHANNEL` is written as wide as the widest plate, the other
+    /// plates are padded with zeros.
+    pub channels: Vec<u8>,
+    /// The calibration matrix as stored in `FORCE_PLATFORM:CAL_MATRIX`, one `Vec` per
+    /// column. Column `j` holds the contribution of channel `j` to each output.
+    pub cal_matrix: Option<Vec<Vec<f32>>>,
+    /// The baseline of each channel that is subtracted before the kinetics are
+    /// computed, empty unless set by `C3d::zero_force_platforms`.
+    pub zero_offsets: Vec<f64>,
 }
 
 impl PartialEq for ForcePlatform {
@@ -57,6 +67,7 @@ impl PartialEq for ForcePlatform {
             && self.origin == other.origin
             && self.channels == other.channels
             && self.cal_matrix == other.cal_matrix
+            && self.zero_offsets == other.zero_offsets
     }
 }
 
@@ -79,9 +90,268 @@ impl ToString for ForcePlatform {
     }
 }
 
+impl ForcePlatform {
+    /// The calibrated values of the platform channels at one analog sample.
+    /// Unused channels read as 0.
+    fn channel_values(&self, analog: &Analog, sample: usize) -> Option<Vec<f64>> {
+        if sample >= analog.rows() {
+            return None;
+        }
+        let mut values = vec![0.0; self.plate_type.num_channels().max(self.channels.len())];
+        for (value, channel) in values.iter_mut().zip(self.channels.iter()) {
+            if *channel == 0 {
+                continue;
+            }
+            if *channel as usize > analog.cols() {
+                return None;
+            }
+            *value = analog[sample][*channel as usize - 1];
+        }
+        for (value, offset) in values.iter_mut().zip(self.zero_offsets.iter()) {
+            *value -= offset;
+        }
+        Some(values)
+    }
+
+    /// The mean of each channel over the analog samples of the given frames,
+    /// `None` if the frames are empty or outside the data.
+    fn channel_baseline(&self, analog: &Analog, frames: &Range<usize>) -> Option<Vec<f64>> {
+        let samples_per_frame = analog.samples_per_channel_per_frame as usize;
+        let samples = frames.start * samples_per_frame..frames.end * samples_per_frame;
+        if samples.is_empty() || samples.end > analog.rows() {
+            return None;
+        }
+        let mut baseline = Vec::with_capacity(self.channels.len());
+        for channel in self.channels.iter() {
+            if *channel == 0 {
+                baseline.push(0.0);
+                continue;
+            }
+            let column = *channel as usize - 1;
+            if column >= analog.cols() {
+                return None;
+            }
+            let sum: f64 = samples.clone().map(|sample| analog[sample][column]).sum();
+            baseline.push(sum / samples.len() as f64);
+        }
+        Some(baseline)
+    }
+
+    /// Computes the wrench from the calibrated values of the platform channels,
+    /// in the order of `FORCE_PLATFORM:CHANNEL`. Missing channels read as 0.
+    pub fn wrench_from_channels(&self, values: &[f64]) -> Wrench {
+        let mut values = values.to_vec();
+        values.resize(self.plate_type.num_channels().max(values.len()), 0.0);
+        if self.plate_type == ForcePlatformType::Type1 {
+            let force = [values[0], values[1], values[2]];
+            let center_of_pressure = [values[3], values[4], 0.0];
+            let mut moment = cross(center_of_pressure, force);
+            moment[2] += values[5];
+            return Wrench {
+                force,
+                moment,
+                center_of_pressure,
+                free_moment: values[5],
+            };
+        }
+        let origin = [
+            self.origin[0] as f64,
+            self.origin[1] as f64,
+            self.origin[2] as f64,
+        ];
+        // Kistler plates: the origin holds the sensor spacing and the
+        // depth of the sensors below the surface
+        let kistler_depth = [0.0, 0.0, origin[2].abs()];
+        let calibrated = match &self.cal_matrix {
+            Some(cal_matrix) if self.plate_type.cal_matrix_shape().is_some() => {
+                apply_cal_matrix(cal_matrix, &values)
+            }
+            _ => values.clone(),
+        };
+        let (force, moment, transducer) = match self.plate_type {
+            ForcePlatformType::Type3 => {
+                let (force, moment) = kistler_eight_channels(&values, origin);
+                (force, moment, kistler_depth)
+            }
+            ForcePlatformType::Type7 => {
+                let (force, moment) = kistler_eight_channels(&calibrated, origin);
+                (force, moment, kistler_depth)
+            }
+            ForcePlatformType::Type5 if self.cal_matrix.is_none() => {
+                let (force, moment) = kistler_eight_channels(&values, origin);
+                (force, moment, kistler_depth)
+            }
+            ForcePlatformType::Type5 => (
+                [calibrated[0], calibrated[1], calibrated[2]],
+                [calibrated[3], calibrated[4], calibrated[5]],
+                kistler_depth,
+            ),
+            ForcePlatformType::Type6 => {
+                let (force, moment) = kistler_twelve_channels(&calibrated, origin);
+                (force, moment, kistler_depth)
+            }
+            _ => {
+                let outputs = match self.plate_type {
+                    ForcePlatformType::Type4 => &calibrated,
+                    _ => &values,
+                };
+                // the origin points from the surface to the transducer, some
+                // systems store it the other way around
+                let transducer = match origin[2] < 0.0 {
+                    true => [-origin[0], -origin[1], -origin[2]],
+                    false => origin,
+                };
+                (
+                    [outputs[0], outputs[1], outputs[2]],
+                    [outputs[3], outputs[4], outputs[5]],
+                    transducer,
+                )
+            }
+        };
+        let offset = cross(transducer, force);
+        Wrench::from_surface_moment(
+            force,
+            [
+                moment[0] + offset[0],
+                moment[1] + offset[1],
+                moment[2] + offset[2],
+            ],
+        )
+    }
+
+    /// The rotation from the force platform coordinate system to the laboratory,
+    /// with the platform axes as columns. The x axis points from corner 2 to
+    /// corner 1 and the y axis from corner 4 to corner 1.
+    pub fn rotation(&self) -> [[f64; 3]; 3] {
+        let corner = |i: usize| {
+            [
+                self.corners[i][0] as f64,
+                self.corners[i][1] as f64,
+                self.corners[i][2] as f64,
+            ]
+        };
+        let x = normalize(subtract(corner(0), corner(1)));
+        let y = normalize(subtract(corner(0), corner(3)));
+        let (x, y) = match (x, y) {
+            (Some(x), Some(y)) => (x, y),
+            // plates without corners are taken to be aligned with the laboratory
+            _ => return [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        };
+        let z = normalize(cross(x, y)).unwrap_or([0.0, 0.0, 1.0]);
+        // the corners are rarely exactly square
+        let y = cross(z, x);
+        [[x[0], y[0], z[0]], [x[1], y[1], z[1]], [x[2], y[2], z[2]]]
+    }
+
+    /// The centre of the working surface in the laboratory, the mean of the corners.
+    pub fn center(&self) -> [f64; 3] {
+        let mut center = [0.0; 3];
+        for corner in self.corners.iter() {
+            for axis in 0..3 {
+                center[axis] += corner[axis] as f64 / 4.0;
+            }
+        }
+        center
+    }
+
+    /// Expresses a wrench in the laboratory coordinate system.
+    pub fn to_lab(&self, wrench: &Wrench) -> LabWrench {
+        let rotation = self.rotation();
+        let center_of_pressure = rotate(&rotation, wrench.center_of_pressure);
+        let center = self.center();
+        LabWrench {
+            force: rotate(&rotation, wrench.force),
+            center_of_pressure: [
+                center_of_pressure[0] + center[0],
+                center_of_pressure[1] + center[1],
+                center_of_pressure[2] + center[2],
+            ],
+            moment: rotate(&rotation, [0.0, 0.0, wrench.free_moment]),
+        }
+    }
+}
+
+/// The force and moment measured by a force platform at one analog sample.
+/// Values are in the force platform coordinate system with the moment taken
+/// about the centre of the working surface.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Wrench {
+    pub force: [f64; 3],
+    pub moment: [f64; 3],
+    /// The centre of pressure on the working surface relative to its centre,
+    /// `NaN` while the vertical force is 0.
+    pub center_of_pressure: [f64; 3],
+    /// The moment about the vertical axis through the centre of pressure.
+    pub free_moment: f64,
+}
+
+impl Wrench {
+    /// Finds the centre of pressure and free moment from the force and the
+    /// moment about the centre of the working surface.
+    fn from_surface_moment(force: [f64; 3], moment: [f64; 3]) -> Wrench {
+        let (center_of_pressure, free_moment) = match force[2] == 0.0 {
+            true => ([f64::NAN; 3], f64::NAN),
+            false => {
+                let x = -moment[1] / force[2];
+                let y = moment[0] / force[2];
+                ([x, y, 0.0], moment[2] - (x * force[1] - y * force[0]))
+            }
+        };
+        Wrench {
+            force,
+            moment,
+            center_of_pressure,
+            free_moment,
+        }
+    }
+}
+
+/// The force of a force platform in the laboratory coordinate system, applied at
+/// the centre of pressure. The moment about the centre of pressure is the free
+/// moment along the platform normal.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LabWrench {
+    pub force: [f64; 3],
+    pub center_of_pressure: [f64; 3],
+    pub moment: [f64; 3],
+}
+
 impl ForcePlatforms {
-    pub(crate) fn from_parameters(parameters: &mut Parameters) -> Result<Self, C3dParseError> {
-        let used_parameter = parameters.remove("FORCE_PLATFORM", "USED");
+    /// Points the force platform channels at the new positions of the given analog columns.
+    /// Channels that are not kept are set to zero.
+    pub(crate) fn retain_analog_columns(&mut self, columns: &[usize]) {
+        for force_platform in self.force_platforms.iter_mut() {
+            for channel in force_platform.channels.iter_mut() {
+                *channel = match columns
+                    .iter()
+                    .position(|column| column + 1 == *channel as usize)
+                {
+                    Some(index) => (index + 1) as u8,
+                    None => 0,
+                };
+            }
+        }
+    }
+
+    /// Moves the zero range after the first `frames_removed` frames are dropped.
+    /// The range is cleared if it only covered dropped frames.
+    pub(crate) fn shift_zero(&mut self, frames_removed: usize) {
+        let start = self.zero[0] as usize;
+        let end = self.zero[1] as usize;
+        self.zero = match end > frames_removed {
+            true => [
+                start.saturating_sub(frames_removed).max(1) as u16,
+                (end - frames_removed) as u16,
+            ],
+            false => [0, 0],
+        };
+    }
+
+    pub(crate) fn from_parameters(
+        parameters: &mut Parameters,
+        context: &mut ParseContext,
+    ) -> Result<Self, C3dParseError> {
+        let used_parameter = parameters.take("FORCE_PLATFORM", "USED");
         let used: Option<u16> = match used_parameter {
             None => None,
             Some(parameter) => Some(parameter.as_ref().try_into()?),
@@ -99,11 +369,33 @@ impl ForcePlatforms {
             let corners = ForcePlatformCorners::from_parameters(parameters, used)?;
             let origin = ForcePlatformOrigin::from_parameters(parameters, used)?;
             let zero = parameters
-                .remove_or_err("FORCE_PLATFORM", "ZERO")?
+                .take_or_err("FORCE_PLATFORM", "ZERO")?
                 .as_ref()
                 .try_into()?;
-            let contains_type_3 = plate_type.iter().any(|x| *x == ForcePlatformType::Type3);
-            let channels = get_channels(parameters, used, contains_type_3)?;
+            let num_channels = plate_type
+                .iter()
+                .map(|x| x.num_channels())
+                .max()
+                .unwrap_or(0);
+            let channel_width = parameters
+                .get("FORCE_PLATFORM", "CHANNEL")
+                .and_then(|channel| channel.dimensions.first().copied())
+                .unwrap_or(0) as usize;
+            // lenient parsing leaves the channels missing from the parameter at zero
+            let channels = get_channels(parameters, used, num_channels, context.lenient)?;
+            if channel_width < num_channels {
+                context.warn_parameter(
+                    C3dWarningKind::InvalidParameter,
+                    parameters,
+                    "FORCE_PLATFORM",
+                    "CHANNEL",
+                    format!(
+                        "channels {} to {} of the plates are unused",
+                        channel_width + 1,
+                        num_channels
+                    ),
+                );
+            }
             let cal_matrices = get_cal_matrix_vector(parameters, &plate_type)?;
 
             let mut force_platforms = Vec::new();
@@ -112,7 +404,18 @@ impl ForcePlatforms {
                 force_platform.plate_type = plate_type[i].clone();
                 force_platform.corners = corners[i].clone();
                 force_platform.origin = origin[i].clone();
-                force_platform.channels = channels[i];
+                // each plate keeps the channels of its type and any others the file assigns
+                // to it, plates of other types keep the whole column
+                let width = match plate_type[i] {
+                    ForcePlatformType::Other(_) => channels[i].len(),
+                    _ => channels[i]
+                        .iter()
+                        .rposition(|&c| c != 0)
+                        .map_or(0, |c| c + 1)
+                        .max(plate_type[i].num_channels())
+                        .min(channels[i].len()),
+                };
+                force_platform.channels = channels[i][..width].to_vec();
                 force_platform.cal_matrix = cal_matrices[i].clone();
                 force_platforms.push(force_platform);
             }
@@ -183,21 +486,37 @@ impl ForcePlatforms {
         }
         // "FORCE_PLATFORM", "CHANNEL"
         if self.force_platforms.len() > 0 {
+            // the widest plate sets the width, narrower plates are padded with zeros
+            let num_channels = self
+                .force_platforms
+                .iter()
+                .map(|x| {
+                    let used = x
+                        .channels
+                        .iter()
+                        .rposition(|&c| c != 0)
+                        .map_or(0, |c| c + 1);
+                    used.max(x.plate_type.num_channels())
+                })
+                .max()
+                .unwrap_or(0);
             let mut channels = Parameter::integers(
                 self.force_platforms
                     .iter()
                     .map(|x| {
-                        x.channels
-                            .clone()
-                            .to_vec()
+                        let mut channels = x
+                            .channels
                             .iter()
+                            .take(num_channels)
                             .map(|y| y.clone() as i16)
-                            .collect::<Vec<i16>>()
+                            .collect::<Vec<i16>>();
+                        channels.resize(num_channels, 0);
+                        channels
                     })
                     .flatten()
                     .collect::<Vec<i16>>(),
             )?;
-            channels.dimensions = vec![8, self.force_platforms.len() as u8];
+            channels.dimensions = vec![num_channels as u8, self.force_platforms.len() as u8];
             bytes.extend(channels.write(
                 processor,
                 "CHANNEL".to_string(),
@@ -206,45 +525,42 @@ impl ForcePlatforms {
             )?);
         }
         // "FORCE_PLATFORM", "CAL_MATRIX"
-        if self.force_platforms.len() > 0 {
-            if self.force_platforms.iter().any(|x| x.cal_matrix.is_some()) {
-                let mut cal_matrices = Parameter::floats(
-                    self.force_platforms
-                        .iter()
-                        .map(|force_platform| {
-                            if force_platform.cal_matrix.is_some() {
-                                force_platform
-                                    .cal_matrix
-                                    .unwrap()
-                                    .to_vec()
-                                    .iter()
-                                    .map(|x| {
-                                        x.to_vec()
-                                            .iter()
-                                            .map(|y| y.clone() as f32)
-                                            .collect::<Vec<f32>>()
-                                    })
-                                    .flatten()
-                                    .collect::<Vec<f32>>()
-                            } else {
-                                let mut temp: Vec<f32> = Vec::new();
-                                for _ in 0..36 {
-                                    temp.push(0.0);
-                                }
-                                temp
-                            }
-                        })
-                        .flatten()
-                        .collect::<Vec<f32>>(),
-                )?;
-                cal_matrices.dimensions = vec![6, 6, self.force_platforms.len() as u8];
-                bytes.extend(cal_matrices.write(
-                    processor,
-                    "CAL_MATRIX".to_string(),
-                    group_names_to_ids["FORCE_PLATFORM"],
-                    false,
-                )?);
+        if self.force_platforms.iter().any(|x| x.cal_matrix.is_some()) {
+            // matrices of different shapes are padded with zeros to the largest one
+            let matrices = self
+                .force_platforms
+                .iter()
+                .filter_map(|x| x.cal_matrix.as_ref());
+            let columns = matrices.clone().map(|x| x.len()).max().unwrap_or(0);
+            let rows = matrices
+                .flat_map(|x| x.iter().map(|column| column.len()))
+                .max()
+                .unwrap_or(0);
+            let mut data = Vec::with_capacity(rows * columns * self.force_platforms.len());
+            for force_platform in self.force_platforms.iter() {
+                for column in 0..columns {
+                    for row in 0..rows {
+                        data.push(
+                            force_platform
+                                .cal_matrix
+                                .as_ref()
+                                .and_then(|x| x.get(column))
+                                .and_then(|x| x.get(row))
+                                .copied()
+                                .unwrap_or(0.0),
+                        );
+                    }
+                }
             }
+            let mut cal_matrices = Parameter::floats(data)?;
+            cal_matrices.dimensions =
+                vec![rows as u8, columns as u8, self.force_platforms.len() as u8];
+            bytes.extend(cal_matrices.write(
+                processor,
+                "CAL_MATRIX".to_string(),
+                group_names_to_ids["FORCE_PLATFORM"],
+                false,
+            )?);
         }
         // "FORCE_PLATFORM", "ZERO"
         bytes.extend(
@@ -261,12 +577,7 @@ impl ForcePlatforms {
                 Parameter::integers(
                     self.force_platforms
                         .iter()
-                        .map(|x| match x.plate_type {
-                            ForcePlatformType::Type1 => 1,
-                            ForcePlatformType::Type2 => 2,
-                            ForcePlatformType::Type3 => 3,
-                            ForcePlatformType::Type4 => 4,
-                        })
+                        .map(|x| x.plate_type.code())
                         .collect::<Vec<i16>>(),
                 )?
                 .write(
@@ -280,40 +591,98 @@ impl ForcePlatforms {
         Ok(bytes)
     }
 
-    pub(crate) fn force_from_analog(
-        &self,
-        analog: [f32; 8],
-        force_platform: usize,
-    ) -> Option<[f32; 3]> {
-        if force_platform < self.force_platforms.len() {
-            Some(
-                self.force_platforms[force_platform]
-                    .plate_type
-                    .force_from_analog(analog),
-            )
-        } else {
-            None
+    /// Computes the wrench of a force platform at one analog sample.
+    /// Returns `None` if the platform or the sample does not exist, or if the
+    /// platform is of an other type.
+    pub fn wrench(&self, analog: &Analog, force_platform: usize, sample: usize) -> Option<Wrench> {
+        let force_platform = self.force_platforms.get(force_platform)?;
+        if let ForcePlatformType::Other(_) = force_platform.plate_type {
+            return None;
         }
+        let values = force_platform.channel_values(analog, sample)?;
+        Some(force_platform.wrench_from_channels(&values))
+    }
+
+    /// Computes the wrench of a force platform at every analog sample, so there are
+    /// `ANALOG:RATE / POINT:RATE` wrenches per frame.
+    pub fn wrenches(&self, analog: &Analog, force_platform: usize) -> Option<Vec<Wrench>> {
+        (0..analog.rows())
+            .map(|sample| self.wrench(analog, force_platform, sample))
+            .collect()
     }
 
-    pub(crate) fn center_of_pressure_from_analog(
+    /// Computes the laboratory wrench of a force platform for each of the `num_frames` point
+    /// frames. The force and moment are averaged over the analog samples of each frame
+    /// before the centre of pressure is found.
+    /// Returns `None` if the analog data does not hold exactly the samples of `num_frames`.
+    pub fn lab_wrenches(
         &self,
-        analog: [f32; 8],
+        analog: &Analog,
         force_platform: usize,
-    ) -> Option<[f32; 2]> {
-        if force_platform < self.force_platforms.len() {
-            Some(
-                self.force_platforms[force_platform]
-                    .plate_type
-                    .center_of_pressure_from_analog(
-                        analog,
-                        self.force_platforms[force_platform].origin.clone(),
-                        self.force_platforms[force_platform].corners.clone(),
-                    ),
-            )
-        } else {
-            None
+        num_frames: usize,
+    ) -> Option<Vec<LabWrench>> {
+        let samples_per_frame = analog.samples_per_channel_per_frame.max(1) as usize;
+        if analog.rows() != num_frames * samples_per_frame {
+            return None;
+        }
+        let wrenches = self.wrenches(analog, force_platform)?;
+        let force_platform = &self.force_platforms[force_platform];
+        Some(
+            wrenches
+                .chunks_exact(samples_per_frame)
+                .map(|samples| {
+                    let mut force = [0.0; 3];
+                    let mut moment = [0.0; 3];
+                    for sample in samples {
+                        for axis in 0..3 {
+                            force[axis] += sample.force[axis] / samples.len() as f64;
+                            moment[axis] += sample.moment[axis] / samples.len() as f64;
+                        }
+                    }
+                    force_platform.to_lab(&Wrench::from_surface_moment(force, moment))
+                })
+                .collect(),
+        )
+    }
+
+    /// Finds the baseline of every force platform channel and either keeps it in
+    /// `ForcePlatform::zero_offsets` or subtracts it from `analog`.
+    /// Returns the offsets per plate, or `None` without changing anything if the
+    /// window is empty or outside the data.
+    pub(crate) fn zero(
+        &mut self,
+        analog: &mut Analog,
+        options: &ZeroOptions,
+    ) -> Option<Vec<Vec<f64>>> {
+        let frames = match &options.frames {
+            Some(frames) => frames.clone(),
+            None => (self.zero[0] as usize).saturating_sub(1)..self.zero[1] as usize,
+        };
+        let offsets = self
+            .force_platforms
+            .iter()
+            .map(|force_platform| force_platform.channel_baseline(analog, &frames))
+            .collect::<Option<Vec<Vec<f64>>>>()?;
+        let mut zeroed_columns = Vec::new();
+        for (force_platform, offsets) in self.force_platforms.iter_mut().zip(offsets.iter()) {
+            if !options.write_back {
+                force_platform.zero_offsets = offsets.clone();
+                continue;
+            }
+            force_platform.zero_offsets.clear();
+            for (channel, offset) in force_platform.channels.iter().zip(offsets.iter()) {
+                // channels shared by two plates are only corrected once
+                if *channel == 0 || zeroed_columns.contains(channel) {
+                    continue;
+                }
+                zeroed_columns.push(*channel);
+                let column = *channel as usize - 1;
+                for sample in 0..analog.rows() {
+                    analog[sample][column] -= offset;
+                }
+            }
         }
+        Some(offsets)
     }
 
     pub fn origin(&self, force_platform: usize) -> Option<&ForcePlatformOrigin> {
@@ -325,13 +694,29 @@ impl ForcePlatforms {
     }
 }
 
+/// The `FORCE_PLATFORM:TYPE` of a plate, which sets the meaning of its channels.
+/// Types 1 to 7 are described in the force platform chapter of the C3D User Guide.
 #[derive(Debug, Clone, PartialEq, Default)]
 pub enum ForcePlatformType {
+    /// FX, FY, FZ, the centre of pressure X, Y and the free moment TZ.
     Type1,
+    /// FX, FY, FZ, MX, MY, MZ.
     #[default]
     Type2,
+    /// Kistler plates: FX12, FX34, FY14, FY23, FZ1, FZ2, FZ3, FZ4.
     Type3,
+    /// Type 2 with a 6 by 6 calibration matrix.
     Type4,
+    /// Type 3 channels with a 6 by 8 calibration matrix that gives the Type 2 outputs.
+    Type5,
+    /// FX, FY, FZ of each of the four Kistler sensors with a 12 by 12 calibration matrix.
+    Type6,
+    /// Type 3 with an 8 by 8 calibration matrix.
+    Type7,
+    /// Any other `FORCE_PLATFORM:TYPE`, such as the vendor types 11, 12 and 21.
+    /// Its channels and calibration matrix are kept as stored so they are written back
+    /// unchanged, but no wrench is computed for it.
+    Other(i16),
 }
 
 impl ForcePlatformType {
@@ -340,62 +725,67 @@ impl ForcePlatformType {
         used: u16,
     ) -> Result<Vec<ForcePlatformType>, C3dParseError> {
         let force_platform_type: Vec<i16> = parameters
-            .remove_or_err("FORCE_PLATFORM", "TYPE")?
+            .take_or_err("FORCE_PLATFORM", "TYPE")?
             .as_ref()
             .try_into()?;
         let force_platform_type = force_platform_type
             .iter()
             .map(|x| match x {
-                1 => Ok(ForcePlatformType::Type1),
-                2 => Ok(ForcePlatformType::Type2),
-                3 => Ok(ForcePlatformType::Type3),
-                4 => Ok(ForcePlatformType::Type4),
-                _ => Err(C3dParseError::InvalidParameterFormat(x.to_string())),
+                1 => ForcePlatformType::Type1,
+                2 => ForcePlatformType::Type2,
+                3 => ForcePlatformType::Type3,
+                4 => ForcePlatformType::Type4,
+                5 => ForcePlatformType::Type5,
+                6 => ForcePlatformType::Type6,
+                7 => ForcePlatformType::Type7,
+                other => ForcePlatformType::Other(*other),
             })
-            .collect::<Result<Vec<ForcePlatformType>, C3dParseError>>()?;
+            .collect::<Vec<ForcePlatformType>>();
         if force_platform_type.len() != used as usize {
             return Err(C3dParseError::InvalidParameterFormat(
                 "FORCE_PLATFORM:TYPE".to_string(),
+                None,
             ));
         }
         Ok(force_platform_type)
     }
 
-    fn force_from_analog(&self, analog: [f32; 8]) -> [f32; 3] {
+    /// The value stored in `FORCE_PLATFORM:TYPE`.
+    pub fn code(&self) -> i16 {
         match self {
-            ForcePlatformType::Type3 => {
-                return [
-                    analog[0] + analog[1],
-                    analog[2] + analog[3],
-                    analog[4] + analog[5] + analog[6] + analog[7],
-                ];
-            }
-            _ => {
-                return [analog[0], analog[1], analog[2]];
-            }
+            ForcePlatformType::Type1 => 1,
+            ForcePlatformType::Type2 => 2,
+            ForcePlatformType::Type3 => 3,
+            ForcePlatformType::Type4 => 4,
+            ForcePlatformType::Type5 => 5,
+            ForcePlatformType::Type6 => 6,
+            ForcePlatformType::Type7 => 7,
+            ForcePlatformType::Other(code) => *code,
         }
     }
 
-    fn center_of_pressure_from_analog(
-        &self,
-        analog: [f32; 8],
-        origin: ForcePlatformOrigin,
-        corners: ForcePlatformCorners,
-    ) -> [f32; 2] {
+    /// The number of analog channels the plate uses, 0 for other types.
+    pub fn num_channels(&self) -> usize {
         match self {
-            ForcePlatformType::Type1 => [analog[3], analog[4]],
-            ForcePlatformType::Type3 => {
-                [(analog[0] + analog[1]) / 2., (analog[2] + analog[3]) / 2.]
-            }
-            _ => {
-                let origin = origin.origin;
-                let height = corners[0][2] - origin[2];
-                let x = (-height * analog[0] - analog[4]) / analog[2];
-                let y = (-height * analog[1] - analog[3]) / analog[2];
-                let x = -x;
-                let y = -y;
-                [x, y]
-            }
+            ForcePlatformType::Type1 | ForcePlatformType::Type2 | ForcePlatformType::Type4 => 6,
+            ForcePlatformType::Type3 | ForcePlatformType::Type5 | ForcePlatformType::Type7 => 8,
+            ForcePlatformType::Type6 => 12,
+            ForcePlatformType::Other(_) => 0,
+        }
+    }
+
+    /// The number of outputs and channels of the calibration matrix,
+    /// `None` for plates without one and for other types.
+    pub fn cal_matrix_shape(&self) -> Option<(usize, usize)> {
+        match self {
+            ForcePlatformType::Type1
+            | ForcePlatformType::Type2
+            | ForcePlatformType::Type3
+            | ForcePlatformType::Other(_) => None,
+            ForcePlatformType::Type4 => Some((6, 6)),
+            ForcePlatformType::Type5 => Some((6, 8)),
+            ForcePlatformType::Type6 => Some((12, 12)),
+            ForcePlatformType::Type7 => Some((8, 8)),
         }
     }
 }
@@ -443,7 +833,7 @@ impl ForcePlatformCorners {
         used: u16,
     ) -> Result<Vec<Self>, C3dParseError> {
         let mut corners = Vec::new();
-        let parameter = parameters.remove_or_err("FORCE_PLATFORM", "CORNERS")?;
+        let parameter = parameters.take_or_err("FORCE_PLATFORM", "CORNERS")?;
         match &parameter.data {
             ParameterData::Float(data) => {
                 let dimensions: Vec<usize> =
@@ -456,8 +846,9 @@ impl ForcePlatformCorners {
                         3 => dimensions[2],
                         2 => 1,
                         _ => Err(C3dParseError::InvalidData(
-                            parameter.clone(),
+                            Box::new(parameter.clone()),
                             "FORCE_PLATFORM_CORNERS".to_string(),
+                            None,
                         ))?,
                     };
                     for i in 0..num_plates {
@@ -474,22 +865,25 @@ impl ForcePlatformCorners {
                     }
                 } else {
                     return Err(C3dParseError::InvalidData(
-                        parameter.clone(),
+                        Box::new(parameter.clone()),
                         "FORCE_PLATFORM_CORNERS".to_string(),
+                        None,
                     ));
                 }
             }
             _ => {
                 return Err(C3dParseError::InvalidData(
-                    parameter.clone(),
+                    Box::new(parameter.clone()),
                     "FORCE_PLATFORM_CORNERS".to_string(),
+                    None,
                 ));
             }
         }
         if corners.len() != used as usize {
             return Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "FORCE_PLATFORM_CORNERS".to_string(),
+                None,
             ));
         }
         Ok(corners
@@ -528,7 +922,7 @@ impl ForcePlatformOrigin {
         used: u16,
     ) -> Result<Vec<Self>, C3dParseError> {
         let mut origin = Vec::new();
-        let parameter = parameters.remove_or_err("FORCE_PLATFORM", "ORIGIN")?;
+        let parameter = parameters.take_or_err("FORCE_PLATFORM", "ORIGIN")?;
         match &parameter.data {
             ParameterData::Float(data) => {
                 let dimensions: Vec<usize> =
@@ -548,22 +942,25 @@ impl ForcePlatformOrigin {
                     }
                 } else {
                     return Err(C3dParseError::InvalidData(
-                        parameter.clone(),
+                        Box::new(parameter.clone()),
                         "FORCE_PLATFORM_ORIGIN".to_string(),
+                        None,
                     ));
                 }
             }
             _ => {
                 return Err(C3dParseError::InvalidData(
-                    parameter.clone(),
+                    Box::new(parameter.clone()),
                     "FORCE_PLATFORM_ORIGIN".to_string(),
+                    None,
                 ));
             }
         }
         if origin.len() != used as usize {
             return Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "FORCE_PLATFORM_ORIGIN".to_string(),
+                None,
             ));
         }
         Ok(origin
@@ -578,118 +975,191 @@ impl ForcePlatformOrigin {
 fn get_channels(
     parameters: &mut Parameters,
     used: u16,
-    contains_type_3: bool,
-) -> Result<Vec<[u8; 8]>, C3dParseError> {
-    let channel_parameter = parameters.remove_or_err("FORCE_PLATFORM", "CHANNEL");
-    let parameter = channel_parameter.unwrap();
-    match &parameter.data {
-        ParameterData::Integer(data) => {
-            let dimensions: Vec<usize> = parameter.dimensions.iter().map(|&x| x as usize).collect();
-            if dimensions.len() == 2 || dimensions.len() == 1 {
-                if (dimensions.len() == 1 && used != 1)
-                    || (dimensions.len() == 2 && dimensions[1] != used as usize)
-                {
-                    return Err(C3dParseError::InvalidData(
-                        parameter.clone(),
-                        "FORCE_PLATFORM_CHANNEL".to_string(),
-                    ));
-                }
-                if dimensions[0] == 8 {
-                    let mut array = Vec::new();
-                    for i in 0..used as usize {
-                        let mut channels = [0; 8];
-                        for j in 0..8 {
-                            channels[j] = data[i * 8 + j] as u8;
-                        }
-                        array.push(channels);
-                    }
-                    Ok(array)
-                } else if dimensions[0] == 6 && !contains_type_3 {
-                    let mut array = Vec::new();
-                    for i in 0..used as usize {
-                        let mut channels = [0; 8];
-                        for j in 0..6 {
-                            channels[j] = data[i * 6 + j] as u8;
-                        }
-                        array.push(channels);
-                    }
-                    Ok(array)
-                } else {
-                    Err(C3dParseError::InvalidData(
-                        parameter.clone(),
-                        "FORCE_PLATFORM_CHANNEL".to_string(),
-                    ))
-                }
-            } else {
-                Err(C3dParseError::InvalidData(
-                    parameter.clone(),
-                    "FORCE_PLATFORM_CHANNEL".to_string(),
-                ))
-            }
-        }
-        _ => Err(C3dParseError::InvalidData(
-            parameter.clone(),
+    num_channels: usize,
+    lenient: bool,
+) -> Result<Vec<Vec<u8>>, C3dParseError> {
+    let parameter = parameters.take_or_err("FORCE_PLATFORM", "CHANNEL")?;
+    let invalid = || {
+        C3dParseError::InvalidData(
+            Box::new(parameter.clone()),
             "FORCE_PLATFORM_CHANNEL".to_string(),
-        )),
+            None,
+        )
+    };
+    let data = match &parameter.data {
+        ParameterData::Integer(data) => data,
+        _ => return Err(invalid()),
+    };
+    let dimensions: Vec<usize> = parameter.dimensions.iter().map(|&x| x as usize).collect();
+    if (dimensions.len() == 1 && used != 1)
+        || (dimensions.len() == 2 && dimensions[1] != used as usize)
+        || dimensions.is_empty()
+        || dimensions.len() > 2
+    {
+        return Err(invalid());
+    }
+    let width = dimensions[0];
+    if (width < num_channels && !lenient) || data.len() < width * used as usize {
+        return Err(invalid());
     }
+    Ok((0..used as usize)
+        .map(|i| {
+            data[i * width..(i + 1) * width]
+                .iter()
+                .map(|&x| x as u8)
+                .collect()
+        })
+        .collect())
 }
 
 fn get_cal_matrix_vector(
     parameters: &mut Parameters,
     plate_type: &Vec<ForcePlatformType>,
-) -> Result<Vec<Option<[[f32; 6]; 6]>>, C3dParseError> {
-    let parameter = parameters.remove("FORCE_PLATFORM", "CAL_MATRIX");
-    if parameter.is_none() {
-        return Ok(vec![None; plate_type.len()]);
-    }
-    let num_type_4 = plate_type
+) -> Result<Vec<Option<Vec<Vec<f32>>>>, C3dParseError> {
+    let num_calibrated = plate_type
         .iter()
-        .filter(|x| **x == ForcePlatformType::Type4)
+        .filter(|x| x.cal_matrix_shape().is_some())
         .count();
-    let parameter = parameter.unwrap();
-    let cal_matrices = match &parameter.data {
-        ParameterData::Float(data) => {
-            let dimensions: Vec<usize> = parameter.dimensions.iter().map(|&x| x as usize).collect();
-            if dimensions.len() == 3 || (dimensions.len() == 2 && data.len() == 36) {
-                let mut array = Vec::new();
-                if data.len() < 36 * num_type_4 {
-                    return Err(C3dParseError::InvalidData(
-                        parameter.clone(),
-                        "FORCE_PLATFORM_CAL_MATRIX".to_string(),
-                    ));
-                }
-                for platform in 0..data.len() / 36 {
-                    let mut matrix = [[0.0; 6]; 6];
-                    for i in 0..6 {
-                        for j in 0..6 {
-                            matrix[i][j] = data[platform * 36 + i * 6 + j];
-                        }
-                    }
-                    array.push(matrix);
-                }
-                Some(array)
-            } else {
-                return Err(C3dParseError::InvalidData(
-                    parameter.clone(),
-                    "FORCE_PLATFORM_CAL_MATRIX".to_string(),
-                ));
-            }
-        }
-        _ => {
-            return Err(C3dParseError::InvalidData(
-                parameter.clone(),
-                "FORCE_PLATFORM_CAL_MATRIX".to_string(),
-            ))
-        }
+    // Matrices that no calibrated plate uses stay in the parameter map so they
+    // are written back unchanged.
+    if num_calibrated == 0 {
+        return Ok(vec![None; plate_type.len()]);
+    }
+    let parameter = match parameters.take("FORCE_PLATFORM", "CAL_MATRIX") {
+        Some(parameter) => parameter,
+        None => return Ok(vec![None; plate_type.len()]),
+    };
+    let invalid = || {
+        C3dParseError::InvalidData(
+            Box::new(parameter.clone()),
+            "FORCE_PLATFORM_CAL_MATRIX".to_string(),
+            None,
+        )
+    };
+    let data = match &parameter.data {
+        ParameterData::Float(data) => data,
+        _ => return Err(invalid()),
     };
-    let cal_matrices = cal_matrices.unwrap();
-    let mut cal_matrix_vec: Vec<Option<[[f32; 6]; 6]>> = vec![None; plate_type.len()];
+    let dimensions: Vec<usize> = parameter.dimensions.iter().map(|&x| x as usize).collect();
+    if dimensions.len() != 2 && dimensions.len() != 3 {
+        return Err(invalid());
+    }
+    let (rows, columns) = (dimensions[0], dimensions[1]);
+    let size = rows * columns;
+    if size == 0 || data.len() < size * num_calibrated {
+        return Err(invalid());
+    }
+    // one matrix per plate, or only as many as there are calibrated plates
+    let num_matrices = data.len() / size;
+    let per_plate = num_matrices >= plate_type.len();
+    let mut cal_matrix_vec = vec![None; plate_type.len()];
     let mut count = 0;
-    for i in 0..plate_type.len() {
-        if plate_type[i] == ForcePlatformType::Type4 {
-            cal_matrix_vec.push(Some(cal_matrices[count]));
-            count += 1;
+    for (i, plate_type) in plate_type.iter().enumerate() {
+        let (outputs, inputs) = match plate_type.cal_matrix_shape() {
+            Some(shape) => shape,
+            // plates of other types keep their whole matrix for writing it back
+            None if per_plate && matches!(plate_type, ForcePlatformType::Other(_)) => {
+                (rows, columns)
+            }
+            None => continue,
+        };
+        if outputs > rows || inputs > columns {
+            return Err(invalid());
         }
+        let start = match per_plate {
+            true => i * size,
+            false => count * size,
+        };
+        cal_matrix_vec[i] = Some(
+            (0..inputs)
+                .map(|column| {
+                    let column = start + column * rows;
+                    data[column..column + outputs].to_vec()
+                })
+                .collect(),
+        );
+        count += 1;
     }
     Ok(cal_matrix_vec)
 }
+
+/// Multiplies the channel values by the calibration matrix. The parameter
+/// stores the matrix column by column, so `cal_matrix[j]` is column `j`.
+fn apply_cal_matrix(cal_matrix: &[Vec<f32>], values: &[f64]) -> Vec<f64> {
+    let outputs = cal_matrix.iter().map(|x| x.len()).max().unwrap_or(0);
+    let mut calibrated = vec![0.0; outputs];
+    for (column, value) in cal_matrix.iter().zip(values.iter()) {
+        for (output, factor) in calibrated.iter_mut().zip(column.iter()) {
+            *output += *factor as f64 * value;
+        }
+    }
+    calibrated.resize(values.len().max(outputs), 0.0);
+    calibrated
+}
+
+/// Forces and moments about the sensor plane of the Type 3 channels
+/// FX12, FX34, FY14, FY23, FZ1, FZ2, FZ3, FZ4.
+fn kistler_eight_channels(values: &[f64], origin: [f64; 3]) -> ([f64; 3], [f64; 3]) {
+    let (a, b) = (origin[0], origin[1]);
+    let force = [
+        values[0] + values[1],
+        values[2] + values[3],
+        values[4] + values[5] + values[6] + values[7],
+    ];
+    let moment = [
+        b * (values[4] + values[5] - values[6] - values[7]),
+        a * (-values[4] + values[5] + values[6] - values[7]),
+        b * (-values[0] + values[1]) + a * (values[2] - values[3]),
+    ];
+    (force, moment)
+}
+
+/// Forces and moments about the sensor plane of the FX, FY, FZ channels of
+/// the four sensors at (a, b), (-a, b), (-a, -b) and (a, -b).
+fn kistler_twelve_channels(values: &[f64], origin: [f64; 3]) -> ([f64; 3], [f64; 3]) {
+    let (a, b) = (origin[0], origin[1]);
+    let positions = [[a, b, 0.0], [-a, b, 0.0], [-a, -b, 0.0], [a, -b, 0.0]];
+    let mut force = [0.0; 3];
+    let mut moment = [0.0; 3];
+    for (sensor, position) in positions.iter().enumerate() {
+        let sensor_force = [
+            values[sensor * 3],
+            values[sensor * 3 + 1],
+            values[sensor * 3 + 2],
+        ];
+        let sensor_moment = cross(*position, sensor_force);
+        for axis in 0..3 {
+            force[axis] += sensor_force[axis];
+            moment[axis] += sensor_moment[axis];
+        }
+    }
+    (force, moment)
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn subtract(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn normalize(a: [f64; 3]) -> Option<[f64; 3]> {
+    let length = (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt();
+    match length > 0.0 {
+        true => Some([a[0] / length, a[1] / length, a[2] / length]),
+        false => None,
+    }
+}
+
+fn rotate(rotation: &[[f64; 3]; 3], a: [f64; 3]) -> [f64; 3] {
+    let mut rotated = [0.0; 3];
+    for (row, value) in rotation.iter().zip(rotated.iter_mut()) {
+        *value = row[0] * a[0] + row[1] * a[1] + row[2] * a[2];
+    }
+    rotated
+}
@@ -0,0 +1,376 @@
+//! Detects gait events from the force platform data or marker trajectories of a C3D file.
+use crate::c3d::C3d;
+use crate::events::{time_of_frame, Event};
+use crate::forces::LabWrench;
+use crate::options::{ForceEventOptions, KinematicEventOptions, KinematicMethod};
+
+/// Finds the foot strikes and foot offs on every force platform from the vertical force.
+///
+/// A contact starts when the vertical force rises above `options.threshold` and ends when
+/// it falls below it again. Contacts shorter than `options.min_duration` are ignored, and
+/// contacts that are already under way at the start or end of the data only produce
+/// the event that is inside the data. The events are sorted by time.
+pub fn force_events(c3d: &C3d, options: &ForceEventOptions) -> Vec<Event> {
+    let samples_per_frame = c3d.analog.samples_per_channel_per_frame as usize;
+    if samples_per_frame == 0 || c3d.analog.rate <= 0.0 || c3d.points.frame_rate <= 0.0 {
+        return Vec::new();
+    }
+    let mut events = Vec::new();
+    for force_platform in 0..c3d.forces.len() {
+        let wrenches = match c3d.wrenches(force_platform) {
+            Some(wrenches) => wrenches,
+            None => continue,
+        };
+        let loaded = wrenches
+            .iter()
+            .map(|wrench| wrench.force[2].abs() > options.threshold)
+            .collect::<Vec<bool>>();
+        let lab_wrenches = match options.sides.contains_key(&force_platform) {
+            true => None,
+            false => c3d.lab_wrenches(force_platform),
+        };
+        let min_samples = (options.min_duration * c3d.analog.rate as f64).ceil() as usize;
+        for (start, end) in contacts(&loaded) {
+            if end - start < min_samples.max(1) {
+                continue;
+            }
+            let frame = start / samples_per_frame;
+            let side = contact_side(c3d, force_platform, frame, lab_wrenches.as_deref(), options);
+            let description = format!("Detected on force platform {}", force_platform + 1);
+            if start > 0 {
+                let time = sample_time(c3d, start);
+                events.push(gait_event("Foot Strike", &side, time, &description));
+            }
+            if end < loaded.len() {
+                let time = sample_time(c3d, end);
+                events.push(gait_event("Foot Off", &side, time, &description));
+            }
+        }
+    }
+    events.sort_by(|a, b| a.time.total_cmp(&b.time));
+    events
+}
+
+/// The half-open sample ranges in which `loaded` is true.
+fn contacts(loaded: &[bool]) -> Vec<(usize, usize)> {
+    let mut contacts = Vec::new();
+    let mut start = None;
+    for (sample, loaded) in loaded.iter().enumerate() {
+        match (start, loaded) {
+            (None, true) => start = Some(sample),
+            (Some(first), false) => {
+                contacts.push((first, sample));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(first) = start {
+        contacts.push((first, loaded.len()));
+    }
+    contacts
+}
+
+/// The side from the mapping in `options`, or of the heel marker closest to
+/// the centre of pressure when the contact starts.
+fn contact_side(
+    c3d: &C3d,
+    force_platform: usize,
+    frame: usize,
+    lab_wrenches: Option<&[LabWrench]>,
+    options: &ForceEventOptions,
+) -> String {
+    if let Some(side) = options.sides.get(&force_platform) {
+        return side.clone();
+    }
+    let center_of_pressure = match lab_wrenches.and_then(|wrenches| wrenches.get(frame)) {
+        Some(wrench) if wrench.center_of_pressure[0].is_finite() => wrench.center_of_pressure,
+        _ => return "General".to_string(),
+    };
+    let distance = |label: &str| {
+        let marker = c3d.points.labels.iter().position(|l| l.trim() == label)?;
+        let point = c3d.points.get(frame, marker)?.point;
+        // markers at the origin are gaps in the trajectory
+        if point == [0.0; 3] || point.iter().any(|x| !x.is_finite()) {
+            return None;
+        }
+        let dx = point[0] as f64 - center_of_pressure[0];
+        let dy = point[1] as f64 - center_of_pressure[1];
+        Some((dx * dx + dy * dy).sqrt())
+    };
+    let [left, right] = &options.heel_markers;
+    match (distance(left), distance(right)) {
+        (Some(left), Some(right)) if left <= right => "Left".to_string(),
+        (Some(_), Some(_)) | (None, Some(_)) => "Right".to_string(),
+        (Some(_), None) => "Left".to_string(),
+        (None, None) => "General".to_string(),
+    }
+}
+
+/// Finds the foot strikes and foot offs of both feet from the heel, toe and sacrum
+/// markers, with the algorithm in `options.method`.
+///
+/// Markers are looked up by their `POINT:LABELS`, and frames where a marker is at the
+/// origin are treated as gaps. A side is skipped if its markers are missing. The events
+/// are sorted by time.
+pub fn kinematic_events(c3d: &C3d, options: &KinematicEventOptions) -> Vec<Event> {
+    let frame_rate = c3d.points.frame_rate as f64;
+    if frame_rate <= 0.0 {
+        return Vec::new();
+    }
+    let min_frames = ((options.min_interval * frame_rate).round() as usize).max(1);
+    let sacrum = trajectory(c3d, &options.sacrum_markers);
+    let mut events = Vec::new();
+    for (index, side) in ["Left", "Right"].iter().enumerate() {
+        let heel = trajectory(c3d, &options.heel_markers[index..=index]);
+        let toe = trajectory(c3d, &options.toe_markers[index..=index]);
+        let (heel, toe) = match (heel, toe) {
+            (Some(heel), Some(toe)) => (heel, toe),
+            _ => continue,
+        };
+        let (strikes, offs) = match options.method {
+            KinematicMethod::Coordinate => {
+                let sacrum = match &sacrum {
+                    Some(sacrum) => sacrum,
+                    None => continue,
+                };
+                let direction = match progression(sacrum) {
+                    Some(direction) => direction,
+                    None => continue,
+                };
+                let heel = along(&heel, sacrum, direction, 1.0);
+                let toe = along(&toe, sacrum, direction, -1.0);
+                (maxima(&heel, min_frames), maxima(&toe, min_frames))
+            }
+            KinematicMethod::Velocity => {
+                let scale = frame_rate * metres_per_unit(&c3d.points.units);
+                // the heel slows below the threshold where its negated speed rises above it
+                let heel = speed(&heel, scale)
+                    .into_iter()
+                    .map(|speed| speed.map(|speed| -speed))
+                    .collect::<Vec<_>>();
+                let toe = speed(&toe, scale);
+                (
+                    crossings(&heel, -options.strike_velocity, min_frames),
+                    crossings(&toe, options.off_velocity, min_frames),
+                )
+            }
+        };
+        let description = "Detected from marker trajectories";
+        for (label, frames) in [("Foot Strike", strikes), ("Foot Off", offs)] {
+            for frame in frames {
+                let time = time_of_frame(
+                    c3d.points.first_frame as usize + frame,
+                    c3d.points.frame_rate,
+                );
+                events.push(gait_event(label, side, time, description));
+            }
+        }
+    }
+    events.sort_by(|a, b| a.time.total_cmp(&b.time));
+    events
+}
+
+/// The mean position of the markers with `labels` in every frame, or `None` if
+/// none of the labels are in the file.
+fn trajectory(c3d: &C3d, labels: &[String]) -> Option<Vec<Option<[f64; 3]>>> {
+    let markers = labels
+        .iter()
+        .filter_map(|label| {
+            c3d.points
+                .labels
+                .iter()
+                .position(|l| l.trim() == label.trim())
+        })
+        .collect::<Vec<usize>>();
+    if markers.is_empty() {
+        return None;
+    }
+    let frames = (0..c3d.points.rows())
+        .map(|frame| {
+            let mut sum = [0.0; 3];
+            for &marker in &markers {
+                let point = c3d.points.get(frame, marker)?.point;
+                // markers at the origin are gaps in the trajectory
+                if point == [0.0; 3] || point.iter().any(|x| !x.is_finite()) {
+                    return None;
+                }
+                for axis in 0..3 {
+                    sum[axis] += point[axis] as f64 / markers.len() as f64;
+                }
+            }
+            Some(sum)
+        })
+        .collect();
+    Some(frames)
+}
+
+/// The horizontal unit vector from the first to the last position of the sacrum.
+fn progression(sacrum: &[Option<[f64; 3]>]) -> Option<[f64; 2]> {
+    let first = sacrum.iter().flatten().next()?;
+    let last = sacrum.iter().flatten().last()?;
+    let (dx, dy) = (last[0] - first[0], last[1] - first[1]);
+    let length = (dx * dx + dy * dy).sqrt();
+    match length > 0.0 {
+        true => Some([dx / length, dy / length]),
+        false => None,
+    }
+}
+
+/// The distance of `marker` ahead of the sacrum along `direction`, multiplied by `sign`.
+fn along(
+    marker: &[Option<[f64; 3]>],
+    sacrum: &[Option<[f64; 3]>],
+    direction: [f64; 2],
+    sign: f64,
+) -> Vec<Option<f64>> {
+    marker
+        .iter()
+        .zip(sacrum)
+        .map(|(marker, sacrum)| {
+            let (marker, sacrum) = (marker.as_ref()?, sacrum.as_ref()?);
+            let ahead =
+                (marker[0] - sacrum[0]) * direction[0] + (marker[1] - sacrum[1]) * direction[1];
+            Some(sign * ahead)
+        })
+        .collect()
+}
+
+/// The frames that are the largest value within `window` frames on either side.
+/// Both neighbours of a frame must be present so that the ends of the data and of
+/// gaps are not mistaken for peaks.
+fn maxima(values: &[Option<f64>], window: usize) -> Vec<usize> {
+    (1..values.len().saturating_sub(1))
+        .filter(|&frame| {
+            let value = match (values[frame - 1], values[frame], values[frame + 1]) {
+                (Some(_), Some(value), Some(_)) => value,
+                _ => return false,
+            };
+            let before = frame.saturating_sub(window)..frame;
+            let after = frame + 1..(frame + window + 1).min(values.len());
+            before.flat_map(|i| values[i]).all(|other| other < value)
+                && after.flat_map(|i| values[i]).all(|other| other <= value)
+        })
+        .collect()
+}
+
+/// The speed of `marker` in every frame by central differences, multiplied by `scale`.
+fn speed(marker: &[Option<[f64; 3]>], scale: f64) -> Vec<Option<f64>> {
+    (0..marker.len())
+        .map(|frame| {
+            let before = marker.get(frame.checked_sub(1)?)?.as_ref()?;
+            let after = marker.get(frame + 1)?.as_ref()?;
+            let distance = (0..3)
+                .map(|axis| (after[axis] - before[axis]).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            Some(distance / 2.0 * scale)
+        })
+        .collect()
+}
+
+/// The frames where `values` rises above `threshold`, at least `min_frames` apart.
+fn crossings(values: &[Option<f64>], threshold: f64, min_frames: usize) -> Vec<usize> {
+    let mut frames: Vec<usize> = Vec::new();
+    for frame in 1..values.len() {
+        if let (Some(before), Some(value)) = (values[frame - 1], values[frame]) {
+            let apart = !matches!(frames.last(), Some(last) if frame - last < min_frames);
+            if before <= threshold && value > threshold && apart {
+                frames.push(frame);
+            }
+        }
+    }
+    frames
+}
+
+/// The length of one unit of `POINT:UNITS` in metres, millimetres if unknown.
+fn metres_per_unit(units: &[char; 4]) -> f64 {
+    match units.iter().collect::<String>().trim() {
+        "m" => 1.0,
+        "dm" => 0.1,
+        "cm" => 0.01,
+        _ => 0.001,
+    }
+}
+
+/// A detected event paired with the closest existing event of the same kind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventMatch {
+    /// The index of the detected event.
+    pub detected: usize,
+    /// The index of the existing event, or `None` if none is within the tolerance.
+    pub existing: Option<usize>,
+    /// The detected time minus the existing time in seconds.
+    pub difference: Option<f32>,
+}
+
+/// Pairs each detected event with the nearest unpaired existing event that has the same
+/// label and context and is at most `tolerance` seconds away. Labels and contexts are
+/// compared ignoring case and surrounding spaces.
+pub fn compare_events(detected: &[Event], existing: &[Event], tolerance: f32) -> Vec<EventMatch> {
+    let same = |a: &str, b: &str| a.trim().eq_ignore_ascii_case(b.trim());
+    let mut paired = vec![false; existing.len()];
+    detected
+        .iter()
+        .enumerate()
+        .map(|(index, event)| {
+            let nearest = existing
+                .iter()
+                .enumerate()
+                .filter(|(i, other)| {
+                    !paired[*i]
+                        && same(&event.label, &other.label)
+                        && same(&event.context, &other.context)
+                        && (event.time - other.time).abs() <= tolerance
+                })
+                .min_by(|(_, a), (_, b)| {
+                    (event.time - a.time)
+                        .abs()
+                        .total_cmp(&(event.time - b.time).abs())
+                })
+                .map(|(i, other)| (i, event.time - other.time));
+            if let Some((i, _)) = nearest {
+                paired[i] = true;
+            }
+            EventMatch {
+                detected: index,
+                existing: nearest.map(|(i, _)| i),
+                difference: nearest.map(|(_, difference)| difference),
+            }
+        })
+        .collect()
+}
+
+/// The time of an analog sample, measured like the frame times from frame 1.
+fn sample_time(c3d: &C3d, sample: usize) -> f32 {
+    let samples_per_frame = c3d.analog.samples_per_channel_per_frame as usize;
+    let frame = c3d.points.first_frame as usize + sample / samples_per_frame;
+    time_of_frame(frame, c3d.points.frame_rate)
+        + (sample % samples_per_frame) as f32 / c3d.analog.rate
+}
+
+fn gait_event(label: &str, side: &str, time: f32, description: &str) -> Event {
+    let abbreviation = match label {
+        "Foot Strike" => ['F', 'S'],
+        _ => ['F', 'O'],
+    };
+    Event {
+        id: [
+            side.chars().next().unwrap_or(' '),
+            abbreviation[0],
+            abbreviation[1],
+            ' ',
+        ],
+        label: label.to_string(),
+        display_flag: true,
+        time,
+        context: side.to_string(),
+        description: description.to_string(),
+        subject: String::new(),
+        icon_id: match label {
+            "Foot Strike" => 1,
+            _ => 2,
+        },
+        generic_flag: 0,
+    }
+}
@@ -19,11 +19,15 @@ pub mod c3d;
 pub mod data;
 pub mod events;
 pub mod forces;
+pub mod gait;
 pub mod manufacturer;
+pub mod options;
 pub mod parameters;
 pub mod points;
 mod processor;
+pub mod reader;
 pub mod seg;
+pub mod writer;
 pub mod builder;
 
 #[path = "file_formats/mod.rs"]
@@ -36,6 +40,7 @@ pub use builder::C3dBuilder;
 pub use c3d::C3d;
 pub use data::DataFormat;
 pub use data::MarkerPoint;
+pub use data::PrecisionLoss;
 pub use events::Event;
 pub use events::EventContext;
 pub use events::Events;
@@ -44,41 +49,56 @@ pub use forces::ForcePlatformCorners;
 pub use forces::ForcePlatformOrigin;
 pub use forces::ForcePlatformType;
 pub use forces::ForcePlatforms;
+pub use forces::LabWrench;
+pub use forces::Wrench;
 pub use manufacturer::Manufacturer;
 pub use manufacturer::ManufacturerVersion;
-pub use parameters::{Parameter, ParameterData, Parameters};
+pub use options::{
+    ForceEventOptions, KinematicEventOptions, KinematicMethod, LoadOptions, WriteOptions,
+    ZeroOptions,
+};
+pub use parameters::{NameMatching, Parameter, ParameterData, Parameters};
 pub use points::Points;
 pub use processor::Processor;
+pub use reader::C3dReader;
+pub use reader::Frame;
 pub use seg::Seg;
+pub use writer::C3dWriter;
 pub use file_formats::trc::Trc;
 pub use file_formats::sto::Sto;
 
 /// Contains the most commonly used types and functions from this crate.
 pub mod prelude {
     pub use crate::{
-        Analog, AnalogFormat, AnalogOffset, C3d, C3dParseError, C3dWriteError, Events,
-        ForcePlatform, ForcePlatformType, ForcePlatforms, Manufacturer, ManufacturerVersion,
-        MarkerPoint, Parameter, ParameterData, Parameters, Points, Processor, Seg, Sto, Trc
+        Analog, AnalogFormat, AnalogOffset, C3d, C3dEditError, C3dParseError, C3dReader,
+        C3dWarning, C3dWarningKind, C3dWriteError, C3dWriter, ErrorLocation, Events,
+        ForceEventOptions, ForcePlatform, ForcePlatformType, ForcePlatforms, KinematicEventOptions,
+        KinematicMethod, LabWrench, LoadOptions, Manufacturer, ManufacturerVersion, MarkerPoint,
+        NameMatching, Parameter, ParameterData, Parameters, Points, PrecisionLoss, Processor, Seg,
+        Sto, Trc, WriteOptions, Wrench, ZeroOptions
     };
 }
 
 /// Reports errors that occurred while parsing a C3D file.
 /// The error type is returned by the `load` and `from_bytes` methods.
+/// Variants that can be traced to a place in the file end with an
+/// `Option<Box<ErrorLocation>>`, see `C3dParseError::location`.
 #[derive(Debug)]
 pub enum C3dParseError {
     ReadError(std::io::Error),
     InsufficientBlocks(String),
     InvalidHeaderStartBlock,
-    InvalidParameterStartBlock,
-    InvalidParameterData,
-    InvalidDataStartBlock,
-    InvalidProcessorType,
-    InvalidDataType,
+    InvalidParameterStartBlock(Option<Box<ErrorLocation>>),
+    InvalidParameterData(Option<Box<ErrorLocation>>),
+    InvalidDataStartBlock(Option<Box<ErrorLocation>>),
+    InvalidProcessorType(Option<Box<ErrorLocation>>),
+    InvalidDataType(Option<Box<ErrorLocation>>),
     InvalidParametersOffset,
     InvalidDescription,
     MissingGroup(String),
     MissingParameter(String),
-    InvalidGroupId,
+    MissingLabel(String),
+    InvalidGroupId(Option<Box<ErrorLocation>>),
     MissingPointScale,
     FileNotOpen,
     NotEnoughData,
@@ -88,12 +108,12 @@ pub enum C3dParseError {
     GroupNotFound(String),
     ParameterNotFound(String, String),
     RequiredParameterNotFound(String),
-    InvalidData(Parameter, String),
-    InvalidParameterFormat(String),
+    InvalidData(Box<Parameter>, String, Option<Box<ErrorLocation>>),
+    InvalidParameterFormat(String, Option<Box<ErrorLocation>>),
     AnalogOffsetScaleMismatch,
     InsufficientAnalogOffsets,
-    InvalidParameterDimensions(String),
-    InvalidParameterType(String),
+    InvalidParameterDimensions(String, Option<Box<ErrorLocation>>),
+    InvalidParameterType(String, Option<Box<ErrorLocation>>),
     InvalidEventLabel(String, String),
     MissingEventTime(usize),
     MissingEventLabel(usize),
@@ -104,10 +124,296 @@ pub enum C3dParseError {
     ScaleFactorMismatch(f32, f32),
 }
 
-impl Error for C3dParseError {}
+impl C3dParseError {
+    /// Where in the file the error occurred, if it is known.
+    pub fn location(&self) -> Option<&ErrorLocation> {
+        self.location_field()?.as_deref()
+    }
+
+    fn location_field(&self) -> Option<&Option<Box<ErrorLocation>>> {
+        match self {
+            C3dParseError::InvalidParameterStartBlock(location)
+            | C3dParseError::InvalidParameterData(location)
+            | C3dParseError::InvalidDataStartBlock(location)
+            | C3dParseError::InvalidProcessorType(location)
+            | C3dParseError::InvalidDataType(location)
+            | C3dParseError::InvalidGroupId(location)
+            | C3dParseError::InvalidData(_, _, location)
+            | C3dParseError::InvalidParameterFormat(_, location)
+            | C3dParseError::InvalidParameterDimensions(_, location)
+            | C3dParseError::InvalidParameterType(_, location) => Some(location),
+            _ => None,
+        }
+    }
+
+    fn location_field_mut(&mut self) -> Option<&mut Option<Box<ErrorLocation>>> {
+        match self {
+            C3dParseError::InvalidParameterStartBlock(location)
+            | C3dParseError::InvalidParameterData(location)
+            | C3dParseError::InvalidDataStartBlock(location)
+            | C3dParseError::InvalidProcessorType(location)
+            | C3dParseError::InvalidDataType(location)
+            | C3dParseError::InvalidGroupId(location)
+            | C3dParseError::InvalidData(_, _, location)
+            | C3dParseError::InvalidParameterFormat(_, location)
+            | C3dParseError::InvalidParameterDimensions(_, location)
+            | C3dParseError::InvalidParameterType(_, location) => Some(location),
+            _ => None,
+        }
+    }
+
+    /// Sets the location of the error, unless it already has one.
+    pub(crate) fn at(mut self, location: ErrorLocation) -> C3dParseError {
+        if let Some(field) = self.location_field_mut() {
+            if field.is_none() {
+                *field = Some(Box::new(location));
+            }
+        }
+        self
+    }
+
+    /// Moves the location of the error by `offset` bytes.
+    pub(crate) fn offset_by(mut self, offset: usize) -> C3dParseError {
+        if let Some(field) = self.location_field_mut() {
+            *field = field
+                .take()
+                .map(|location| Box::new(location.offset_by(offset)));
+        }
+        self
+    }
+}
+
+impl Error for C3dParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            C3dParseError::ReadError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for C3dParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "C3dParseError: {:?}", self)
+        self.fmt_message(f)?;
+        match self.location() {
+            Some(location) => write!(f, " {}", location),
+            None => Ok(()),
+        }
+    }
+}
+
+impl C3dParseError {
+    fn fmt_message(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            C3dParseError::ReadError(error) => write!(f, "failed to read the file: {}", error),
+            C3dParseError::InsufficientBlocks(section) => {
+                write!(f, "the file ends before the {} section", section)
+            }
+            C3dParseError::InvalidHeaderStartBlock => write!(f, "invalid header start block"),
+            C3dParseError::InvalidParameterStartBlock(_) => {
+                write!(f, "invalid parameter start block")
+            }
+            C3dParseError::InvalidParameterData(_) => {
+                write!(f, "parameter data does not match its type and dimensions")
+            }
+            C3dParseError::InvalidDataStartBlock(_) => write!(f, "invalid data start block"),
+            C3dParseError::InvalidProcessorType(_) => write!(f, "unknown processor type"),
+            C3dParseError::InvalidDataType(_) => write!(f, "unknown parameter data type"),
+            C3dParseError::InvalidParametersOffset => write!(f, "invalid parameter offset"),
+            C3dParseError::InvalidDescription => write!(f, "invalid description"),
+            C3dParseError::MissingGroup(group) => write!(f, "missing group {}", group),
+            C3dParseError::MissingParameter(parameter) => {
+                write!(f, "missing parameter {}", parameter)
+            }
+            C3dParseError::MissingLabel(label) => write!(f, "no channel is labelled {}", label),
+            C3dParseError::InvalidGroupId(_) => write!(f, "parameter refers to an unknown group"),
+            C3dParseError::MissingPointScale => write!(f, "missing POINT:SCALE"),
+            C3dParseError::FileNotOpen => write!(f, "the file is not open"),
+            C3dParseError::NotEnoughData => write!(f, "the data section ends early"),
+            C3dParseError::InvalidNextParameter => {
+                write!(f, "invalid offset to the next parameter")
+            }
+            C3dParseError::TooManyEvents(events) => {
+                write!(f, "{} events do not fit in the header", events)
+            }
+            C3dParseError::NumFramesMismatch(expected, found) => {
+                write!(f, "expected {} frames, found {}", expected, found)
+            }
+            C3dParseError::GroupNotFound(group) => write!(f, "group {} not found", group),
+            C3dParseError::ParameterNotFound(group, parameter) => {
+                write!(f, "parameter {}:{} not found", group, parameter)
+            }
+            C3dParseError::RequiredParameterNotFound(parameter) => {
+                write!(f, "required parameter {} not found", parameter)
+            }
+            C3dParseError::InvalidData(parameter, expected, _) => write!(
+                f,
+                "parameter {} with dimensions {:?} cannot be read as {}",
+                parameter.name, parameter.dimensions, expected
+            ),
+            C3dParseError::InvalidParameterFormat(format, _) => {
+                write!(f, "invalid parameter format {}", format)
+            }
+            C3dParseError::AnalogOffsetScaleMismatch => {
+                write!(f, "ANALOG:OFFSET and ANALOG:SCALE have different lengths")
+            }
+            C3dParseError::InsufficientAnalogOffsets => {
+                write!(f, "ANALOG:OFFSET has fewer entries than ANALOG:USED")
+            }
+            C3dParseError::InvalidParameterDimensions(parameter, _) => {
+                write!(f, "invalid dimensions for parameter {}", parameter)
+            }
+            C3dParseError::InvalidParameterType(parameter, _) => {
+                write!(f, "invalid type for parameter {}", parameter)
+            }
+            C3dParseError::InvalidEventLabel(label, context) => {
+                write!(f, "invalid event label {} in {}", label, context)
+            }
+            C3dParseError::MissingEventTime(event) => write!(f, "missing time of event {}", event),
+            C3dParseError::MissingEventLabel(event) => {
+                write!(f, "missing label of event {}", event)
+            }
+            C3dParseError::NoParameterTimeEvents => write!(f, "no events in the parameters"),
+            C3dParseError::HeaderNotParsed => write!(f, "the header has not been parsed"),
+            C3dParseError::AnalogBytesPerFrameMismatch => write!(
+                f,
+                "the analog samples per frame in the header do not match ANALOG:USED"
+            ),
+            C3dParseError::FrameRateMismatch(header, parameter) => write!(
+                f,
+                "header frame rate {} does not match POINT:RATE {}",
+                header, parameter
+            ),
+            C3dParseError::ScaleFactorMismatch(header, parameter) => write!(
+                f,
+                "header scale factor {} does not match POINT:SCALE {}",
+                header, parameter
+            ),
+        }
+    }
+}
+
+/// The place in a C3D file where a `C3dParseError` occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorLocation {
+    /// Byte offset from the start of the file.
+    pub offset: usize,
+    /// One-based index of the 512-byte block that contains `offset`.
+    pub block: usize,
+    /// Group that was being read, if any.
+    pub group: Option<String>,
+    /// Parameter that was being read, if any.
+    pub parameter: Option<String>,
+}
+
+impl ErrorLocation {
+    pub(crate) fn new(offset: usize) -> Self {
+        ErrorLocation {
+            offset,
+            block: offset / 512 + 1,
+            group: None,
+            parameter: None,
+        }
+    }
+
+    pub(crate) fn group(mut self, group: &str) -> Self {
+        self.group = Some(group.to_string());
+        self
+    }
+
+    pub(crate) fn parameter(mut self, parameter: &str) -> Self {
+        self.parameter = Some(parameter.to_string());
+        self
+    }
+
+    fn offset_by(self, offset: usize) -> Self {
+        ErrorLocation {
+            group: self.group,
+            parameter: self.parameter,
+            ..ErrorLocation::new(self.offset + offset)
+        }
+    }
+}
+
+impl fmt::Display for ErrorLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at byte {} (block {})", self.offset, self.block)?;
+        match (&self.group, &self.parameter) {
+            (Some(group), Some(parameter)) => write!(f, " in {}:{}", group, parameter),
+            (Some(group), None) => write!(f, " in group {}", group),
+            (None, Some(parameter)) => write!(f, " in parameter {}", parameter),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Describes a correction that was applied while parsing a C3D file.
+/// Warnings are returned by the `load_with_diagnostics` method.
+#[derive(Debug, Clone, PartialEq)]
+pub struct C3dWarning {
+    pub kind: C3dWarningKind,
+    /// Group of the parameter that was corrected, if the warning refers to a parameter.
+    pub group: Option<String>,
+    /// Name of the parameter that was corrected, if the warning refers to a parameter.
+    pub parameter: Option<String>,
+    /// Byte offset in the file of the header word or parameter record that was corrected.
+    pub offset: Option<usize>,
+    /// What was assumed in place of the value in the file.
+    pub assumed: String,
+}
+
+/// The kind of correction described by a `C3dWarning`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum C3dWarningKind {
+    /// A parameter was not found and a default was assumed.
+    MissingParameter,
+    /// A parameter could not be interpreted and a default was assumed.
+    InvalidParameter,
+    /// The header disagrees with the parameters.
+    HeaderMismatch,
+    /// Two parameters disagree with each other.
+    ParameterMismatch,
+    /// The data section holds fewer frames than declared.
+    TruncatedData,
+}
+
+impl fmt::Display for C3dWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.kind)?;
+        if let (Some(group), Some(parameter)) = (&self.group, &self.parameter) {
+            write!(f, " in {}:{}", group, parameter)?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " at byte {}", offset)?;
+        }
+        write!(f, ", assumed {}", self.assumed)
+    }
+}
+
+/// Reports changes to the parameters that were refused.
+#[derive(Debug, Clone, PartialEq)]
+pub enum C3dEditError {
+    ParameterNotFound(String, String),
+    LockedParameter(String, String),
+    LockedGroup(String),
+    NameCollision(String, String),
+}
+
+impl Error for C3dEditError {}
+impl fmt::Display for C3dEditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            C3dEditError::ParameterNotFound(group, parameter) => {
+                write!(f, "parameter {}:{} not found", group, parameter)
+            }
+            C3dEditError::LockedParameter(group, parameter) => {
+                write!(f, "parameter {}:{} is locked", group, parameter)
+            }
+            C3dEditError::LockedGroup(group) => write!(f, "group {} is locked", group),
+            C3dEditError::NameCollision(existing, name) => {
+                write!(f, "{} would be read as the existing {}", name, existing)
+            }
+        }
     }
 }
 
@@ -129,6 +435,10 @@ pub enum C3dWriteError {
     InvalidParameterDimensions(String),
     ParameterDescriptionTooLong(String),
     InvalidForcePlatformInfo(String),
+    InvalidFrameSize(String),
+    InsufficientParameterBlocks(String),
+    NameCollision(String, String),
+    IntegerOverflow(String),
 }
 
 impl Error for C3dWriteError {}
@@ -54,18 +54,18 @@ impl Manufacturer {
     }
 
     pub(crate) fn from_parameters(parameters: &mut Parameters) -> Result<Self, C3dParseError> {
-        let company = parameters.remove("MANUFACTURER", "COMPANY");
+        let company = parameters.take("MANUFACTURER", "COMPANY");
         let company: Option<String> = match company {
             None => None,
             Some(parameter) => Some(parameter.as_ref().try_into()?),
         };
-        let software = parameters.remove("MANUFACTURER", "SOFTWARE");
+        let software = parameters.take("MANUFACTURER", "SOFTWARE");
         let software: Option<String> = match software {
             None => None,
             Some(parameter) => Some(parameter.as_ref().try_into()?),
         };
         let version = get_manufacturer_version(parameters);
-        let edited = parameters.remove("MANUFACTURER", "EDITED");
+        let edited = parameters.take("MANUFACTURER", "EDITED");
         let edited: Option<Vec<String>> = match edited {
             None => None,
             Some(parameter) => Some(parameter.as_ref().try_into()?),
@@ -143,7 +143,7 @@ impl Manufacturer {
 }
 
 fn get_manufacturer_version(parameters: &mut Parameters) -> Option<ManufacturerVersion> {
-    let version = parameters.remove("MANUFACTURER", "VERSION");
+    let version = parameters.take("MANUFACTURER", "VERSION");
     if version.is_none() {
         None
     } else {
@@ -0,0 +1,420 @@
+//! Options that control how a C3D file is loaded, written and processed.
+use crate::parameters::{NameMatching, Parameters};
+use crate::processor::Processor;
+use crate::{C3dParseError, C3dWarning, C3dWarningKind, ErrorLocation};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Restricts which parts of the data section are decoded by `C3d::load_with`.
+///
+/// By default every frame, marker and analog channel is loaded.
+///
+/// # Examples
+/// ```
+/// use c3dio::prelude::*;
+///
+/// let options = LoadOptions::new()
+///     .frames(10..20)
+///     .analog_labels(&["F1Z", "F2Z"]);
+/// assert_eq!(options.frames, Some(10..20));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadOptions {
+    /// Zero-based indices of the frames to load, relative to the first frame of the data section.
+    /// The range is clamped to the frames in the file.
+    pub frames: Option<Range<usize>>,
+    /// Labels of the markers to load, in the order they should appear in `points`.
+    pub point_labels: Option<Vec<String>>,
+    /// Labels of the analog channels to load, in the order they should appear in `analog`.
+    pub analog_labels: Option<Vec<String>>,
+    /// Recover from known defects in real-world files instead of returning an error:
+    /// - a missing `ANALOG:RATE` is derived from the header frame rate
+    ///   and the number of analog samples per frame
+    /// - `ANALOG:USED` is clamped to the number of `ANALOG:LABELS`
+    /// - the number of analog samples per frame in the header is recomputed
+    ///   from `ANALOG:USED` when the two disagree
+    /// - missing `ANALOG:SCALE` and `ANALOG:OFFSET` entries default to 1 and 0
+    /// - Type 3 force platforms with only 6 `FORCE_PLATFORM:CHANNEL` entries are accepted
+    ///
+    /// Files with fewer frames than `POINT:FRAMES` are always truncated to the available frames.
+    pub lenient: bool,
+    /// How group and parameter names are matched while the file is read
+    /// and by the `Parameters` of the loaded file.
+    /// Files written by older software may shorten names to six characters,
+    /// with `NameMatching::Legacy` a `POINT:DESCRI` parameter is found as `POINT:DESCRIPTIONS`.
+    pub name_matching: NameMatching,
+}
+
+impl LoadOptions {
+    pub fn new() -> Self {
+        LoadOptions::default()
+    }
+
+    /// Only load the frames in `frames`.
+    pub fn frames(mut self, frames: Range<usize>) -> Self {
+        self.frames = Some(frames);
+        self
+    }
+
+    /// Only load the markers with the given `POINT:LABELS`.
+    pub fn point_labels(mut self, labels: &[&str]) -> Self {
+        self.point_labels = Some(labels.iter().map(|label| label.to_string()).collect());
+        self
+    }
+
+    /// Only load the analog channels with the given `ANALOG:LABELS`.
+    pub fn analog_labels(mut self, labels: &[&str]) -> Self {
+        self.analog_labels = Some(labels.iter().map(|label| label.to_string()).collect());
+        self
+    }
+
+    /// Recover from known file defects, see `LoadOptions::lenient`.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Match names as set by `name_matching`, see `LoadOptions::name_matching`.
+    pub fn name_matching(mut self, name_matching: NameMatching) -> Self {
+        self.name_matching = name_matching;
+        self
+    }
+}
+
+/// Controls how `C3d::write_with` encodes a C3D file.
+///
+/// By default a file is written with the processor type it was read with.
+///
+/// # Examples
+/// ```
+/// use c3dio::prelude::*;
+///
+/// let c3d = C3d::load("tests/data/short.c3d").unwrap();
+/// let options = WriteOptions::new().processor(Processor::SgiMips);
+/// let bytes = c3d.to_bytes_with(&options).unwrap();
+/// assert_eq!(C3d::from_bytes(&bytes).unwrap().processor(), Processor::SgiMips);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WriteOptions {
+    /// The byte order and float format of the written file,
+    /// `None` keeps the processor type of the `C3d`.
+    pub processor: Option<Processor>,
+}
+
+impl WriteOptions {
+    pub fn new() -> Self {
+        WriteOptions::default()
+    }
+
+    /// Write the file for `processor`.
+    pub fn processor(mut self, processor: Processor) -> Self {
+        self.processor = Some(processor);
+        self
+    }
+}
+
+/// Controls how `C3d::zero_force_platforms` removes the baseline of the force platform channels.
+///
+/// By default the frames in `FORCE_PLATFORM:ZERO` are averaged and the offsets are
+/// kept with the force platforms, leaving `analog` unchanged.
+///
+/// # Examples
+/// ```
+/// use c3dio::prelude::*;
+///
+/// let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+/// let options = ZeroOptions::new().frames(0..5);
+/// let offsets = c3d.zero_force_platforms(&options).unwrap();
+/// assert_eq!(offsets.len(), c3d.forces.len());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ZeroOptions {
+    /// Zero-based indices of the quiet frames to average, `None` uses `FORCE_PLATFORM:ZERO`.
+    pub frames: Option<Range<usize>>,
+    /// Subtract the offsets from the force platform channels in `analog`
+    /// instead of keeping them in `ForcePlatform::zero_offsets`.
+    pub write_back: bool,
+}
+
+impl ZeroOptions {
+    pub fn new() -> Self {
+        ZeroOptions::default()
+    }
+
+    /// Average the frames in `frames` instead of `FORCE_PLATFORM:ZERO`.
+    pub fn frames(mut self, frames: Range<usize>) -> Self {
+        self.frames = Some(frames);
+        self
+    }
+
+    /// Subtract the offsets from `analog`, see `ZeroOptions::write_back`.
+    pub fn write_back(mut self, write_back: bool) -> Self {
+        self.write_back = write_back;
+        self
+    }
+}
+
+/// Controls how `C3d::detect_force_events` finds foot strikes and foot offs.
+///
+/// By default a foot is in contact with a plate while the vertical force is above 20 N
+/// for at least 50 ms, and the side is taken from the `LHEE` or `RHEE` marker
+/// closest to the centre of pressure.
+///
+/// # Examples
+/// ```
+/// use c3dio::prelude::*;
+///
+/// let options = ForceEventOptions::new().threshold(30.0).side(0, "Left");
+/// assert_eq!(options.sides[&0], "Left");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForceEventOptions {
+    /// The vertical force in newtons above which a foot is in contact with a plate.
+    pub threshold: f64,
+    /// The shortest contact in seconds, shorter contacts are treated as noise.
+    pub min_duration: f64,
+    /// The event context of the contacts on a plate, by zero-based plate index.
+    pub sides: HashMap<usize, String>,
+    /// The labels of the left and right heel markers used for plates without a side.
+    pub heel_markers: [String; 2],
+}
+
+impl Default for ForceEventOptions {
+    fn default() -> Self {
+        ForceEventOptions {
+            threshold: 20.0,
+            min_duration: 0.05,
+            sides: HashMap::new(),
+            heel_markers: ["LHEE".to_string(), "RHEE".to_string()],
+        }
+    }
+}
+
+impl ForceEventOptions {
+    pub fn new() -> Self {
+        ForceEventOptions::default()
+    }
+
+    /// Detect contacts above `threshold` newtons.
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Ignore contacts shorter than `min_duration` seconds.
+    pub fn min_duration(mut self, min_duration: f64) -> Self {
+        self.min_duration = min_duration;
+        self
+    }
+
+    /// Use `side` as the context of every contact on `force_platform`.
+    pub fn side(mut self, force_platform: usize, side: &str) -> Self {
+        self.sides.insert(force_platform, side.to_string());
+        self
+    }
+
+    /// Find the side of a contact from the heel markers with these labels.
+    pub fn heel_markers(mut self, left: &str, right: &str) -> Self {
+        self.heel_markers = [left.to_string(), right.to_string()];
+        self
+    }
+}
+
+/// The algorithm used by `C3d::detect_kinematic_events`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KinematicMethod {
+    /// Foot strike when the heel is furthest ahead of the sacrum and foot off when
+    /// the toe is furthest behind it, along the direction of progression (Zeni et al. 2008).
+    #[default]
+    Coordinate,
+    /// Foot strike when the heel slows below `strike_velocity` and foot off when
+    /// the toe speeds up above `off_velocity`.
+    Velocity,
+}
+
+/// Controls how `C3d::detect_kinematic_events` finds foot strikes and foot offs
+/// from the marker trajectories.
+///
+/// By default the coordinate method is used with the `LHEE`, `RHEE`, `LTOE`, `RTOE`
+/// and `SACR` markers.
+///
+/// # Examples
+/// ```
+/// use c3dio::prelude::*;
+///
+/// let options = KinematicEventOptions::new()
+///     .method(KinematicMethod::Velocity)
+///     .sacrum_markers(&["LPSI", "RPSI"]);
+/// assert_eq!(options.sacrum_markers.len(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct KinematicEventOptions {
+    pub method: KinematicMethod,
+    /// The labels of the left and right heel markers.
+    pub heel_markers: [String; 2],
+    /// The labels of the left and right toe markers.
+    pub toe_markers: [String; 2],
+    /// The labels of the pelvis markers whose mean position is used as the sacrum.
+    pub sacrum_markers: Vec<String>,
+    /// The speed of the heel in metres per second below which the foot has landed.
+    pub strike_velocity: f64,
+    /// The speed of the toe in metres per second above which the foot has left the ground.
+    pub off_velocity: f64,
+    /// The shortest time in seconds between two events of the same kind on one side.
+    pub min_interval: f64,
+}
+
+impl Default for KinematicEventOptions {
+    fn default() -> Self {
+        KinematicEventOptions {
+            method: KinematicMethod::Coordinate,
+            heel_markers: ["LHEE".to_string(), "RHEE".to_string()],
+            toe_markers: ["LTOE".to_string(), "RTOE".to_string()],
+            sacrum_markers: vec!["SACR".to_string()],
+            strike_velocity: 0.5,
+            off_velocity: 0.5,
+            min_interval: 0.3,
+        }
+    }
+}
+
+impl KinematicEventOptions {
+    pub fn new() -> Self {
+        KinematicEventOptions::default()
+    }
+
+    pub fn method(mut self, method: KinematicMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Use the heel markers with these labels.
+    pub fn heel_markers(mut self, left: &str, right: &str) -> Self {
+        self.heel_markers = [left.to_string(), right.to_string()];
+        self
+    }
+
+    /// Use the toe markers with these labels.
+    pub fn toe_markers(mut self, left: &str, right: &str) -> Self {
+        self.toe_markers = [left.to_string(), right.to_string()];
+        self
+    }
+
+    /// Use the mean position of the markers with these labels as the sacrum.
+    pub fn sacrum_markers(mut self, labels: &[&str]) -> Self {
+        self.sacrum_markers = labels.iter().map(|label| label.to_string()).collect();
+        self
+    }
+
+    /// Set the heel and toe speeds in metres per second of the velocity method.
+    pub fn velocities(mut self, strike_velocity: f64, off_velocity: f64) -> Self {
+        self.strike_velocity = strike_velocity;
+        self.off_velocity = off_velocity;
+        self
+    }
+
+    /// Ignore events closer than `min_interval` seconds to the previous event
+    /// of the same kind on the same side.
+    pub fn min_interval(mut self, min_interval: f64) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+}
+
+/// Settings and collected warnings shared by the parse functions while a file is loaded.
+#[derive(Debug, Default)]
+pub(crate) struct ParseContext {
+    pub(crate) lenient: bool,
+    pub(crate) name_matching: NameMatching,
+    /// Byte offset of the parameter section in the file.
+    pub(crate) parameter_start: usize,
+    pub(crate) warnings: Vec<C3dWarning>,
+}
+
+impl ParseContext {
+    pub(crate) fn new(options: &LoadOptions) -> Self {
+        ParseContext {
+            lenient: options.lenient,
+            name_matching: options.name_matching,
+            ..Default::default()
+        }
+    }
+
+    /// Records a correction to a parameter.
+    pub(crate) fn warn_parameter(
+        &mut self,
+        kind: C3dWarningKind,
+        parameters: &Parameters,
+        group: &str,
+        parameter: &str,
+        assumed: String,
+    ) {
+        let offset = parameters
+            .offset(group, parameter)
+            .map(|offset| self.parameter_start + offset);
+        self.warnings.push(C3dWarning {
+            kind,
+            group: Some(group.to_string()),
+            parameter: Some(parameter.to_string()),
+            offset,
+            assumed,
+        });
+    }
+
+    /// Records a correction that is not tied to a location in the file.
+    pub(crate) fn warn(&mut self, kind: C3dWarningKind, assumed: String) {
+        self.warnings.push(C3dWarning {
+            kind,
+            group: None,
+            parameter: None,
+            offset: None,
+            assumed,
+        });
+    }
+
+    /// Records a correction to the header word at `offset`.
+    pub(crate) fn warn_header(&mut self, kind: C3dWarningKind, offset: usize, assumed: String) {
+        self.warnings.push(C3dWarning {
+            kind,
+            group: None,
+            parameter: None,
+            offset: Some(offset),
+            assumed,
+        });
+    }
+
+    /// Adds the location of the parameter named by `error` to an error raised
+    /// while reading the parameters of `groups`.
+    pub(crate) fn locate(
+        &self,
+        error: C3dParseError,
+        parameters: &Parameters,
+        groups: &[&str],
+    ) -> C3dParseError {
+        let (group, name) = match &error {
+            C3dParseError::InvalidData(parameter, _, _) => (None, parameter.name.clone()),
+            C3dParseError::InvalidParameterType(name, _)
+            | C3dParseError::InvalidParameterFormat(name, _)
+            | C3dParseError::InvalidParameterDimensions(name, _) => match name.split_once(':') {
+                Some((group, name)) => (Some(group.to_string()), name.to_string()),
+                None => return error,
+            },
+            _ => return error,
+        };
+        let location = groups
+            .iter()
+            .copied()
+            .filter(|candidate| group.as_deref().is_none_or(|group| group == *candidate))
+            .find_map(|group| {
+                parameters.offset(group, &name).map(|offset| {
+                    ErrorLocation::new(self.parameter_start + offset)
+                        .group(group)
+                        .parameter(&name)
+                })
+            });
+        match location {
+            Some(location) => error.at(location),
+            None => error,
+        }
+    }
+}
@@ -1,8 +1,9 @@
 //! Logic for parsing and writing parameters.
 use crate::processor::Processor;
-use crate::{C3dParseError, C3dWriteError};
+use crate::{C3dEditError, C3dParseError, C3dWriteError, ErrorLocation};
 use grid::Grid;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
 
 /// The parameters of a C3D file are stored in a `Parameters` struct.
 /// Each group of parameters is stored in a separate struct.
@@ -11,7 +12,65 @@ use std::collections::HashMap;
 /// The value is a tuple of the parameter data and the description.
 #[derive(Debug, Clone, Default)]
 pub struct Parameters {
-    parameters: HashMap<String, (String, HashMap<String, Parameter>)>,
+    parameters: HashMap<String, Group>,
+    /// Offsets of the parsed parameter records from the start of the parameter section.
+    offsets: HashMap<String, HashMap<String, usize>>,
+    /// Layout of the parsed parameter section, used to write unchanged records as they were read.
+    layout: Option<ParameterLayout>,
+    locked_groups: HashSet<String>,
+    name_matching: NameMatching,
+}
+
+/// The description and the parameters of a group.
+type Group = (String, HashMap<String, Parameter>);
+
+/// How the lookup methods of `Parameters` match group and parameter names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NameMatching {
+    /// Names match when they are equal.
+    #[default]
+    Exact,
+    /// Names match when their first six characters are equal, ignoring case,
+    /// as readers of the original C3D format compare them.
+    /// A name that matches a stored name completely, ignoring case, is preferred.
+    ///
+    /// In this mode `Parameters::insert` refuses names that match another parameter
+    /// of the group and writing refuses groups and parameters that match each other.
+    Legacy,
+}
+
+/// The order, group ids and bytes of the records in a parsed parameter section.
+#[derive(Debug, Clone)]
+struct ParameterLayout {
+    /// The first three bytes of the parameter section.
+    header: [u8; 3],
+    /// Number of 512-byte blocks in the parameter section.
+    blocks: usize,
+    /// Ids of all parsed groups, including groups that only parameters refer to.
+    group_ids: HashMap<String, usize>,
+    /// The byte order of the record bytes.
+    processor: Processor,
+    records: Vec<LayoutRecord>,
+    /// The parameters the typed structs such as `Points` read from the file.
+    consumed: HashSet<(String, String)>,
+    /// The parameters the typed structs wrote right after parsing, so that the ones
+    /// that were not changed since can be told apart. Encoded on the first write.
+    parsed_typed: OnceLock<HashMap<(String, String), Parameter>>,
+}
+
+#[derive(Debug, Clone)]
+enum LayoutRecord {
+    Group {
+        name: String,
+        description: String,
+        locked: bool,
+        bytes: Vec<u8>,
+    },
+    Parameter {
+        group: String,
+        parameter: Parameter,
+        bytes: Vec<u8>,
+    },
 }
 
 impl ToString for Parameters {
@@ -51,73 +110,295 @@ impl Parameters {
         parameter_blocks: &Vec<u8>,
         processor: &Processor,
     ) -> Result<Self, C3dParseError> {
-        Ok(Parameters {
-            parameters: parse_parameters(parameter_blocks, processor)?,
-        })
+        parse_parameters(parameter_blocks, processor)
     }
 
-    pub(crate) fn write_groups(
-        &self,
-        processor: &Processor,
-    ) -> Result<(Vec<u8>, HashMap<String, usize>), C3dWriteError> {
-        let mut bytes = Vec::new();
-        let mut group_names_to_ids = HashMap::new();
-        let mut group_id = 1;
-        for (group, (group_description, _parameters)) in self.parameters.iter() {
-            if group.len() > 127 {
-                return Err(C3dWriteError::GroupNameTooLong(group.clone()));
-            }
-            bytes.push(group.len() as u8);
-            bytes.push(-(group_id as i8) as u8);
-            if !group.is_ascii() {
-                return Err(C3dWriteError::GroupNameNotAscii(group.clone()));
-            }
-            bytes.extend(group.to_ascii_uppercase().as_bytes());
-            if group_description.as_bytes().len() > 255 {
-                return Err(C3dWriteError::GroupDescriptionTooLong(
-                    group_description.clone(),
-                ));
-            }
-            let bytes_to_end = processor.u16_to_bytes(group_description.len() as u16 + 2);
-            bytes.extend(bytes_to_end);
-            bytes.extend(group_description.as_bytes());
+    /// Offset of a parsed parameter record from the start of the parameter section.
+    /// The offset is kept after the parameter is removed.
+    pub(crate) fn offset(&self, group: &str, parameter: &str) -> Option<usize> {
+        let group = find_name(self.offsets.keys(), group, self.name_matching)?;
+        let parameters = &self.offsets[group];
+        find_name(parameters.keys(), parameter, self.name_matching).map(|name| parameters[name])
+    }
+
+    pub fn name_matching(&self) -> NameMatching {
+        self.name_matching
+    }
+
+    /// Sets how group and parameter names are matched, see `NameMatching`.
+    pub fn set_name_matching(&mut self, name_matching: NameMatching) {
+        self.name_matching = name_matching;
+    }
+
+    /// The stored name of `group`, or `group` if no stored name matches.
+    fn group_name(&self, group: &str) -> String {
+        find_name(self.parameters.keys(), group, self.name_matching)
+            .cloned()
+            .unwrap_or_else(|| group.to_string())
+    }
+
+    /// The stored names of `group` and `parameter`, or the names themselves if no stored name matches.
+    fn parameter_name(&self, group: &str, parameter: &str) -> (String, String) {
+        let group = self.group_name(group);
+        let parameter = self
+            .parameters
+            .get(&group)
+            .and_then(|(_, parameters)| find_name(parameters.keys(), parameter, self.name_matching))
+            .cloned()
+            .unwrap_or_else(|| parameter.to_string());
+        (group, parameter)
+    }
 
-            group_names_to_ids.insert(group.clone(), group_id);
-            group_id += 1;
+    /// The first two bytes of the parameter section and its minimum number of blocks.
+    /// Parsed parameters keep the values read from the file.
+    pub(crate) fn section_header(&self) -> ([u8; 2], usize) {
+        match &self.layout {
+            Some(layout) => ([layout.header[0], layout.header[1]], layout.blocks),
+            None => ([0, 0], 0),
         }
-        Ok((bytes, group_names_to_ids))
     }
 
-    pub(crate) fn write_parameters(
+    /// Assigns an id to every group that can be written.
+    /// Groups that were read from a file keep their ids, other groups are numbered after them.
+    pub(crate) fn group_ids(&self) -> HashMap<String, usize> {
+        let mut group_ids = HashMap::new();
+        if let Some(layout) = &self.layout {
+            for (group, id) in &layout.group_ids {
+                if self.parameters.contains_key(group) {
+                    group_ids.insert(group.clone(), *id);
+                }
+            }
+        }
+        let mut other_groups: Vec<&str> = self
+            .parameters
+            .keys()
+            .map(|group| group.as_str())
+            .filter(|group| !REQUIRED_GROUPS.contains(group))
+            .collect();
+        other_groups.sort();
+        let mut next_id = group_ids.values().max().copied().unwrap_or(0) + 1;
+        for group in REQUIRED_GROUPS.iter().copied().chain(other_groups) {
+            if !group_ids.contains_key(group) {
+                group_ids.insert(group.to_string(), next_id);
+                next_id += 1;
+            }
+        }
+        group_ids
+    }
+
+    /// Writes the group and parameter records of the parameter section.
+    ///
+    /// `typed_records` are the records written by the typed structs such as `Points`,
+    /// they replace the parameters with the same name.
+    /// Records that were read from a file and still hold the same values are written
+    /// byte for byte in their original order, all other records follow them.
+    /// A typed record holds the same value when the typed struct read the parameter
+    /// from the file and writes it as it did right after parsing.
+    /// When writing for another processor than the file was read with,
+    /// the records keep their order but are written anew.
+    pub(crate) fn write_records(
         &self,
         processor: &Processor,
-        group_names_to_ids: &HashMap<String, usize>,
+        group_ids: &HashMap<String, usize>,
+        typed_records: &[u8],
     ) -> Result<Vec<u8>, C3dWriteError> {
-        let mut bytes = Vec::new();
-        for (i, (group, (_, parameters))) in self.parameters.iter().enumerate() {
-            let group_id = group_names_to_ids.get(group).unwrap();
-            for (j, (parameter_name, parameter)) in parameters.iter().enumerate() {
-                bytes.extend(parameter.write(
+        let id_to_group: HashMap<usize, &String> =
+            group_ids.iter().map(|(group, id)| (*id, group)).collect();
+        let mut typed = split_records(typed_records, processor, &id_to_group);
+        self.check_name_collisions(&typed)?;
+        let mut records: Vec<Vec<u8>> = Vec::new();
+        let mut written: HashSet<(String, String)> = HashSet::new();
+        let mut written_groups: HashSet<String> = HashSet::new();
+
+        if let Some(layout) = &self.layout {
+            let same_processor = layout.processor == *processor;
+            for record in &layout.records {
+                match record {
+                    LayoutRecord::Group {
+                        name,
+                        description,
+                        locked,
+                        bytes,
+                    } => {
+                        let current_description = match self.parameters.get(name) {
+                            Some((current_description, _)) => current_description,
+                            None => continue,
+                        };
+                        if !written_groups.insert(name.clone()) {
+                            if same_processor {
+                                records.push(bytes.clone());
+                            }
+                            continue;
+                        }
+                        let current_locked = self.is_group_locked(name);
+                        records.push(
+                            match same_processor
+                                && current_description == description
+                                && current_locked == *locked
+                            {
+                                true => bytes.clone(),
+                                false => write_group(
+                                    processor,
+                                    name,
+                                    group_ids[name],
+                                    current_description,
+                                    current_locked,
+                                )?,
+                            },
+                        );
+                    }
+                    LayoutRecord::Parameter {
+                        group,
+                        parameter,
+                        bytes,
+                    } => {
+                        let key = (group.clone(), parameter.name.clone());
+                        if written.contains(&key) {
+                            continue;
+                        }
+                        let typed_index = typed.iter().position(|(typed_group, value, _)| {
+                            typed_group == group && value.name == parameter.name
+                        });
+                        if let Some(typed_index) = typed_index {
+                            let (_, value, mut typed_bytes) = typed.remove(typed_index);
+                            // typed structs do not know about locks, keep the lock of the file
+                            typed_bytes[0] = name_length(&parameter.name, parameter.locked);
+                            let unchanged = self.consumed(group, &parameter.name)
+                                && self.unchanged_since_parse(group, &value);
+                            records.push(match same_processor && unchanged {
+                                true => bytes.clone(),
+                                false => typed_bytes,
+                            });
+                        } else if let Some(value) = self.get(group, &parameter.name) {
+                            records.push(match same_processor && value == parameter {
+                                true => bytes.clone(),
+                                false => value.write(
+                                    processor,
+                                    parameter.name.clone(),
+                                    group_ids[group],
+                                    false,
+                                )?,
+                            });
+                        } else {
+                            continue;
+                        }
+                        written.insert(key);
+                    }
+                }
+            }
+            // parameters the file did not have are only added once they are changed
+            // from the values the typed structs assumed for them
+            typed.retain(|(group, value, _)| !self.unchanged_since_parse(group, value));
+        }
+
+        let mut new_groups: Vec<(&String, &usize)> = group_ids
+            .iter()
+            .filter(|(group, _)| !written_groups.contains(*group))
+            .collect();
+        new_groups.sort_by_key(|(_, id)| **id);
+        for (group, id) in new_groups {
+            let has_typed_records = typed.iter().any(|(typed_group, _, _)| typed_group == group);
+            let (description, has_new_parameters) = match self.parameters.get(group) {
+                Some((description, parameters)) => (
+                    description.as_str(),
+                    parameters
+                        .keys()
+                        .any(|name| !written.contains(&(group.clone(), name.clone()))),
+                ),
+                None => ("", false),
+            };
+            // required groups and groups that only parameters of the file referred to
+            // are only written when they hold new parameters,
+            // so that parsed files without them are written unchanged
+            let parsed_group = self
+                .layout
+                .as_ref()
+                .is_some_and(|layout| layout.group_ids.contains_key(group));
+            let user_group = self.parameters.contains_key(group)
+                && !REQUIRED_GROUPS.contains(&group.as_str())
+                && !parsed_group;
+            if has_typed_records || has_new_parameters || user_group {
+                records.push(write_group(
                     processor,
-                    parameter_name.clone(),
-                    *group_id,
-                    j == parameters.len() - 1 && i == self.parameters.len() - 1,
+                    group,
+                    *id,
+                    description,
+                    self.is_group_locked(group),
                 )?);
             }
         }
+        for (group, value, bytes) in typed {
+            written.insert((group, value.name));
+            records.push(bytes);
+        }
+        let mut groups: Vec<(&String, &Group)> = self.parameters.iter().collect();
+        groups.sort_by_key(|(group, _)| group_ids[*group]);
+        for (group, (_, parameters)) in groups {
+            let mut parameters: Vec<(&String, &Parameter)> = parameters.iter().collect();
+            parameters.sort_by_key(|(name, _)| *name);
+            for (name, parameter) in parameters {
+                if written.insert((group.clone(), name.clone())) {
+                    records.push(parameter.write(
+                        processor,
+                        name.clone(),
+                        group_ids[group],
+                        false,
+                    )?);
+                }
+            }
+        }
+
+        // every record points to the next one, the last record points to nothing
+        // unless it is the unchanged last record of the file
+        let last_bytes = self
+            .layout
+            .as_ref()
+            .filter(|layout| layout.processor == *processor)
+            .and_then(|layout| layout.records.last())
+            .map(|record| match record {
+                LayoutRecord::Group { bytes, .. } => bytes,
+                LayoutRecord::Parameter { bytes, .. } => bytes,
+            });
+        let num_records = records.len();
+        let mut bytes = Vec::new();
+        for (i, mut record) in records.into_iter().enumerate() {
+            let pointer = 2 + (record[0] as i8).unsigned_abs() as usize;
+            let next = match i + 1 == num_records {
+                true if last_bytes == Some(&record) => None,
+                true => Some(0),
+                false => Some(record.len() - pointer),
+            };
+            if let Some(next) = next {
+                record[pointer..pointer + 2].copy_from_slice(&processor.u16_to_bytes(next as u16));
+            }
+            bytes.extend(record);
+        }
         Ok(bytes)
     }
 
     pub fn get(&self, group: &str, parameter: &str) -> Option<&Parameter> {
-        self.parameters
-            .get(group)
-            .and_then(|(_, group)| group.get(parameter))
+        let (_, parameters) = find_entry(&self.parameters, group, self.name_matching)?;
+        find_entry(parameters, parameter, self.name_matching)
     }
 
+    /// Returns `None` for locked parameters and parameters in locked groups,
+    /// use `force_get_mut` to change them.
     pub fn get_mut(&mut self, group: &str, parameter: &str) -> Option<&mut Parameter> {
+        match self.check_unlocked(group, parameter) {
+            Ok(()) => self.force_get_mut(group, parameter),
+            Err(_) => None,
+        }
+    }
+
+    /// Gets a parameter for changing it even if it is locked.
+    pub fn force_get_mut(&mut self, group: &str, parameter: &str) -> Option<&mut Parameter> {
+        if self.name_matching == NameMatching::Exact {
+            return self.parameters.get_mut(group)?.1.get_mut(parameter);
+        }
+        let (group, parameter) = self.parameter_name(group, parameter);
         self.parameters
-            .get_mut(group)
-            .and_then(|(_, group)| group.get_mut(parameter))
+            .get_mut(&group)
+            .and_then(|(_, group)| group.get_mut(&parameter))
     }
 
     pub fn get_or_err(&self, group: &str, parameter: &str) -> Result<&Parameter, C3dParseError> {
@@ -132,67 +413,328 @@ impl Parameters {
         &mut self,
         group: &str,
         parameter: &str,
-    ) -> Result<&mut Parameter, C3dParseError> {
-        self.get_mut(group, parameter)
-            .ok_or(C3dParseError::ParameterNotFound(
+    ) -> Result<&mut Parameter, C3dEditError> {
+        self.check_unlocked(group, parameter)?;
+        self.force_get_mut(group, parameter)
+            .ok_or(C3dEditError::ParameterNotFound(
                 group.to_string(),
                 parameter.to_string(),
             ))
     }
 
-    pub fn insert(&mut self, group: &str, parameter: &str, mut value: Parameter) {
-        value.name = parameter.to_string();
+    /// Inserts a parameter, replacing a parameter with the same name.
+    /// Locked parameters and parameters in locked groups are not replaced,
+    /// use `force_insert` to replace them.
+    /// With `NameMatching::Legacy`, a name that only shares its first six characters
+    /// with another parameter of the group is refused.
+    pub fn insert(
+        &mut self,
+        group: &str,
+        parameter: &str,
+        value: Parameter,
+    ) -> Result<(), C3dEditError> {
+        self.check_unlocked(group, parameter)?;
+        self.check_unique(group, parameter)?;
+        self.force_insert(group, parameter, value);
+        Ok(())
+    }
+
+    /// Inserts a parameter even if it replaces a locked parameter or is in a locked group.
+    pub fn force_insert(&mut self, group: &str, parameter: &str, mut value: Parameter) {
+        let (group, parameter) = self.parameter_name(group, parameter);
+        value.name = parameter.clone();
         self.parameters
-            .entry(group.to_string())
+            .entry(group)
             .or_insert((String::new(), HashMap::new()))
             .1
-            .insert(parameter.to_string(), value);
+            .insert(parameter, value);
     }
 
+    /// Returns `None` for locked parameters and parameters in locked groups,
+    /// use `force_remove` to remove them.
     pub fn remove(&mut self, group: &str, parameter: &str) -> Option<Parameter> {
+        match self.check_unlocked(group, parameter) {
+            Ok(()) => self.force_remove(group, parameter),
+            Err(_) => None,
+        }
+    }
+
+    /// Removes a parameter even if it is locked.
+    pub fn force_remove(&mut self, group: &str, parameter: &str) -> Option<Parameter> {
+        let (group, parameter) = self.parameter_name(group, parameter);
         self.parameters
-            .get_mut(group)
-            .and_then(|(_, group)| group.remove(parameter))
+            .get_mut(&group)
+            .and_then(|(_, group)| group.remove(&parameter))
     }
 
     pub fn remove_or_err(
         &mut self,
         group: &str,
         parameter: &str,
+    ) -> Result<Parameter, C3dEditError> {
+        self.check_unlocked(group, parameter)?;
+        self.force_remove(group, parameter)
+            .ok_or(C3dEditError::ParameterNotFound(
+                group.to_string(),
+                parameter.to_string(),
+            ))
+    }
+
+    /// Removes a parameter for reading it into a typed struct, locked or not.
+    pub(crate) fn take(&mut self, group: &str, parameter: &str) -> Option<Parameter> {
+        let key = self.parameter_name(group, parameter);
+        let value = self.force_remove(group, parameter)?;
+        if let Some(layout) = &mut self.layout {
+            layout.consumed.insert(key);
+        }
+        Some(value)
+    }
+
+    /// Keeps the records the typed structs write for the parsed file, see `write_records`.
+    /// `typed_records` is only called when they were not kept yet.
+    pub(crate) fn record_parsed_typed(
+        &self,
+        processor: &Processor,
+        group_ids: &HashMap<String, usize>,
+        typed_records: impl FnOnce() -> Result<Vec<u8>, C3dWriteError>,
+    ) -> Result<(), C3dWriteError> {
+        let Some(layout) = &self.layout else {
+            return Ok(());
+        };
+        if layout.parsed_typed.get().is_none() {
+            let typed_records = typed_records()?;
+            let id_to_group: HashMap<usize, &String> =
+                group_ids.iter().map(|(group, id)| (*id, group)).collect();
+            let parsed_typed = split_records(&typed_records, processor, &id_to_group)
+                .into_iter()
+                .map(|(group, value, _)| ((group, value.name.clone()), value))
+                .collect();
+            let _ = layout.parsed_typed.set(parsed_typed);
+        }
+        Ok(())
+    }
+
+    /// Whether a typed struct writes `value` to `group` as it did right after parsing.
+    fn unchanged_since_parse(&self, group: &str, value: &Parameter) -> bool {
+        self.layout.as_ref().is_some_and(|layout| {
+            layout
+                .parsed_typed
+                .get()
+                .and_then(|parsed_typed| parsed_typed.get(&(group.to_string(), value.name.clone())))
+                == Some(value)
+        })
+    }
+
+    /// Whether a typed struct read the parameter from the file.
+    fn consumed(&self, group: &str, parameter: &str) -> bool {
+        self.layout.as_ref().is_some_and(|layout| {
+            layout
+                .consumed
+                .contains(&(group.to_string(), parameter.to_string()))
+        })
+    }
+
+    pub(crate) fn take_or_err(
+        &mut self,
+        group: &str,
+        parameter: &str,
     ) -> Result<Parameter, C3dParseError> {
-        self.remove(group, parameter)
+        self.take(group, parameter)
             .ok_or(C3dParseError::ParameterNotFound(
                 group.to_string(),
                 parameter.to_string(),
             ))
     }
 
+    /// Removes a parameter and its continuation parameters `LABELS2`, `LABELS3` and so on
+    /// for reading them into a typed struct, and returns their values in order.
+    pub(crate) fn take_continued<T>(
+        &mut self,
+        group: &str,
+        parameter: &str,
+    ) -> Result<Option<Vec<T>>, C3dParseError>
+    where
+        for<'a> Vec<T>: TryFrom<&'a Parameter, Error = C3dParseError>,
+    {
+        let mut values: Vec<T> = match self.take(group, parameter) {
+            Some(first) => first.as_ref().try_into()?,
+            None => return Ok(None),
+        };
+        let mut index = 1;
+        while let Some(next) = self.take(group, &continuation_name(parameter, index)) {
+            let next: Vec<T> = next.as_ref().try_into()?;
+            values.extend(next);
+            index += 1;
+        }
+        Ok(Some(values))
+    }
+
+    pub(crate) fn take_continued_or_err<T>(
+        &mut self,
+        group: &str,
+        parameter: &str,
+    ) -> Result<Vec<T>, C3dParseError>
+    where
+        for<'a> Vec<T>: TryFrom<&'a Parameter, Error = C3dParseError>,
+    {
+        self.take_continued(group, parameter)?
+            .ok_or(C3dParseError::ParameterNotFound(
+                group.to_string(),
+                parameter.to_string(),
+            ))
+    }
+
+    /// Refuses changes to locked parameters and to parameters of locked groups.
+    fn check_unlocked(&self, group: &str, parameter: &str) -> Result<(), C3dEditError> {
+        if self.is_group_locked(group) {
+            return Err(C3dEditError::LockedGroup(group.to_string()));
+        }
+        match self.get(group, parameter) {
+            Some(value) if value.locked => Err(C3dEditError::LockedParameter(
+                group.to_string(),
+                parameter.to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Refuses to write groups or parameters of a group whose names would be read as the
+    /// same name. Only names added since parsing are checked, the names of the file and
+    /// those the typed structs write, such as `TRIAL:ACTUAL_START_FIELD` and
+    /// `TRIAL:ACTUAL_END_FIELD`, are not checked against each other.
+    fn check_name_collisions(
+        &self,
+        typed: &[(String, Parameter, Vec<u8>)],
+    ) -> Result<(), C3dWriteError> {
+        let mut parsed_groups = HashSet::new();
+        let mut parsed_parameters = HashSet::new();
+        if let Some(layout) = &self.layout {
+            parsed_groups.extend(layout.group_ids.keys().map(|group| group.as_str()));
+            for record in &layout.records {
+                if let LayoutRecord::Parameter {
+                    group, parameter, ..
+                } = record
+                {
+                    parsed_parameters.insert((group.as_str(), parameter.name.as_str()));
+                }
+            }
+        }
+        let groups: Vec<&String> = self
+            .parameters
+            .keys()
+            .chain(typed.iter().map(|(group, _, _)| group))
+            .collect();
+        let added_group = |group: &String| {
+            self.parameters.contains_key(group) && !parsed_groups.contains(group.as_str())
+        };
+        if let Some((a, b)) = find_collision(groups.clone(), added_group, self.name_matching) {
+            return Err(C3dWriteError::NameCollision(a.clone(), b.clone()));
+        }
+        for group in groups {
+            let added = |name: &String| {
+                self.parameters
+                    .get(group)
+                    .is_some_and(|(_, parameters)| parameters.contains_key(name))
+                    && !parsed_parameters.contains(&(group.as_str(), name.as_str()))
+            };
+            let names: Vec<&String> = self
+                .parameters
+                .get(group)
+                .into_iter()
+                .flat_map(|(_, parameters)| parameters.keys())
+                .chain(
+                    typed
+                        .iter()
+                        .filter(|(typed_group, _, _)| typed_group == group)
+                        .map(|(_, value, _)| &value.name),
+                )
+                .collect();
+            if let Some((a, b)) = find_collision(names, added, self.name_matching) {
+                return Err(C3dWriteError::NameCollision(
+                    format!("{}:{}", group, a),
+                    format!("{}:{}", group, b),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_unique(&self, group: &str, parameter: &str) -> Result<(), C3dEditError> {
+        if self.name_matching != NameMatching::Legacy {
+            return Ok(());
+        }
+        let group = self.group_name(group);
+        let existing = self.parameters.get(&group).and_then(|(_, parameters)| {
+            parameters
+                .keys()
+                .find(|name| !name.eq_ignore_ascii_case(parameter) && legacy_match(name, parameter))
+        });
+        match existing {
+            Some(existing) => Err(C3dEditError::NameCollision(
+                format!("{}:{}", group, existing),
+                format!("{}:{}", group, parameter),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_group_locked(&self, group: &str) -> bool {
+        self.locked_groups.contains(&self.group_name(group))
+    }
+
+    /// Locked groups are stored with a negative name length,
+    /// their description and parameters should not be changed.
+    pub fn set_group_locked(&mut self, group: &str, locked: bool) {
+        let group = self.group_name(group);
+        match locked {
+            true => self.locked_groups.insert(group),
+            false => self.locked_groups.remove(&group),
+        };
+    }
+
     pub fn contains(&self, group: &str, parameter: &str) -> bool {
-        self.parameters
-            .get(group)
-            .and_then(|(_, group)| group.get(parameter))
-            .is_some()
+        self.get(group, parameter).is_some()
     }
 
     pub fn get_group(&self, group: &str) -> Option<&HashMap<String, Parameter>> {
-        self.parameters.get(group).map(|(_, group)| group)
+        find_entry(&self.parameters, group, self.name_matching).map(|(_, group)| group)
     }
 
     pub fn get_group_description(&self, group: &str) -> Option<&String> {
-        self.parameters
-            .get(group)
+        find_entry(&self.parameters, group, self.name_matching)
             .map(|(group_description, _)| group_description)
     }
 
+    /// Returns `None` for locked groups.
     pub fn get_group_description_mut(&mut self, group: &str) -> Option<&mut String> {
+        if self.is_group_locked(group) {
+            return None;
+        }
+        let group = self.group_name(group);
         self.parameters
-            .get_mut(group)
+            .get_mut(&group)
             .map(|(group_description, _)| group_description)
     }
 
-    pub fn insert_group(&mut self, group_name: &str, description: String) {
+    /// Inserts an empty group, replacing a group with the same name and its parameters.
+    /// Locked groups are not replaced, use `force_insert_group` to replace them.
+    pub fn insert_group(
+        &mut self,
+        group_name: &str,
+        description: String,
+    ) -> Result<(), C3dEditError> {
+        if self.is_group_locked(group_name) {
+            return Err(C3dEditError::LockedGroup(group_name.to_string()));
+        }
+        self.force_insert_group(group_name, description);
+        Ok(())
+    }
+
+    /// Inserts an empty group even if it replaces a locked group.
+    pub fn force_insert_group(&mut self, group_name: &str, description: String) {
+        let group_name = self.group_name(group_name);
         self.parameters
-            .insert(group_name.to_string(), (description, HashMap::new()));
+            .insert(group_name, (description, HashMap::new()));
     }
 
     pub fn num_groups(&self) -> usize {
@@ -200,7 +742,7 @@ impl Parameters {
     }
 
     pub fn num_parameters(&self, group: &str) -> Option<usize> {
-        self.parameters.get(group).map(|(_, group)| group.len())
+        self.get_group(group).map(|group| group.len())
     }
 
     pub fn groups(&self) -> Vec<&String> {
@@ -208,15 +750,15 @@ impl Parameters {
     }
 
     pub fn parameters(&self, group: &str) -> Option<Vec<&Parameter>> {
-        self.parameters
-            .get(group)
-            .map(|(_, group)| group.values().collect())
+        self.get_group(group).map(|group| group.values().collect())
     }
 }
 
 impl PartialEq for Parameters {
     fn eq(&self, other: &Self) -> bool {
-        if self.parameters.len() != other.parameters.len() {
+        if self.parameters.len() != other.parameters.len()
+            || self.locked_groups != other.locked_groups
+        {
             return false;
         }
         for (group, (group_description, parameters)) in self.parameters.iter() {
@@ -259,14 +801,16 @@ impl TryFrom<&Parameter> for u16 {
                     Ok(data[0] as u16)
                 } else {
                     Err(C3dParseError::InvalidData(
-                        parameter.clone(),
+                        Box::new(parameter.clone()),
                         "u16".to_string(),
+                        None,
                     ))
                 }
             }
             _ => Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "u16".to_string(),
+                None,
             )),
         }
     }
@@ -281,14 +825,16 @@ impl TryFrom<&Parameter> for [u16; 2] {
                     Ok([data[0] as u16, data[1] as u16])
                 } else {
                     Err(C3dParseError::InvalidData(
-                        parameter.clone(),
+                        Box::new(parameter.clone()),
                         "u162".to_string(),
+                        None,
                     ))
                 }
             }
             _ => Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "u162".to_string(),
+                None,
             )),
         }
     }
@@ -303,14 +849,16 @@ impl TryFrom<&Parameter> for Vec<u16> {
                     Ok(data.iter().map(|x| *x as u16).collect())
                 } else {
                     Err(C3dParseError::InvalidData(
-                        parameter.clone(),
+                        Box::new(parameter.clone()),
                         "Vec<u16>".to_string(),
+                        None,
                     ))
                 }
             }
             _ => Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "Vec<u16>".to_string(),
+                None,
             )),
         }
     }
@@ -325,14 +873,16 @@ impl TryFrom<&Parameter> for i16 {
                     Ok(data[0])
                 } else {
                     Err(C3dParseError::InvalidData(
-                        parameter.clone(),
+                        Box::new(parameter.clone()),
                         "i16".to_string(),
+                        None,
                     ))
                 }
             }
             _ => Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "i16".to_string(),
+                None,
             )),
         }
     }
@@ -347,14 +897,16 @@ impl TryFrom<&Parameter> for Vec<i16> {
                     Ok(data.iter().map(|&x| x as i16).collect())
                 } else {
                     Err(C3dParseError::InvalidData(
-                        parameter.clone(),
+                        Box::new(parameter.clone()),
                         "Vec<i16>".to_string(),
+                        None,
                     ))
                 }
             }
             _ => Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "Vec<i16>".to_string(),
+                None,
             )),
         }
     }
@@ -372,14 +924,16 @@ impl TryFrom<&Parameter> for Grid<i16> {
                     Ok(Grid::from_vec(data.clone(), 1))
                 } else {
                     Err(C3dParseError::InvalidData(
-                        parameter.clone(),
+                        Box::new(parameter.clone()),
                         "Grid<i16>".to_string(),
+                        None,
                     ))
                 }
             }
             _ => Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "Grid<i16>".to_string(),
+                None,
             )),
         }
     }
@@ -394,14 +948,16 @@ impl TryFrom<&Parameter> for f32 {
                     Ok(data[0])
                 } else {
                     Err(C3dParseError::InvalidData(
-                        parameter.clone(),
+                        Box::new(parameter.clone()),
                         "f32".to_string(),
+                        None,
                     ))
                 }
             }
             _ => Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "f32".to_string(),
+                None,
             )),
         }
     }
@@ -416,14 +972,16 @@ impl TryFrom<&Parameter> for Vec<f32> {
                     Ok(data.iter().map(|&x| x as f32).collect())
                 } else {
                     Err(C3dParseError::InvalidData(
-                        parameter.clone(),
+                        Box::new(parameter.clone()),
                         "Vec<f32>".to_string(),
+                        None,
                     ))
                 }
             }
             _ => Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "Vec<f32>".to_string(),
+                None,
             )),
         }
     }
@@ -441,14 +999,16 @@ impl TryFrom<&Parameter> for Grid<f32> {
                     Ok(Grid::from_vec(data.clone(), 1))
                 } else {
                     Err(C3dParseError::InvalidData(
-                        parameter.clone(),
+                        Box::new(parameter.clone()),
                         "Grid<f32>".to_string(),
+                        None,
                     ))
                 }
             }
             _ => Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "Grid<f32>".to_string(),
+                None,
             )),
         }
     }
@@ -466,8 +1026,9 @@ impl TryFrom<&Parameter> for String {
                 Ok(string.trim().to_string())
             }
             _ => Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "String".to_string(),
+                None,
             )),
         }
     }
@@ -496,14 +1057,16 @@ impl TryFrom<&Parameter> for Vec<String> {
                     Ok(Vec::new())
                 } else {
                     Err(C3dParseError::InvalidData(
-                        parameter.clone(),
+                        Box::new(parameter.clone()),
                         "Vec<String>".to_string(),
+                        None,
                     ))
                 }
             }
             _ => Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "Vec<String>".to_string(),
+                None,
             )),
         }
     }
@@ -524,8 +1087,9 @@ impl TryFrom<&Parameter> for [char; 4] {
                 Ok(chars)
             }
             _ => Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "char4".to_string(),
+                None,
             )),
         }
     }
@@ -548,14 +1112,16 @@ impl TryFrom<&Parameter> for Vec<[char; 4]> {
                     Ok(chars)
                 } else {
                     Err(C3dParseError::InvalidData(
-                        parameter.clone(),
+                        Box::new(parameter.clone()),
                         "Vec<[char; 4]>".to_string(),
+                        None,
                     ))
                 }
             }
             _ => Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "Vec<[char; 4]>".to_string(),
+                None,
             )),
         }
     }
@@ -576,8 +1142,9 @@ impl TryFrom<&Parameter> for [char; 2] {
                 Ok(chars)
             }
             _ => Err(C3dParseError::InvalidData(
-                parameter.clone(),
+                Box::new(parameter.clone()),
                 "char2".to_string(),
+                None,
             )),
         }
     }
@@ -611,7 +1178,7 @@ impl TryFrom<i8> for DataType {
             1 => Ok(DataType::Byte),
             2 => Ok(DataType::Integer),
             4 => Ok(DataType::Float),
-            _ => Err(C3dParseError::InvalidDataType),
+            _ => Err(C3dParseError::InvalidDataType(None)),
         }
     }
 }
@@ -622,6 +1189,8 @@ pub struct Parameter {
     pub description: String,
     pub dimensions: Vec<u8>,
     pub data: ParameterData,
+    /// Locked parameters are stored with a negative name length and should not be changed.
+    pub locked: bool,
 }
 
 impl Parameter {
@@ -643,13 +1212,14 @@ impl Parameter {
             dimensions.push(1);
         }
         if dimensions.iter().map(|&x| x as usize).product::<usize>() != data_length {
-            return Err(C3dParseError::InvalidParameterData);
+            return Err(C3dParseError::InvalidParameterData(None));
         }
         Ok(Parameter {
             name: String::new(),
             description,
             dimensions,
             data,
+            locked: false,
         })
     }
 
@@ -659,6 +1229,7 @@ impl Parameter {
             description: String::new(),
             dimensions: Vec::new(),
             data: ParameterData::Byte(Vec::new()),
+            locked: false,
         }
     }
 
@@ -668,6 +1239,7 @@ impl Parameter {
             description: String::new(),
             dimensions: Vec::new(),
             data: ParameterData::Char(Vec::new()),
+            locked: false,
         }
     }
 
@@ -677,6 +1249,7 @@ impl Parameter {
             description: String::new(),
             dimensions: Vec::new(),
             data: ParameterData::Integer(Vec::new()),
+            locked: false,
         }
     }
 
@@ -686,6 +1259,7 @@ impl Parameter {
             description: String::new(),
             dimensions: Vec::new(),
             data: ParameterData::Float(Vec::new()),
+            locked: false,
         }
     }
 
@@ -695,6 +1269,7 @@ impl Parameter {
             description: String::new(),
             dimensions: vec![1],
             data: ParameterData::Byte(vec![data]),
+            locked: false,
         }
     }
 
@@ -704,6 +1279,7 @@ impl Parameter {
             description: String::new(),
             dimensions: vec![1],
             data: ParameterData::Char(vec![data]),
+            locked: false,
         }
     }
 
@@ -713,6 +1289,7 @@ impl Parameter {
             description: String::new(),
             dimensions: vec![1],
             data: ParameterData::Integer(vec![data]),
+            locked: false,
         }
     }
 
@@ -722,6 +1299,7 @@ impl Parameter {
             description: String::new(),
             dimensions: vec![1],
             data: ParameterData::Float(vec![data]),
+            locked: false,
         }
     }
 
@@ -731,6 +1309,7 @@ impl Parameter {
             description: String::new(),
             dimensions: vec![data.len() as u8],
             data: ParameterData::Byte(data),
+            locked: false,
         }
     }
 
@@ -745,6 +1324,7 @@ impl Parameter {
             description: String::new(),
             dimensions: vec![data.len() as u8],
             data: ParameterData::Char(data),
+            locked: false,
         })
     }
 
@@ -769,6 +1349,7 @@ impl Parameter {
             description: String::new(),
             dimensions: vec![data.len() as u8],
             data: ParameterData::Integer(data),
+            locked: false,
         })
     }
 
@@ -783,6 +1364,7 @@ impl Parameter {
             description: String::new(),
             dimensions: vec![data.len() as u8],
             data: ParameterData::Float(data),
+            locked: false,
         })
     }
 
@@ -792,6 +1374,7 @@ impl Parameter {
             description: String::new(),
             dimensions: vec![data.rows() as u8, data.cols() as u8],
             data: ParameterData::Byte(data.into_vec()),
+            locked: false,
         }
     }
 
@@ -820,6 +1403,7 @@ impl Parameter {
             description: String::new(),
             dimensions: vec![data.cols() as u8, data.rows() as u8],
             data: ParameterData::Char(data.into_vec()),
+            locked: false,
         }
     }
 
@@ -829,6 +1413,7 @@ impl Parameter {
             description: String::new(),
             dimensions: vec![data.cols() as u8, data.rows() as u8],
             data: ParameterData::Integer(data.into_vec()),
+            locked: false,
         }
     }
 
@@ -838,6 +1423,7 @@ impl Parameter {
             description: String::new(),
             dimensions: vec![data.cols() as u8, data.rows() as u8],
             data: ParameterData::Float(data.into_vec()),
+            locked: false,
         }
     }
 
@@ -852,7 +1438,7 @@ impl Parameter {
         if parameter_name.len() > 127 {
             return Err(C3dWriteError::ParameterNameTooLong(parameter_name.clone()));
         }
-        bytes.push(parameter_name.len() as u8);
+        bytes.push(name_length(&parameter_name, self.locked));
         bytes.push(group_id.clone() as u8);
         if !parameter_name.is_ascii() {
             return Err(C3dWriteError::ParameterNameNotAscii(parameter_name.clone()));
@@ -948,14 +1534,14 @@ impl ParameterData {
         processor: &Processor,
     ) -> Result<Self, C3dParseError> {
         if data.len() % usize::from(data_type) != 0 {
-            return Err(C3dParseError::InvalidParameterData);
+            return Err(C3dParseError::InvalidParameterData(None));
         }
         let dimensions = dimensions
             .iter()
             .map(|&x| x as usize)
             .collect::<Vec<usize>>();
         if dimensions.iter().product::<usize>() != data.len() / usize::from(data_type) {
-            return Err(C3dParseError::InvalidParameterData);
+            return Err(C3dParseError::InvalidParameterData(None));
         }
         let array = match data_type {
             DataType::Char => {
@@ -991,9 +1577,9 @@ impl ParameterData {
 fn parse_parameters(
     parameter_blocks: &Vec<u8>,
     processor: &Processor,
-) -> Result<HashMap<String, (String, HashMap<String, Parameter>)>, C3dParseError> {
+) -> Result<Parameters, C3dParseError> {
     if parameter_blocks.len() < 512 {
-        return Err(C3dParseError::InvalidParameterStartBlock);
+        return Err(C3dParseError::InvalidParameterStartBlock(None));
     }
 
     let mut groups: Vec<ParsedGroup> = Vec::new();
@@ -1008,15 +1594,50 @@ fn parse_parameters(
             &mut groups,
             &mut parameters,
             processor,
-        )?;
-    }
+        )
+        .map_err(|e| e.at(record_location(parameter_blocks, index, &groups)))?;
+    }
+    let mut layout = ParameterLayout {
+        header: [
+            parameter_blocks[0],
+            parameter_blocks[1],
+            parameter_blocks[2],
+        ],
+        blocks: parameter_blocks.len() / 512,
+        group_ids: HashMap::new(),
+        processor: *processor,
+        records: Vec::new(),
+        consumed: HashSet::new(),
+        parsed_typed: OnceLock::new(),
+    };
+    let mut record_offsets = Vec::new();
     let mut groups_map = HashMap::new();
     let mut temp_group_id_to_name = HashMap::new();
+    let mut locked_groups = HashSet::new();
     for group in groups {
         temp_group_id_to_name.insert(group.id, group.name.clone());
-        groups_map.insert(group.name.clone(), (group.description, HashMap::new()));
+        // a group name used more than once keeps the id of its first record
+        layout
+            .group_ids
+            .entry(group.name.clone())
+            .or_insert(group.id.unsigned_abs() as usize);
+        if group.locked {
+            locked_groups.insert(group.name.clone());
+        }
+        groups_map.insert(
+            group.name.clone(),
+            (group.description.clone(), HashMap::new()),
+        );
+        record_offsets.push(group.offset);
+        layout.records.push(LayoutRecord::Group {
+            name: group.name,
+            description: group.description,
+            locked: group.locked,
+            bytes: group.bytes,
+        });
     }
     insert_missing_required_groups(&mut groups_map, &mut temp_group_id_to_name)?;
+    let mut offsets: HashMap<String, HashMap<String, usize>> = HashMap::new();
     for parameter in parameters {
         let group_name = match temp_group_id_to_name.contains_key(&parameter.group_id) {
             true => temp_group_id_to_name
@@ -1033,19 +1654,90 @@ fn parse_parameters(
             }
         };
         let name = parameter.name.clone();
+        offsets
+            .entry(group_name.clone())
+            .or_default()
+            .insert(name.clone(), parameter.offset);
+        record_offsets.push(parameter.offset);
+        let bytes = parameter.bytes;
         let parameter = Parameter {
             name: parameter.name,
             description: parameter.description,
             dimensions: parameter.dimensions,
             data: parameter.data,
+            locked: parameter.locked,
         };
+        layout.records.push(LayoutRecord::Parameter {
+            group: group_name.clone(),
+            parameter: parameter.clone(),
+            bytes,
+        });
         groups_map
             .get_mut(&group_name)
-            .ok_or(C3dParseError::InvalidGroupId)?
+            .ok_or(C3dParseError::InvalidGroupId(None))?
             .1
             .insert(name, parameter);
     }
-    Ok(groups_map)
+    for (id, name) in temp_group_id_to_name {
+        layout
+            .group_ids
+            .entry(name)
+            .or_insert(id.unsigned_abs() as usize);
+    }
+    // repeated group records are written as they were read,
+    // the first one holds the description of the group
+    let mut seen_groups = HashSet::new();
+    for record in layout.records.iter_mut() {
+        if let LayoutRecord::Group {
+            name, description, ..
+        } = record
+        {
+            if seen_groups.insert(name.clone()) {
+                *description = groups_map[name.as_str()].0.clone();
+            }
+        }
+    }
+    // groups and parameters can be interleaved in the file
+    let mut order: Vec<usize> = (0..layout.records.len()).collect();
+    order.sort_by_key(|&i| record_offsets[i]);
+    let mut records: Vec<Option<LayoutRecord>> = layout.records.into_iter().map(Some).collect();
+    layout.records = order
+        .into_iter()
+        .filter_map(|i| records[i].take())
+        .collect();
+    Ok(Parameters {
+        parameters: groups_map,
+        offsets,
+        layout: Some(layout),
+        locked_groups,
+        name_matching: NameMatching::default(),
+    })
+}
+
+/// Location of the group or parameter record at `index`, relative to the start of the parameter section.
+fn record_location(parameter_blocks: &[u8], index: usize, groups: &[ParsedGroup]) -> ErrorLocation {
+    let location = ErrorLocation::new(index);
+    let num_chars_in_name = parameter_blocks
+        .get(index)
+        .map_or(0, |&num_chars| (num_chars as i8).unsigned_abs() as usize);
+    let group_id = parameter_blocks.get(index + 1).map_or(0, |&id| id as i8);
+    let name = parameter_blocks
+        .get(index + 2..index + 2 + num_chars_in_name)
+        .map(|name| name.iter().map(|&c| c as char).collect::<String>());
+    if group_id < 0 {
+        return match name {
+            Some(name) => location.group(&name),
+            None => location,
+        };
+    }
+    let location = match groups.iter().find(|group| group.id == group_id) {
+        Some(group) => location.group(&group.name),
+        None => location,
+    };
+    match name {
+        Some(name) => location.parameter(&name.to_ascii_uppercase()),
+        None => location,
+    }
 }
 
 fn parse_next_group_or_parameter(
@@ -1087,18 +1779,28 @@ fn parse_group(
     let name = parse_group_name(&parameter_blocks, i, num_chars_in_name)?;
     i += num_chars_in_name.abs() as usize;
     let next_group_index_bytes = &parameter_blocks[i..i + 2];
-    let next_group_index =
-        processor.u16(next_group_index_bytes.try_into().unwrap()) as usize + i as usize;
+    let next_group_offset = processor.u16(next_group_index_bytes.try_into().unwrap()) as usize;
+    let next_group_index = next_group_offset + i as usize;
     i += 2;
     let num_chars_in_description = parameter_blocks[i];
     i += 1;
     let description = parse_description(&parameter_blocks, i, num_chars_in_description)?;
+    i += num_chars_in_description as usize;
 
     Ok((
         ParsedGroup {
             id,
             name,
+            locked: num_chars_in_name < 0,
             description,
+            offset: index,
+            bytes: record_bytes(
+                parameter_blocks,
+                index,
+                next_group_offset,
+                next_group_index,
+                i,
+            ),
         },
         next_group_index,
     ))
@@ -1150,7 +1852,8 @@ fn parse_parameter(
     let name = parse_parameter_name(&parameter_blocks, i, num_chars_in_name)?.to_ascii_uppercase();
     i += num_chars_in_name.abs() as usize;
     let next_index_bytes = &parameter_blocks[i..i + 2];
-    let next_index = processor.u16(next_index_bytes.try_into().unwrap()) as usize + i as usize;
+    let next_offset = processor.u16(next_index_bytes.try_into().unwrap()) as usize;
+    let next_index = next_offset + i as usize;
     i += 2;
     let data_type = DataType::try_from(parameter_blocks[i] as i8)?;
     i += 1;
@@ -1164,11 +1867,15 @@ fn parse_parameter(
     let num_chars_in_description = parameter_blocks[i];
     i += 1;
     let description = parse_description(&parameter_blocks, i, num_chars_in_description)?;
+    i += num_chars_in_description as usize;
 
     Ok((
         ParsedParameter {
+            bytes: record_bytes(parameter_blocks, index, next_offset, next_index, i),
+            offset: index,
             group_id,
             name,
+            locked: num_chars_in_name < 0,
             data,
             dimensions,
             description,
@@ -1229,7 +1936,7 @@ fn parse_data(
     let data_byte_size = dimensions_product * usize::from(data_type);
 
     if index + data_byte_size > parameter_blocks.len() {
-        return Err(C3dParseError::InvalidParameterData);
+        return Err(C3dParseError::InvalidParameterData(None));
     }
 
     let bytes: &[u8] = &parameter_blocks[index..index + data_byte_size];
@@ -1240,17 +1947,222 @@ fn parse_data(
     ))
 }
 
+fn write_group(
+    processor: &Processor,
+    group: &str,
+    group_id: usize,
+    description: &str,
+    locked: bool,
+) -> Result<Vec<u8>, C3dWriteError> {
+    if group.len() > 127 {
+        return Err(C3dWriteError::GroupNameTooLong(group.to_string()));
+    }
+    if !group.is_ascii() {
+        return Err(C3dWriteError::GroupNameNotAscii(group.to_string()));
+    }
+    if description.as_bytes().len() > 255 {
+        return Err(C3dWriteError::GroupDescriptionTooLong(
+            description.to_string(),
+        ));
+    }
+    let mut bytes = Vec::new();
+    bytes.push(name_length(group, locked));
+    bytes.push(-(group_id as i8) as u8);
+    bytes.extend(group.to_ascii_uppercase().as_bytes());
+    // the offset to the next record counts itself and the description length
+    bytes.extend(processor.u16_to_bytes(description.len() as u16 + 3));
+    bytes.push(description.len() as u8);
+    bytes.extend(description.as_bytes());
+    Ok(bytes)
+}
+
+/// Looks up the entry that `name` refers to without allocating.
+fn find_entry<'a, T>(
+    entries: &'a HashMap<String, T>,
+    name: &str,
+    name_matching: NameMatching,
+) -> Option<&'a T> {
+    match name_matching {
+        NameMatching::Exact => entries.get(name),
+        _ => entries.get(find_name(entries.keys(), name, name_matching)?),
+    }
+}
+
+/// Finds the stored name that `name` refers to.
+fn find_name<'a, I>(names: I, name: &str, name_matching: NameMatching) -> Option<&'a String>
+where
+    I: Iterator<Item = &'a String> + Clone,
+{
+    if let Some(found) = names.clone().find(|stored| stored.as_str() == name) {
+        return Some(found);
+    }
+    if name_matching == NameMatching::Exact {
+        return None;
+    }
+    if let Some(found) = names
+        .clone()
+        .find(|stored| stored.eq_ignore_ascii_case(name))
+    {
+        return Some(found);
+    }
+    let mut matches = names.filter(|stored| legacy_match(stored, name));
+    match (matches.next(), matches.next()) {
+        (Some(found), None) => Some(found),
+        _ => None,
+    }
+}
+
+/// Finds two names that would be read as the same name, at least one of them added.
+fn find_collision(
+    mut names: Vec<&String>,
+    added: impl Fn(&String) -> bool,
+    name_matching: NameMatching,
+) -> Option<(&String, &String)> {
+    names.sort();
+    names.dedup();
+    names.iter().enumerate().find_map(|(i, a)| {
+        names[i + 1..]
+            .iter()
+            .find(|b| (added(a) || added(b)) && names_collide(a, b, name_matching))
+            .map(|b| (*a, *b))
+    })
+}
+
+/// The part of a name that readers of the original C3D format compare.
+fn legacy_name(name: &str) -> String {
+    name.chars()
+        .take(6)
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+/// Whether two names share their first six characters.
+/// Continuation parameters such as `LABELS2` are told apart from `LABELS` by their full name.
+fn legacy_match(a: &str, b: &str) -> bool {
+    legacy_name(a) == legacy_name(b) && !is_continuation(a) && !is_continuation(b)
+}
+
+/// Whether `name` continues a parameter with too many values, such as `LABELS2` or `DESCRIPTIONS3`.
+fn is_continuation(name: &str) -> bool {
+    let base = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    base.len() < name.len() && base.len() >= 6
+}
+
+/// The name of the continuation parameter that holds the values from `index * 255`,
+/// `LABELS`, `LABELS2`, `LABELS3` and so on.
+pub(crate) fn continuation_name(parameter: &str, index: usize) -> String {
+    match index {
+        0 => parameter.to_string(),
+        _ => format!("{}{}", parameter, index + 1),
+    }
+}
+
+/// Writes `values` as `parameter_name` with up to 255 values per parameter,
+/// the remaining values go to the continuation parameters `LABELS2`, `LABELS3` and so on.
+pub(crate) fn write_continued<T: Clone>(
+    values: &[T],
+    to_parameter: impl Fn(Vec<T>) -> Result<Parameter, C3dWriteError>,
+    processor: &Processor,
+    parameter_name: &str,
+    group_id: usize,
+) -> Result<Vec<u8>, C3dWriteError> {
+    let mut bytes = Vec::new();
+    let chunks: Vec<&[T]> = match values.is_empty() {
+        true => vec![values],
+        false => values.chunks(MAX_CONTINUED_VALUES).collect(),
+    };
+    for (index, chunk) in chunks.into_iter().enumerate() {
+        bytes.extend(to_parameter(chunk.to_vec())?.write(
+            processor,
+            continuation_name(parameter_name, index),
+            group_id,
+            false,
+        )?);
+    }
+    Ok(bytes)
+}
+
+/// Whether two different names would be read as the same name.
+/// Names are written in upper case, so names that only differ in case always collide.
+fn names_collide(a: &str, b: &str, name_matching: NameMatching) -> bool {
+    a != b
+        && (a.eq_ignore_ascii_case(b)
+            || name_matching == NameMatching::Legacy && legacy_match(a, b))
+}
+
+/// The name length of a record, negative for locked records.
+fn name_length(name: &str, locked: bool) -> u8 {
+    match locked {
+        true => -(name.len() as i8) as u8,
+        false => name.len() as u8,
+    }
+}
+
+/// Splits the records written by `Parameter::write` into their group, value and bytes.
+fn split_records(
+    bytes: &[u8],
+    processor: &Processor,
+    id_to_group: &HashMap<usize, &String>,
+) -> Vec<(String, Parameter, Vec<u8>)> {
+    let mut records = Vec::new();
+    let mut index = 0;
+    while index + 4 <= bytes.len() {
+        let pointer = index + 2 + (bytes[index] as i8).unsigned_abs() as usize;
+        let next = pointer + processor.u16([bytes[pointer], bytes[pointer + 1]]) as usize;
+        let record = bytes[index..next].to_vec();
+        let group = id_to_group
+            .get(&(bytes[index + 1] as usize))
+            .map(|group| group.to_string())
+            .unwrap_or_default();
+        let value = match parse_parameter(&record, 0, processor) {
+            Ok((parsed, _)) => Parameter {
+                name: parsed.name,
+                description: parsed.description,
+                dimensions: parsed.dimensions,
+                data: parsed.data,
+                locked: parsed.locked,
+            },
+            Err(_) => Parameter::empty_bytes(),
+        };
+        records.push((group, value, record));
+        index = next;
+    }
+    records
+}
+
+/// The bytes of the record that starts at `index`. Records end where the next record starts,
+/// the last record has no next record and ends after its description at `end`.
+fn record_bytes(
+    parameter_blocks: &[u8],
+    index: usize,
+    next_offset: usize,
+    next_index: usize,
+    end: usize,
+) -> Vec<u8> {
+    let end = match next_offset {
+        0 => end,
+        _ => next_index,
+    };
+    parameter_blocks[index..end.min(parameter_blocks.len())].to_vec()
+}
+
 #[derive(Debug, Clone)]
 struct ParsedGroup {
     id: i8,
     name: String,
+    locked: bool,
     description: String,
+    offset: usize,
+    bytes: Vec<u8>,
 }
 
 #[derive(Debug, Clone)]
 struct ParsedParameter {
+    bytes: Vec<u8>,
+    offset: usize,
     group_id: i8,
     name: String,
+    locked: bool,
     pub data: ParameterData,
     dimensions: Vec<u8>,
     description: String,
@@ -1267,8 +2179,11 @@ const REQUIRED_GROUPS: [&str; 8] = [
     "SEG",
 ];
 
+/// The number of values that fit in the last dimension of a parameter.
+const MAX_CONTINUED_VALUES: usize = 255;
+
 fn insert_missing_required_groups(
-    groups_map: &mut HashMap<String, (String, HashMap<String, Parameter>)>,
+    groups_map: &mut HashMap<String, Group>,
     temp_group_id_to_name: &mut HashMap<i8, String>,
 ) -> Result<(), C3dParseError> {
     let max_group_id = temp_group_id_to_name.keys().max();
@@ -1,16 +1,28 @@
 //! Implements the Points struct and methods for parsing and writing point data.
-use crate::data::{get_analog_bytes_per_frame, get_point_bytes_per_frame, DataFormat, MarkerPoint};
-use crate::parameters::{Parameter, ParameterData, Parameters};
+use crate::data::{
+    get_analog_bytes_per_frame, get_point_bytes_per_frame, retain_indices, DataFormat, MarkerPoint,
+};
+use crate::options::ParseContext;
+use crate::parameters::{write_continued, Parameter, ParameterData, Parameters};
 use crate::processor::Processor;
-use crate::{C3dParseError, C3dWriteError};
+use crate::{C3dParseError, C3dWarningKind, C3dWriteError};
 use grid::Grid;
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::ops::{Deref, DerefMut};
 
+/// `POINT:FRAMES`, `TRIAL:ACTUAL_START_FIELD`, `TRIAL:ACTUAL_END_FIELD` and
+/// `POINT:LONG_FRAMES` as read from the parameters.
+type FrameCounts = (Option<usize>, Option<usize>, Option<usize>, Option<usize>);
+
 #[derive(Clone)]
 pub struct Points {
     parsed_header: bool,
+    header_markers: usize,
+    /// Whether the file stored `POINT:LONG_FRAMES`, which is then written even for short recordings.
+    long_frames: bool,
+    /// Whether the file stored `TRIAL:ACTUAL_START_FIELD` and `TRIAL:ACTUAL_END_FIELD`.
+    actual_fields: bool,
     pub points: Grid<MarkerPoint>,
     pub labels: Vec<String>,
     pub descriptions: Vec<String>,
@@ -20,8 +32,8 @@ pub struct Points {
     pub frame_rate: f32,
     pub scale_factor: f32,
     pub max_interpolation_gap: u16,
-    pub first_frame: u16,
-    pub last_frame: u16,
+    pub first_frame: u32,
+    pub last_frame: u32,
     pub format: DataFormat,
 }
 
@@ -103,6 +115,9 @@ impl Default for Points {
     fn default() -> Self {
         Points {
             parsed_header: false,
+            header_markers: 0,
+            long_frames: false,
+            actual_fields: false,
             points: Grid::new(0, 0),
             labels: Vec::new(),
             descriptions: Vec::new(),
@@ -126,13 +141,9 @@ impl Points {
 
     pub(crate) fn parse_header(header: &[u8; 512], processor: &Processor) -> Self {
         let mut points = Points::new();
-        let num_markers = processor.u16([header[2], header[3]]);
-        points.first_frame = processor.u16([header[6], header[7]]);
-        points.last_frame = processor.u16([header[8], header[9]]);
-        points.points = Grid::new(
-            (points.last_frame - points.first_frame + 1) as usize,
-            num_markers as usize,
-        );
+        points.header_markers = processor.u16([header[2], header[3]]) as usize;
+        points.first_frame = processor.u16([header[6], header[7]]) as u32;
+        points.last_frame = processor.u16([header[8], header[9]]) as u32;
         points.max_interpolation_gap = processor.u16([header[10], header[11]]);
         let scale_factor = processor.f32([header[12], header[13], header[14], header[15]]);
         if scale_factor <= 0.0 {
@@ -152,22 +163,102 @@ impl Points {
         parameters: &mut Parameters,
         processor: &Processor,
         analog_samples_per_frame: u16,
+        context: &mut ParseContext,
     ) -> Result<(&mut Self, usize), C3dParseError> {
+        let num_frames = self.parse_parameters(
+            parameters,
+            data_bytes.len(),
+            analog_samples_per_frame,
+            context,
+        )?;
+        self.parse_points(data_bytes, processor, analog_samples_per_frame, num_frames)?;
+        Ok((self, num_frames))
+    }
+
+    /// Reads the POINT parameters and returns the number of frames
+    /// that are available in `data_len` bytes of the data section.
+    pub(crate) fn parse_parameters(
+        &mut self,
+        parameters: &mut Parameters,
+        data_len: usize,
+        analog_samples_per_frame: u16,
+        context: &mut ParseContext,
+    ) -> Result<usize, C3dParseError> {
         if !self.parsed_header {
             return Err(C3dParseError::HeaderNotParsed);
         }
         let (point_frames, actual_start_field, actual_end_field, long_frames) =
-            self.get_point_parameters(parameters)?;
-        let num_frames = self.calc_num_frames(
-            data_bytes,
+            self.get_point_parameters(parameters, context)?;
+        self.calc_num_frames(
+            data_len,
             point_frames,
             actual_start_field,
             actual_end_field,
             long_frames,
             analog_samples_per_frame,
-        )?;
-        self.parse_points(data_bytes, processor, analog_samples_per_frame, num_frames)?;
-        Ok((self, num_frames))
+            parameters,
+            context,
+        )
+    }
+
+    /// Number of markers stored in each frame of the data section.
+    pub(crate) fn markers_per_frame(&self) -> usize {
+        match self.parsed_header {
+            true => self.header_markers,
+            false => self.cols(),
+        }
+    }
+
+    /// Decodes the points of a single frame from the point portion of a data record.
+    pub(crate) fn parse_frame(
+        &self,
+        point_frame_data: &[u8],
+        processor: &Processor,
+    ) -> Vec<MarkerPoint> {
+        let bytes_per_point = get_point_bytes_per_frame(&self.format, 1);
+        point_frame_data
+            .chunks_exact(bytes_per_point)
+            .take(self.markers_per_frame())
+            .map(|point_slice| self.parse_point(point_slice, processor))
+            .collect()
+    }
+
+    /// Decodes only the markers at the given column indices of a single frame.
+    pub(crate) fn parse_frame_columns(
+        &self,
+        point_frame_data: &[u8],
+        processor: &Processor,
+        columns: &[usize],
+    ) -> Vec<MarkerPoint> {
+        let bytes_per_point = get_point_bytes_per_frame(&self.format, 1);
+        columns
+            .iter()
+            .map(|column| {
+                let start = column * bytes_per_point;
+                let point_slice = &point_frame_data[start..start + bytes_per_point];
+                self.parse_point(point_slice, processor)
+            })
+            .collect()
+    }
+
+    /// Keeps the labels and descriptions of the markers at the given column indices.
+    pub(crate) fn retain_columns(&mut self, columns: &[usize]) {
+        self.labels = retain_indices(&self.labels, columns);
+        self.descriptions = retain_indices(&self.descriptions, columns);
+        self.header_markers = columns.len();
+    }
+
+    fn parse_point(&self, point_slice: &[u8], processor: &Processor) -> MarkerPoint {
+        let mut point = match self.format {
+            DataFormat::Float => parse_point_data_float(point_slice, processor),
+            DataFormat::Integer => parse_point_data_int(point_slice, processor),
+        };
+        match self.format {
+            DataFormat::Integer => point
+                .scale(self.scale_factor)
+                .scale_residual(self.scale_factor),
+            DataFormat::Float => point.scale_residual(self.scale_factor),
+        }
     }
 
     pub(crate) fn write_parameters(
@@ -189,17 +280,19 @@ impl Points {
             group_names_to_ids["POINT"],
             false,
         )?);
-        bytes.extend(Parameter::strings(self.labels.clone()).write(
+        bytes.extend(write_continued(
+            &self.labels,
+            |labels| Ok(Parameter::strings(labels)),
             processor,
-            "LABELS".to_string(),
+            "LABELS",
             group_names_to_ids["POINT"],
-            false,
         )?);
-        bytes.extend(Parameter::strings(self.descriptions.clone()).write(
+        bytes.extend(write_continued(
+            &self.descriptions,
+            |descriptions| Ok(Parameter::strings(descriptions)),
             processor,
-            "DESCRIPTIONS".to_string(),
+            "DESCRIPTIONS",
             group_names_to_ids["POINT"],
-            false,
         )?);
         if self.x_screen.is_some() {
             bytes.extend(Parameter::chars(self.x_screen.unwrap().to_vec())?.write(
@@ -223,13 +316,114 @@ impl Points {
             group_names_to_ids["POINT"],
             false,
         )?);
+        if self.long_frames || num_frames > u16::MAX as usize {
+            bytes.extend(Parameter::float(num_frames as f32).write(
+                processor,
+                "LONG_FRAMES".to_string(),
+                group_names_to_ids["POINT"],
+                false,
+            )?);
+        }
+        // RATE and SCALE are read back and checked against the header
+        bytes.extend(Parameter::float(self.frame_rate).write(
+            processor,
+            "RATE".to_string(),
+            group_names_to_ids["POINT"],
+            false,
+        )?);
+        let scale_factor = match self.format {
+            DataFormat::Float => -self.scale_factor.abs(),
+            DataFormat::Integer => self.scale_factor.abs(),
+        };
+        bytes.extend(Parameter::float(scale_factor).write(
+            processor,
+            "SCALE".to_string(),
+            group_names_to_ids["POINT"],
+            false,
+        )?);
+        if self.actual_fields || self.last_frame > u16::MAX as u32 {
+            bytes.extend(Parameter::integers(split_field(self.first_frame))?.write(
+                processor,
+                "ACTUAL_START_FIELD".to_string(),
+                group_names_to_ids["TRIAL"],
+                false,
+            )?);
+            bytes.extend(Parameter::integers(split_field(self.last_frame))?.write(
+                processor,
+                "ACTUAL_END_FIELD".to_string(),
+                group_names_to_ids["TRIAL"],
+                false,
+            )?);
+        }
         Ok(bytes)
     }
 
-    pub(crate) fn write_frame(&self, frame: usize, processor: &Processor) -> Vec<u8> {
+    /// Converts the points to the integer format with `POINT:SCALE` set to
+    /// the largest coordinate divided by 32000, and rounds the coordinates and residuals
+    /// to the values that are written.
+    /// Returns the largest change of a coordinate.
+    pub(crate) fn convert_to_integer(&mut self) -> f32 {
+        self.format = DataFormat::Integer;
+        self.scale_factor = self.optimal_scale_factor();
+        let mut max_error: f32 = 0.0;
+        let scale_factor = self.scale_factor;
+        for point in self.points.iter_mut() {
+            for coordinate in point.point.iter_mut() {
+                let rounded = (*coordinate / scale_factor).round() as i16 as f32 * scale_factor;
+                max_error = max_error.max((rounded - *coordinate).abs());
+                *coordinate = rounded;
+            }
+        }
+        self.round_residuals();
+        max_error
+    }
+
+    /// Converts the points to the floating-point format. The coordinates are kept as they are
+    /// and `POINT:SCALE` keeps scaling the residuals, which are rounded to the values that
+    /// are read back from the file.
+    pub(crate) fn convert_to_float(&mut self) {
+        self.format = DataFormat::Float;
+        self.round_residuals();
+    }
+
+    /// The largest coordinate divided by 32000, or the current scale factor if all points are zero.
+    fn optimal_scale_factor(&self) -> f32 {
+        let max_coordinate = self
+            .points
+            .iter()
+            .flat_map(|point| point.point.iter())
+            .filter(|coordinate| coordinate.is_finite())
+            .fold(0.0f32, |max, coordinate| max.max(coordinate.abs()));
+        match max_coordinate > 0.0 {
+            true => max_coordinate / 32000.0,
+            false if self.scale_factor != 0.0 => self.scale_factor.abs(),
+            false => 1.0,
+        }
+    }
+
+    /// Rounds the residuals to the values that are read back from the file.
+    fn round_residuals(&mut self) {
+        let scale_factor = self.scale_factor;
+        let format = self.format.clone();
+        for point in self.points.iter_mut() {
+            let residual = (point.residual / scale_factor).round();
+            let stored = match format {
+                DataFormat::Float => residual as i8 as f32,
+                DataFormat::Integer if residual > 0.0 => residual as i16 as u8 as f32,
+                DataFormat::Integer => residual as i16 as u8 as i8 as f32,
+            };
+            point.residual = stored * scale_factor;
+        }
+    }
+
+    pub(crate) fn write_frame(
+        &self,
+        frame: usize,
+        processor: &Processor,
+    ) -> Result<Vec<u8>, C3dWriteError> {
         let mut bytes = Vec::new();
         if frame >= self.points.rows() {
-            return bytes;
+            return Ok(bytes);
         }
         for (column, _) in self.iter_cols().enumerate() {
             match self.format {
@@ -249,18 +443,16 @@ impl Points {
                     bytes.extend_from_slice(&cameras_and_residual);
                 }
                 DataFormat::Integer => {
-                    let x = processor.i16_to_bytes(
-                        (self.points[frame][column][0] / self.scale_factor).round() as i16,
-                    );
-                    let y = processor.i16_to_bytes(
-                        (self.points[frame][column][1] / self.scale_factor).round() as i16,
-                    );
-                    let z = processor.i16_to_bytes(
-                        (self.points[frame][column][2] / self.scale_factor).round() as i16,
-                    );
-                    bytes.extend_from_slice(&x);
-                    bytes.extend_from_slice(&y);
-                    bytes.extend_from_slice(&z);
+                    for coordinate in self.points[frame][column].point {
+                        let value = (coordinate / self.scale_factor).round();
+                        if !(i16::MIN as f32..=i16::MAX as f32).contains(&value) {
+                            return Err(C3dWriteError::IntegerOverflow(format!(
+                                "marker {} in frame {} does not fit POINT:SCALE {}",
+                                column, frame, self.scale_factor
+                            )));
+                        }
+                        bytes.extend_from_slice(&processor.i16_to_bytes(value as i16));
+                    }
                     let residual =
                         (self.points[frame][column].residual / self.scale_factor).round() as i16;
                     let cameras = self.points[frame][column].cameras_as_byte();
@@ -277,23 +469,44 @@ impl Points {
                 }
             }
         }
-        bytes
+        Ok(bytes)
     }
 
     fn get_point_parameters(
         &mut self,
         parameters: &mut Parameters,
-    ) -> Result<(usize, Option<usize>, Option<usize>, Option<usize>), C3dParseError> {
-        let point_frames = match parameters.remove("POINT", "FRAMES") {
+        context: &mut ParseContext,
+    ) -> Result<FrameCounts, C3dParseError> {
+        let point_frames = match parameters.take("POINT", "FRAMES") {
             Some(parameter) => match &parameter.data {
-                ParameterData::Integer(frames) => frames[0] as u16 as usize,
-                ParameterData::Float(frames) => frames[0] as usize,
-                _ => 0,
+                ParameterData::Integer(frames) => Some(frames[0] as u16 as usize),
+                ParameterData::Float(frames) => Some(frames[0] as usize),
+                _ => {
+                    context.warn_parameter(
+                        C3dWarningKind::InvalidParameter,
+                        parameters,
+                        "POINT",
+                        "FRAMES",
+                        "0 frames".to_string(),
+                    );
+                    None
+                }
             },
-            _ => 0,
+            _ => {
+                context.warn_parameter(
+                    C3dWarningKind::MissingParameter,
+                    parameters,
+                    "POINT",
+                    "FRAMES",
+                    "0 frames".to_string(),
+                );
+                None
+            }
         };
         let (actual_start_field, actual_end_field) = get_actual_start_and_end_fields(parameters)?;
-        let long_frames = parameters.remove("POINT", "LONG_FRAMES");
+        self.actual_fields = actual_start_field.is_some() && actual_end_field.is_some();
+        let long_frames = parameters.take("POINT", "LONG_FRAMES");
+        self.long_frames = long_frames.is_some();
         let long_frames = match long_frames {
             Some(frames) => match &frames.data {
                 ParameterData::Integer(frames) => Some(frames[0] as u16 as usize),
@@ -302,7 +515,7 @@ impl Points {
             },
             None => None,
         };
-        let used = parameters.remove("POINT", "USED");
+        let used = parameters.take("POINT", "USED");
         let mut is_none_or_zero = used.is_none();
         if !is_none_or_zero {
             let used = used.unwrap();
@@ -324,32 +537,30 @@ impl Points {
                 long_frames,
             ))
         } else {
-            self.labels = parameters
-                .remove_or_err("POINT", "LABELS")?
-                .as_ref()
-                .try_into()?;
-            self.descriptions = parameters
-                .remove("POINT", "DESCRIPTIONS")
-                .unwrap_or(Parameter::strings(vec![" ".to_string()]))
-                .as_ref()
-                .try_into()?;
+            self.labels = parameters.take_continued_or_err("POINT", "LABELS")?;
+            self.descriptions = match parameters.take_continued("POINT", "DESCRIPTIONS")? {
+                Some(descriptions) => descriptions,
+                None => Parameter::strings(vec![" ".to_string()])
+                    .as_ref()
+                    .try_into()?,
+            };
             self.units = parameters
-                .remove_or_err("POINT", "UNITS")?
+                .take_or_err("POINT", "UNITS")?
                 .as_ref()
                 .try_into()?;
-            let x_screen = parameters.remove("POINT", "X_SCREEN");
+            let x_screen = parameters.take("POINT", "X_SCREEN");
             self.x_screen = match x_screen {
                 Some(parameter) => Some(parameter.as_ref().try_into()?),
                 None => None,
             };
-            let y_screen = parameters.remove("POINT", "Y_SCREEN");
+            let y_screen = parameters.take("POINT", "Y_SCREEN");
             self.y_screen = match y_screen {
                 Some(parameter) => Some(parameter.as_ref().try_into()?),
                 None => None,
             };
             // some c3d files don't have a POINT:RATE parameter
             // but it is required by the c3d spec
-            let rate = parameters.remove("POINT", "RATE");
+            let rate = parameters.take("POINT", "RATE");
             match rate {
                 Some(rate) => {
                     let rate: f32 = rate.as_ref().try_into()?;
@@ -359,7 +570,7 @@ impl Points {
                 }
                 None => {}
             }
-            let scale_factor = parameters.remove("POINT", "SCALE");
+            let scale_factor = parameters.take("POINT", "SCALE");
             match scale_factor {
                 Some(scale_factor) => {
                     let scale_factor: f32 = scale_factor.as_ref().try_into()?;
@@ -381,16 +592,34 @@ impl Points {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn calc_num_frames(
         &mut self,
-        data_bytes: &[u8],
-        point_frames: usize,
+        data_len: usize,
+        point_frames: Option<usize>,
         actual_start_field: Option<usize>,
         actual_end_field: Option<usize>,
         long_frames: Option<usize>,
         analog_samples_per_frame: u16,
+        parameters: &Parameters,
+        context: &mut ParseContext,
     ) -> Result<usize, C3dParseError> {
-        let mut num_frames = (self.last_frame as usize - self.first_frame as usize) + 1;
+        let mut num_frames =
+            (self.last_frame as usize + 1).saturating_sub(self.first_frame as usize);
+        if point_frames.is_some_and(|point_frames| point_frames != num_frames) {
+            context.warn_parameter(
+                C3dWarningKind::HeaderMismatch,
+                parameters,
+                "POINT",
+                "FRAMES",
+                format!(
+                    "{} frames from POINT:FRAMES instead of {} from the header",
+                    point_frames.unwrap(),
+                    num_frames
+                ),
+            );
+        }
+        let point_frames = point_frames.unwrap_or(0);
         if num_frames != point_frames {
             num_frames = point_frames;
             //    return Err(C3dParseError::NumFramesMismatch(
@@ -398,27 +627,87 @@ impl Points {
             //        num_frames,
             //    ));
         }
-        if actual_start_field.is_some() && actual_end_field.is_some() {
-            if (actual_end_field.unwrap() - actual_start_field.unwrap() + 1) as usize != num_frames
-            {
-                num_frames = (actual_end_field.unwrap() - actual_start_field.unwrap() + 1) as usize;
+        // an ACTUAL_END_FIELD of [-1, 0] is a 16-bit frame number that ran out, the
+        // recording is as long as its data section
+        let end_unknown = actual_end_field == Some(u16::MAX as usize) && long_frames.is_none();
+        if let (Some(actual_start_field), Some(actual_end_field), false) =
+            (actual_start_field, actual_end_field, end_unknown)
+        {
+            let actual_frames = (actual_end_field + 1).saturating_sub(actual_start_field);
+            if actual_frames != num_frames {
+                context.warn_parameter(
+                    C3dWarningKind::ParameterMismatch,
+                    parameters,
+                    "TRIAL",
+                    "ACTUAL_END_FIELD",
+                    format!(
+                        "{} frames from TRIAL:ACTUAL_START_FIELD and TRIAL:ACTUAL_END_FIELD instead of {}",
+                        actual_frames, num_frames
+                    ),
+                );
+                num_frames = actual_frames;
             }
         }
         if long_frames.is_some() {
             if long_frames.unwrap() > num_frames {
+                context.warn_parameter(
+                    C3dWarningKind::ParameterMismatch,
+                    parameters,
+                    "POINT",
+                    "LONG_FRAMES",
+                    format!(
+                        "{} frames from POINT:LONG_FRAMES instead of {}",
+                        long_frames.unwrap(),
+                        num_frames
+                    ),
+                );
                 num_frames = long_frames.unwrap();
             }
         }
-        let point_bytes_per_frame = get_point_bytes_per_frame(&self.format, self.cols()) as usize;
+        let point_bytes_per_frame =
+            get_point_bytes_per_frame(&self.format, self.markers_per_frame());
 
         let analog_bytes_per_frame =
             get_analog_bytes_per_frame(&self.format, analog_samples_per_frame)?;
 
         let bytes_per_frame = point_bytes_per_frame + analog_bytes_per_frame;
-        num_frames = match data_bytes.len() < num_frames * bytes_per_frame as usize {
+        if end_unknown && bytes_per_frame > 0 {
+            let available_frames = data_len / bytes_per_frame as usize;
+            if available_frames > num_frames {
+                context.warn_parameter(
+                    C3dWarningKind::ParameterMismatch,
+                    parameters,
+                    "TRIAL",
+                    "ACTUAL_END_FIELD",
+                    format!(
+                        "{} frames in the data section instead of {}",
+                        available_frames, num_frames
+                    ),
+                );
+                num_frames = available_frames;
+            }
+        }
+        // the header holds 16-bit frame numbers, longer recordings are numbered by the TRIAL parameters
+        // and a header range ending at 65535 may have been shifted down to keep its length
+        if let Some(actual_start_field) = actual_start_field {
+            if actual_start_field > u16::MAX as usize || self.last_frame == u16::MAX as u32 {
+                self.first_frame = actual_start_field as u32;
+            }
+        }
+        if self.first_frame as usize + num_frames > u16::MAX as usize + 1 {
+            self.last_frame = (self.first_frame as usize + num_frames - 1) as u32;
+        }
+        num_frames = match data_len < num_frames * bytes_per_frame as usize {
             true => {
-                let num_frames = data_bytes.len() / bytes_per_frame as usize;
-                num_frames
+                let available_frames = data_len / bytes_per_frame as usize;
+                context.warn(
+                    C3dWarningKind::TruncatedData,
+                    format!(
+                        "{} frames in the data section instead of {}",
+                        available_frames, num_frames
+                    ),
+                );
+                available_frames
             }
             false => num_frames,
             //return Err(C3dParseError::NotEnoughData);
@@ -433,36 +722,24 @@ impl Points {
         analog_samples_per_frame: u16,
         num_frames: usize,
     ) -> Result<&mut Self, C3dParseError> {
-        let mut point_data = Grid::new(num_frames, self.cols() as usize);
+        let mut point_data = Grid::new(num_frames, self.markers_per_frame());
 
-        let point_bytes_per_frame = get_point_bytes_per_frame(&self.format, self.cols()) as usize;
+        let point_bytes_per_frame =
+            get_point_bytes_per_frame(&self.format, self.markers_per_frame());
         let analog_bytes_per_frame =
             get_analog_bytes_per_frame(&self.format, analog_samples_per_frame)?;
         let bytes_per_frame = point_bytes_per_frame + analog_bytes_per_frame;
-        let bytes_per_point: u16 = match self.cols() {
-            0 => 0,
-            _ => point_bytes_per_frame as u16 / self.cols() as u16,
-        };
 
         for i in 0..point_data.rows() {
             let start = i * bytes_per_frame as usize;
             let end = start + bytes_per_frame as usize;
             let point_frame_data = &data_bytes[start..end - analog_bytes_per_frame as usize];
-            for j in 0..self.cols() as u16 {
-                let start = j * bytes_per_point;
-                let end = start + bytes_per_point;
-                let point_slice = &point_frame_data[start as usize..end as usize];
-                let mut point = match self.format {
-                    DataFormat::Float => parse_point_data_float(point_slice, processor),
-                    DataFormat::Integer => parse_point_data_int(point_slice, processor),
-                };
-                let point = match self.format {
-                    DataFormat::Integer => point
-                        .scale(self.scale_factor)
-                        .scale_residual(self.scale_factor),
-                    DataFormat::Float => point.scale_residual(self.scale_factor),
-                };
-                point_data[i][j as usize] = point;
+            for (j, point) in self
+                .parse_frame(point_frame_data, processor)
+                .into_iter()
+                .enumerate()
+            {
+                point_data[i][j] = point;
             }
         }
         self.points = point_data;
@@ -474,24 +751,24 @@ impl Points {
 fn get_actual_start_and_end_fields(
     parameters: &mut Parameters,
 ) -> Result<(Option<usize>, Option<usize>), C3dParseError> {
-    let end_field = parameters.remove("TRIAL", "ACTUAL_END_FIELD");
+    let end_field = parameters.take("TRIAL", "ACTUAL_END_FIELD");
     let actual_end_field = if end_field.is_some() {
         let end_field: Vec<i16> = end_field.unwrap().as_ref().try_into()?;
         if end_field.len() != 2 {
             None
         } else {
-            Some(end_field[0] as u16 as usize + (end_field[1] as u16 * 65535) as usize)
+            Some(join_field(&end_field))
         }
     } else {
         None
     };
-    let start_field = parameters.remove("TRIAL", "ACTUAL_START_FIELD");
+    let start_field = parameters.take("TRIAL", "ACTUAL_START_FIELD");
     let actual_start_field = if start_field.is_some() {
         let start_field: Vec<i16> = start_field.unwrap().as_ref().try_into()?;
         if start_field.len() != 2 {
             None
         } else {
-            Some(start_field[0] as u16 as usize + (start_field[1] as u16 * 65535) as usize)
+            Some(join_field(&start_field))
         }
     } else {
         None
@@ -499,6 +776,16 @@ fn get_actual_start_and_end_fields(
     Ok((actual_start_field, actual_end_field))
 }
 
+/// Reads a frame number stored as the low and high 16-bit words of a `TRIAL` field.
+fn join_field(field: &[i16]) -> usize {
+    field[0] as u16 as usize + field[1] as u16 as usize * 65536
+}
+
+/// Splits a frame number into the low and high 16-bit words of a `TRIAL` field.
+fn split_field(frame: u32) -> Vec<i16> {
+    vec![(frame & 0xFFFF) as u16 as i16, (frame >> 16) as u16 as i16]
+}
+
 fn parse_point_data_float(point_frame_data: &[u8], processor: &Processor) -> MarkerPoint {
     let x = processor.f32(point_frame_data[0..4].try_into().unwrap());
     let y = processor.f32(point_frame_data[4..8].try_into().unwrap());
@@ -54,7 +54,7 @@ impl Processor {
             0x54 => Ok(Processor::Intel),
             0x55 => Ok(Processor::Dec),
             0x56 => Ok(Processor::SgiMips),
-            _ => Err(C3dParseError::InvalidProcessorType),
+            _ => Err(C3dParseError::InvalidProcessorType(None)),
         }
     }
 
@@ -0,0 +1,313 @@
+//! Streaming access to the data section of a C3D file.
+//! The header and parameter sections are parsed once, after which frames
+//! are decoded one at a time from any `Read + Seek` source.
+use crate::c3d::C3d;
+use crate::data::{get_analog_bytes_per_frame, get_point_bytes_per_frame, MarkerPoint};
+use crate::options::{LoadOptions, ParseContext};
+use crate::C3dParseError;
+use grid::Grid;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+/// A single frame of the data section.
+/// The analog grid contains one row per analog sample recorded during the frame
+/// and one column per analog channel.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Zero-based index of the frame in the data section.
+    pub index: usize,
+    pub points: Vec<MarkerPoint>,
+    pub analog: Grid<f64>,
+}
+
+/// Reads a C3D file frame by frame instead of loading the whole data section into memory.
+///
+/// # Examples
+/// ```
+/// use c3dio::prelude::*;
+///
+/// let reader = C3dReader::open("tests/data/short.c3d").unwrap();
+/// let num_markers = reader.num_markers();
+/// for frame in reader {
+///     let frame = frame.unwrap();
+///     assert_eq!(frame.points.len(), num_markers);
+/// }
+/// ```
+pub struct C3dReader<R: Read + Seek> {
+    reader: R,
+    c3d: C3d,
+    analog_used: u16,
+    data_start_byte: u64,
+    point_bytes_per_frame: usize,
+    analog_bytes_per_frame: usize,
+    num_frames: usize,
+    current_frame: usize,
+    point_columns: Option<Vec<usize>>,
+    analog_columns: Option<Vec<usize>>,
+}
+
+impl C3dReader<BufReader<File>> {
+    /// Opens a C3D file from a file path string for streaming.
+    pub fn open(file_name: &str) -> Result<Self, C3dParseError> {
+        C3dReader::open_path(PathBuf::from(file_name))
+    }
+
+    /// Opens a C3D file from a file path for streaming.
+    pub fn open_path(file_path: PathBuf) -> Result<Self, C3dParseError> {
+        let file = File::open(file_path).map_err(C3dParseError::ReadError)?;
+        C3dReader::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read + Seek> C3dReader<R> {
+    /// Parses the header and parameter sections from the current position of `reader`
+    /// and positions it at the first frame of the data section.
+    pub fn new(reader: R) -> Result<Self, C3dParseError> {
+        C3dReader::with_context(reader, &mut ParseContext::default())
+    }
+
+    pub(crate) fn with_context(
+        mut reader: R,
+        context: &mut ParseContext,
+    ) -> Result<Self, C3dParseError> {
+        // the file may start anywhere in the source, such as an entry in an archive
+        let file_start = reader
+            .stream_position()
+            .map_err(C3dParseError::ReadError)?;
+        let (c3d, header_bytes, parameter_bytes, data_start_block_index) =
+            C3d::new().parse_basic_info(&mut reader)?;
+        let mut c3d = c3d.parse_header(&header_bytes)?.parse_parameters(
+            &header_bytes,
+            &parameter_bytes,
+            context,
+        )?;
+
+        if data_start_block_index == 0 {
+            return Err(C3dParseError::InvalidDataStartBlock(None));
+        }
+        let data_start_byte = file_start + 512 * (data_start_block_index as u64 - 1);
+        let end_byte = reader
+            .seek(SeekFrom::End(0))
+            .map_err(C3dParseError::ReadError)?;
+        let data_len = end_byte.saturating_sub(data_start_byte) as usize;
+
+        let analog_used = c3d
+            .analog
+            .parse_parameters(
+                &mut c3d.parameters,
+                &c3d.points.format,
+                c3d.points.frame_rate,
+                context,
+            )
+            .map_err(|e| context.locate(e, &c3d.parameters, &["ANALOG"]))?;
+        let num_frames = c3d
+            .points
+            .parse_parameters(
+                &mut c3d.parameters,
+                data_len,
+                c3d.analog.samples_per_frame,
+                context,
+            )
+            .map_err(|e| context.locate(e, &c3d.parameters, &["POINT", "TRIAL"]))?;
+        let point_bytes_per_frame =
+            get_point_bytes_per_frame(&c3d.points.format, c3d.points.markers_per_frame());
+        let analog_bytes_per_frame =
+            get_analog_bytes_per_frame(&c3d.points.format, c3d.analog.samples_per_frame)?;
+
+        c3d.record_parsed_parameters(num_frames);
+
+        reader
+            .seek(SeekFrom::Start(data_start_byte))
+            .map_err(C3dParseError::ReadError)?;
+        Ok(C3dReader {
+            reader,
+            c3d,
+            analog_used,
+            data_start_byte,
+            point_bytes_per_frame,
+            analog_bytes_per_frame,
+            num_frames,
+            current_frame: 0,
+            point_columns: None,
+            analog_columns: None,
+        })
+    }
+
+    /// The parsed header and parameter information.
+    /// The point and analog grids are empty, frames are only available through the reader.
+    pub fn c3d(&self) -> &C3d {
+        &self.c3d
+    }
+
+    /// Number of frames in the data section.
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+
+    /// Number of markers in each frame.
+    pub fn num_markers(&self) -> usize {
+        self.c3d.points.markers_per_frame()
+    }
+
+    /// Number of analog channels in each frame.
+    pub fn num_analog_channels(&self) -> usize {
+        self.analog_used as usize
+    }
+
+    /// Index of the frame that will be returned by the next call to `read_frame`.
+    pub fn position(&self) -> usize {
+        self.current_frame
+    }
+
+    /// Moves the reader to the frame at `index` (zero-based).
+    /// Seeking past the last frame positions the reader at the end of the data.
+    pub fn seek_frame(&mut self, index: usize) -> Result<(), C3dParseError> {
+        let index = index.min(self.num_frames);
+        let offset = self.data_start_byte + (index * self.bytes_per_frame()) as u64;
+        self.reader
+            .seek(SeekFrom::Start(offset))
+            .map_err(C3dParseError::ReadError)?;
+        self.current_frame = index;
+        Ok(())
+    }
+
+    /// Reads and decodes the next frame, or returns `None` after the last frame.
+    pub fn read_frame(&mut self) -> Result<Option<Frame>, C3dParseError> {
+        if self.current_frame >= self.num_frames {
+            return Ok(None);
+        }
+        let mut frame_bytes = vec![0u8; self.bytes_per_frame()];
+        self.reader
+            .read_exact(&mut frame_bytes)
+            .map_err(C3dParseError::ReadError)?;
+        let (point_bytes, analog_bytes) = frame_bytes.split_at(self.point_bytes_per_frame);
+        let processor = self.c3d.processor;
+        let points = match &self.point_columns {
+            Some(columns) => self
+                .c3d
+                .points
+                .parse_frame_columns(point_bytes, &processor, columns),
+            None => self.c3d.points.parse_frame(point_bytes, &processor),
+        };
+        let analog = match &self.analog_columns {
+            Some(columns) => self.c3d.analog.parse_frame_columns(
+                analog_bytes,
+                &processor,
+                &self.c3d.points.format,
+                self.analog_used,
+                columns,
+            ),
+            None => self.c3d.analog.parse_frame(
+                analog_bytes,
+                &processor,
+                &self.c3d.points.format,
+                self.analog_used,
+            ),
+        };
+        let frame = Frame {
+            index: self.current_frame,
+            points,
+            analog,
+        };
+        self.current_frame += 1;
+        Ok(Some(frame))
+    }
+
+    /// Consumes the reader and returns the underlying source.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Decodes the frames and columns selected by `options` into a `C3d`,
+    /// keeping the frame numbers, events and force platform channels consistent with the subset.
+    pub(crate) fn load(mut self, options: &LoadOptions) -> Result<C3d, C3dParseError> {
+        if let Some(labels) = &options.point_labels {
+            let columns = find_columns(&self.c3d.points.labels, labels, self.num_markers())?;
+            self.point_columns = Some(columns);
+        }
+        if let Some(labels) = &options.analog_labels {
+            let columns =
+                find_columns(&self.c3d.analog.labels, labels, self.num_analog_channels())?;
+            self.analog_columns = Some(columns);
+        }
+        let num_markers = match &self.point_columns {
+            Some(columns) => columns.len(),
+            None => self.num_markers(),
+        };
+        let num_analog_channels = match &self.analog_columns {
+            Some(columns) => columns.len(),
+            None => self.num_analog_channels(),
+        };
+
+        let frames = options.frames.clone().unwrap_or(0..self.num_frames);
+        let start = frames.start.min(self.num_frames);
+        let end = frames.end.clamp(start, self.num_frames);
+        self.seek_frame(start)?;
+        let mut points = Vec::with_capacity((end - start) * num_markers);
+        let mut analog = Vec::new();
+        for _ in start..end {
+            let frame = self.read_frame()?.ok_or(C3dParseError::NotEnoughData)?;
+            points.extend(frame.points);
+            analog.extend(frame.analog.into_vec());
+        }
+
+        let mut c3d = self.c3d;
+        c3d.points.points = Grid::from_vec(points, num_markers);
+        c3d.analog.analog = Grid::from_vec(analog, num_analog_channels);
+        if let Some(columns) = &self.point_columns {
+            c3d.points.retain_columns(columns);
+        }
+        if let Some(columns) = &self.analog_columns {
+            c3d.analog.retain_columns(columns);
+            c3d.forces.retain_analog_columns(columns);
+        }
+        if options.frames.is_some() {
+            let first_frame = c3d.points.first_frame as usize + start;
+            let last_frame = first_frame + (end - start).saturating_sub(1);
+            c3d.points.first_frame = first_frame as u32;
+            c3d.points.last_frame = last_frame as u32;
+            c3d.events
+                .retain_frames(first_frame, last_frame, c3d.points.frame_rate);
+            if start > 0 {
+                c3d.forces.shift_zero(start);
+            }
+        }
+        Ok(c3d)
+    }
+
+    fn bytes_per_frame(&self) -> usize {
+        self.point_bytes_per_frame + self.analog_bytes_per_frame
+    }
+}
+
+/// Finds the column of each label among the first `used` labels, ignoring padding.
+fn find_columns(
+    labels: &[String],
+    wanted: &[String],
+    used: usize,
+) -> Result<Vec<usize>, C3dParseError> {
+    wanted
+        .iter()
+        .map(|label| {
+            labels
+                .iter()
+                .take(used)
+                .position(|existing| existing.trim() == label.trim())
+                .ok_or_else(|| C3dParseError::MissingLabel(label.clone()))
+        })
+        .collect()
+}
+
+impl<R: Read + Seek> Iterator for C3dReader<R> {
+    type Item = Result<Frame, C3dParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = self.read_frame();
+        if frame.is_err() {
+            // stop iterating after the first error instead of repeating it
+            self.current_frame = self.num_frames;
+        }
+        frame.transpose()
+    }
+}
@@ -87,32 +87,32 @@ impl ToString for Seg {
 
 impl Seg {
     pub(crate) fn from_parameters(parameters: &mut Parameters) -> Result<Self, C3dParseError> {
-        let marker_diameter_parameter = parameters.remove("SEG", "MARKER_DIAMETER");
+        let marker_diameter_parameter = parameters.take("SEG", "MARKER_DIAMETER");
         let marker_diameter: Option<f32> = match marker_diameter_parameter {
             None => None,
             Some(parameter) => Some(parameter.as_ref().try_into()?),
         };
-        let data_limits_parameter = parameters.remove("SEG", "DATA_LIMITS");
+        let data_limits_parameter = parameters.take("SEG", "DATA_LIMITS");
         let data_limits: Option<Grid<f32>> = match data_limits_parameter {
             None => None,
             Some(parameter) => Some(parameter.as_ref().try_into()?),
         };
-        let acc_factor = parameters.remove("SEG", "ACC_FACTOR");
+        let acc_factor = parameters.take("SEG", "ACC_FACTOR");
         let acc_factor: Option<f32> = match acc_factor {
             None => None,
             Some(parameter) => Some(parameter.as_ref().try_into()?),
         };
-        let noise_factor = parameters.remove("SEG", "NOISE_FACTOR");
+        let noise_factor = parameters.take("SEG", "NOISE_FACTOR");
         let noise_factor: Option<f32> = match noise_factor {
             None => None,
             Some(parameter) => Some(parameter.as_ref().try_into()?),
         };
-        let residual_error_factor = parameters.remove("SEG", "RESIDUAL_ERROR_FACTOR");
+        let residual_error_factor = parameters.take("SEG", "RESIDUAL_ERROR_FACTOR");
         let residual_error_factor: Option<f32> = match residual_error_factor {
             None => None,
             Some(parameter) => Some(parameter.as_ref().try_into()?),
         };
-        let intersection_limit = parameters.remove("SEG", "INTERSECTION_LIMIT");
+        let intersection_limit = parameters.take("SEG", "INTERSECTION_LIMIT");
         let intersection_limit: Option<f32> = match intersection_limit {
             None => None,
             Some(parameter) => Some(parameter.as_ref().try_into()?),
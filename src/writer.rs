@@ -0,0 +1,223 @@
+//! Streaming output of the data section of a C3D file.
+//! The header and parameter sections are written up front, frames are
+//! appended one at a time and the frame counts are patched on `finish`.
+use crate::c3d::{validate_file_path, C3d};
+use crate::data::MarkerPoint;
+use crate::options::WriteOptions;
+use crate::C3dWriteError;
+use grid::Grid;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+/// Number of empty parameter blocks reserved after the parameters,
+/// so that parameters which depend on the number of frames can grow on `finish`.
+const RESERVED_PARAMETER_BLOCKS: usize = 1;
+
+/// Writes a C3D file frame by frame instead of holding the whole data section in memory.
+///
+/// The layout of the file is taken from a template `C3d`: the point and analog
+/// labels, data format, scales, rates and all other parameters are written as is.
+/// The number of markers and analog channels is taken from the template grids,
+/// or from the labels if the grids are empty.
+///
+/// # Examples
+/// ```
+/// use c3dio::prelude::*;
+/// use std::io::Cursor;
+///
+/// let reader = C3dReader::open("tests/data/short.c3d").unwrap();
+/// let template = reader.c3d().clone();
+/// let mut writer = C3dWriter::new(Cursor::new(Vec::new()), template).unwrap();
+/// for frame in reader {
+///     let frame = frame.unwrap();
+///     writer.write_frame(&frame.points, &frame.analog).unwrap();
+/// }
+/// let bytes = writer.finish().unwrap().into_inner();
+/// assert!(C3d::from_bytes(&bytes).is_ok());
+/// ```
+pub struct C3dWriter<W: Write + Seek> {
+    writer: W,
+    c3d: C3d,
+    num_markers: usize,
+    num_analog_channels: usize,
+    parameter_blocks: usize,
+    num_frames: usize,
+}
+
+impl C3dWriter<BufWriter<File>> {
+    /// Creates a C3D file at a file path string for streaming.
+    /// This function will overwrite any existing file.
+    pub fn create(file_name: &str, c3d: C3d) -> Result<Self, C3dWriteError> {
+        C3dWriter::create_path(PathBuf::from(file_name), c3d)
+    }
+
+    /// Creates a C3D file at a file path for streaming.
+    /// This function will overwrite any existing file.
+    pub fn create_path(file_name: PathBuf, c3d: C3d) -> Result<Self, C3dWriteError> {
+        validate_file_path(&file_name)?;
+        let file = File::create(file_name.clone())
+            .map_err(|e| C3dWriteError::WriteError(file_name.clone(), e))?;
+        C3dWriter::new(BufWriter::new(file), c3d)
+    }
+}
+
+impl<W: Write + Seek> C3dWriter<W> {
+    /// Writes the header and parameter sections of `c3d` to the start of `writer`.
+    /// Any point or analog data in `c3d` is ignored, frames are added with `write_frame`.
+    pub fn new(writer: W, c3d: C3d) -> Result<Self, C3dWriteError> {
+        C3dWriter::new_with(writer, c3d, &WriteOptions::default())
+    }
+
+    /// Writes the header and parameter sections like `new`, encoded as set by `options`.
+    pub fn new_with(
+        writer: W,
+        mut c3d: C3d,
+        options: &WriteOptions,
+    ) -> Result<Self, C3dWriteError> {
+        if let Some(processor) = options.processor {
+            c3d.processor = processor;
+        }
+        let num_markers = match c3d.points.cols() {
+            0 => c3d.points.labels.len(),
+            cols => cols,
+        };
+        let num_analog_channels = match c3d.analog.cols() {
+            0 => c3d.analog.labels.len(),
+            cols => cols,
+        };
+        // The template grids hold a single frame, which is encoded
+        // with the same functions used to write a full C3D file.
+        c3d.points.points = Grid::new(1, num_markers);
+        c3d.analog.analog = Grid::new(
+            c3d.analog.samples_per_channel_per_frame as usize,
+            num_analog_channels,
+        );
+        c3d.analog.samples_per_frame =
+            (num_analog_channels * c3d.analog.samples_per_channel_per_frame as usize) as u16;
+
+        let parameter_blocks = c3d
+            .write_parameter_blocks(0, c3d.processor)?
+            .len()
+            .div_ceil(512)
+            + RESERVED_PARAMETER_BLOCKS;
+        let mut c3d_writer = C3dWriter {
+            writer,
+            c3d,
+            num_markers,
+            num_analog_channels,
+            parameter_blocks,
+            num_frames: 0,
+        };
+        c3d_writer
+            .writer
+            .seek(SeekFrom::Start(0))
+            .map_err(C3dWriteError::WriteHeaderError)?;
+        c3d_writer.write_header_and_parameters()?;
+        Ok(c3d_writer)
+    }
+
+    /// The template used for the header and parameter sections.
+    pub fn c3d(&self) -> &C3d {
+        &self.c3d
+    }
+
+    /// Number of frames written so far.
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+
+    /// Appends a frame to the data section.
+    /// `points` must contain one entry per marker and `analog` must contain
+    /// one row per analog sample in the frame and one column per analog channel.
+    pub fn write_frame(
+        &mut self,
+        points: &[MarkerPoint],
+        analog: &Grid<f64>,
+    ) -> Result<&mut Self, C3dWriteError> {
+        if points.len() != self.num_markers {
+            return Err(C3dWriteError::InvalidFrameSize(format!(
+                "expected {} points, got {}",
+                self.num_markers,
+                points.len()
+            )));
+        }
+        let analog_size = (
+            self.c3d.analog.samples_per_channel_per_frame as usize,
+            self.num_analog_channels,
+        );
+        let analog_matches = match self.num_analog_channels {
+            0 => analog.is_empty(),
+            _ => analog.size() == analog_size,
+        };
+        if !analog_matches {
+            return Err(C3dWriteError::InvalidFrameSize(format!(
+                "expected {:?} analog samples, got {:?}",
+                analog_size,
+                analog.size()
+            )));
+        }
+        if self.num_markers > 0 {
+            self.c3d.points.points = Grid::from_vec(points.to_vec(), self.num_markers);
+        }
+        if self.num_analog_channels > 0 {
+            self.c3d.analog.analog = analog.clone();
+        }
+
+        let mut bytes = self.c3d.points.write_frame(0, &self.c3d.processor)?;
+        bytes.extend(self.c3d.analog.write_frame(
+            0,
+            &self.c3d.processor,
+            &self.c3d.points.format,
+        )?);
+        self.writer
+            .write_all(&bytes)
+            .map_err(C3dWriteError::WriteDataError)?;
+        self.num_frames += 1;
+        Ok(self)
+    }
+
+    /// Rewrites the header and parameter sections with the number of frames written
+    /// and returns the underlying writer positioned at the end of the data section.
+    pub fn finish(mut self) -> Result<W, C3dWriteError> {
+        // without frames the recording ends the frame before it starts
+        let last_frame =
+            (self.c3d.points.first_frame as usize + self.num_frames).saturating_sub(1);
+        self.c3d.points.last_frame = last_frame as u32;
+        self.writer
+            .seek(SeekFrom::Start(0))
+            .map_err(C3dWriteError::WriteHeaderError)?;
+        self.write_header_and_parameters()?;
+        self.writer
+            .seek(SeekFrom::End(0))
+            .map_err(C3dWriteError::WriteDataError)?;
+        self.writer.flush().map_err(C3dWriteError::WriteDataError)?;
+        Ok(self.writer)
+    }
+
+    fn write_header_and_parameters(&mut self) -> Result<(), C3dWriteError> {
+        let mut parameter_bytes = self
+            .c3d
+            .write_parameter_blocks(self.num_frames, self.c3d.processor)?;
+        if parameter_bytes.len() > self.parameter_blocks * 512 {
+            return Err(C3dWriteError::InsufficientParameterBlocks(format!(
+                "{} bytes of parameters do not fit in {} reserved blocks",
+                parameter_bytes.len(),
+                self.parameter_blocks
+            )));
+        }
+        parameter_bytes.resize(self.parameter_blocks * 512, 0);
+        parameter_bytes[2] = self.parameter_blocks as u8;
+        let header_bytes = self
+            .c3d
+            .write_header((2 + self.parameter_blocks) as u16, self.c3d.processor)?;
+
+        self.writer
+            .write_all(&header_bytes)
+            .map_err(C3dWriteError::WriteHeaderError)?;
+        self.writer
+            .write_all(&parameter_bytes)
+            .map_err(C3dWriteError::WriteParametersError)?;
+        Ok(())
+    }
+}
@@ -8,14 +8,28 @@ use common::assert_read_write;
 fn compare_force_plate_types() {
     // Sample10: differentiate between TYPE-2 and TYPE-4 force data
     // as well as TYPE-3 data with different force plate types
-    let two = C3d::load("tests/c3d_org_samples/sample_10/TYPE-2.C3D").unwrap();
-    let _twoa = C3d::load("tests/c3d_org_samples/sample_10/TYPE-2a.c3d").unwrap();
+    let _two = C3d::load("tests/c3d_org_samples/sample_10/TYPE-2.C3D").unwrap();
+    let twoa = C3d::load("tests/c3d_org_samples/sample_10/TYPE-2a.c3d").unwrap();
     let _three = C3d::load("tests/c3d_org_samples/sample_10/TYPE-3.c3d").unwrap();
-    let four = C3d::load("tests/c3d_org_samples/sample_10/TYPE-4.C3D").unwrap();
-    let _foura = C3d::load("tests/c3d_org_samples/sample_10/TYPE-4a.c3d").unwrap();
-
-    //TODO: show two and four have the same analog data after the calibration matrix is applied
-    //assert_eq!(two.force(0, 0).unwrap(), four.force(0, 0).unwrap());
+    let _four = C3d::load("tests/c3d_org_samples/sample_10/TYPE-4.C3D").unwrap();
+    let foura = C3d::load("tests/c3d_org_samples/sample_10/TYPE-4a.c3d").unwrap();
+
+    // the full calibration matrix only adds small cross-talk corrections
+    for plate in 0..2 {
+        let two = twoa.wrenches(plate).unwrap();
+        let four = foura.wrenches(plate).unwrap();
+        assert_eq!(two.len(), twoa.analog.rows());
+        let peak = two.iter().fold(0.0f64, |max, w| max.max(w.force[2].abs()));
+        for (two, four) in two.iter().zip(four.iter()) {
+            for axis in 0..3 {
+                assert!((two.force[axis] - four.force[axis]).abs() < 0.05 * peak);
+            }
+            if two.force[2].abs() > 0.5 * peak {
+                assert!((two.center_of_pressure[0] - four.center_of_pressure[0]).abs() < 5.0);
+                assert!((two.center_of_pressure[1] - four.center_of_pressure[1]).abs() < 5.0);
+            }
+        }
+    }
 }
 
 #[test]
@@ -13,4 +13,9 @@ fn poor_1() {
 fn poor_2() {
     //contains type 3 force plates but force plate channels are only 6 wide instead of 8
     //assert_read_write("tests/c3d_org_samples/sample_11/evart.c3d");
+    assert!(C3d::load("tests/c3d_org_samples/sample_11/evart.c3d").is_err());
+    let options = LoadOptions::new().lenient(true);
+    let c3d = C3d::load_with("tests/c3d_org_samples/sample_11/evart.c3d", &options).unwrap();
+    assert_eq!(c3d.forces.len(), 4);
+    assert_eq!(c3d.forces[3].channels, [19, 20, 21, 22, 23, 24]);
 }
@@ -6,3 +6,14 @@ fn sample24() {
     // Sample24: empty parameters
     assert!(C3d::load("tests/c3d_org_samples/sample_24/MotionMonitorC3D.c3d").is_err());
 }
+
+#[test]
+fn sample24_lenient() {
+    // the header stores the number of analog channels instead of the number of samples per frame
+    let options = LoadOptions::new().lenient(true);
+    let c3d =
+        C3d::load_with("tests/c3d_org_samples/sample_24/MotionMonitorC3D.c3d", &options).unwrap();
+    assert_eq!(c3d.points.rows(), 840);
+    assert_eq!(c3d.analog.samples_per_frame, 16 * 7);
+    assert_eq!(c3d.analog.size(), (840 * 7, 16));
+}
@@ -23,4 +23,12 @@ fn standing() {
 fn type1() {
     // does not include an ANALOG:RATE parameter
     //assert_read_write("tests/c3d_org_samples/sample_28/type1.C3D");
+    assert!(C3d::load("tests/c3d_org_samples/sample_28/type1.C3D").is_err());
+    let options = LoadOptions::new().lenient(true);
+    let c3d = C3d::load_with("tests/c3d_org_samples/sample_28/type1.C3D", &options).unwrap();
+    assert_eq!(
+        c3d.analog.rate,
+        c3d.points.frame_rate * c3d.analog.samples_per_channel_per_frame as f32
+    );
+    assert_eq!(c3d.points.rows(), 296);
 }
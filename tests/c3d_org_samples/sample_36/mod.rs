@@ -34,15 +34,19 @@ fn i36220() {
 
 #[test]
 fn f72610() {
-    //let f72610 = C3d::load("tests/c3d_org_samples/sample_36/72610framesf.c3d");
-    //assert!(f72610.is_ok());
-    //assert_eq!(f72610.unwrap().points.rows(), 72610);
+    let f72610 = C3d::load("tests/c3d_org_samples/sample_36/72610framesf.c3d");
+    assert!(f72610.is_ok());
+    let f72610 = f72610.unwrap();
+    assert_eq!(f72610.points.rows(), 72610);
+    assert_eq!(f72610.points.last_frame, f72610.points.first_frame + 72609);
 }
 
 #[test]
 fn i72610() {
-    // Contains [-1, 0] for actual_end_frame
-    //let i72610 = C3d::load("tests/c3d_org_samples/sample_36/72610framesi.c3d");
-    //assert!(i72610.is_ok());
-    //assert_eq!(i72610.unwrap().points.rows(), 72610);
+    // Contains [-1, 0] for actual_end_frame, the frames are counted from the data section
+    let i72610 = C3d::load("tests/c3d_org_samples/sample_36/72610framesi.c3d");
+    assert!(i72610.is_ok());
+    let i72610 = i72610.unwrap();
+    assert_eq!(i72610.points.rows(), 72610);
+    assert_eq!(i72610.points.last_frame, i72610.points.first_frame + 72609);
 }
@@ -1,4 +1,4 @@
-use c3dio::C3d;
+use c3dio::{C3d, Parameter};
 use std::fs::File;
 use std::io::{BufReader, Read};
 
@@ -16,3 +16,109 @@ fn compare_byte_to_file_load() {
     assert_eq!(sample19_file.events, sample19_bytes.events);
 }
 
+fn parameter_section(bytes: &[u8]) -> &[u8] {
+    let start = 512 * (bytes[0] as usize - 1);
+    &bytes[start..start + 512 * bytes[start + 2] as usize]
+}
+
+#[test]
+fn unchanged_parameter_section_round_trip() {
+    for path in [
+        "tests/c3d_org_samples/sample_01/Eb015pr.c3d",
+        "tests/c3d_org_samples/sample_02/dec_real.c3d",
+        "tests/c3d_org_samples/sample_10/TYPE-4.C3D",
+        "tests/c3d_org_samples/sample_26/Capture0002.c3d",
+    ] {
+        let bytes = std::fs::read(path).unwrap();
+        let written = C3d::from_bytes(&bytes).unwrap().to_bytes().unwrap();
+        assert!(
+            parameter_section(&bytes) == parameter_section(&written),
+            "{}",
+            path
+        );
+    }
+}
+
+#[test]
+fn write_is_deterministic() {
+    let build = || {
+        let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+        for group in ["CUSTOM_A", "CUSTOM_B", "CUSTOM_C", "CUSTOM_D"] {
+            c3d.parameters
+                .insert_group(group, format!("{} description", group))
+                .unwrap();
+            c3d.parameters
+                .insert(group, "VALUE", Parameter::integer(1))
+                .unwrap();
+        }
+        c3d.to_bytes().unwrap()
+    };
+    assert_eq!(build(), build());
+}
+
+#[test]
+fn group_descriptions_round_trip() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    *c3d.parameters.get_group_description_mut("POINT").unwrap() =
+        "Changed point description".to_string();
+    c3d.parameters
+        .insert_group("CUSTOM", "Custom description".to_string())
+        .unwrap();
+    let written = C3d::from_bytes(&c3d.to_bytes().unwrap()).unwrap();
+    assert_eq!(
+        written.parameters.get_group_description("POINT").unwrap(),
+        "Changed point description"
+    );
+    assert_eq!(
+        written.parameters.get_group_description("CUSTOM").unwrap(),
+        "Custom description"
+    );
+}
+
+/// Follows the record pointers of a parameter section and returns the group
+/// names with their descriptions, checking each group's pointer on the way.
+fn walk_group_records(section: &[u8]) -> Vec<(String, String)> {
+    let read_u16 = |at: usize| match section[3] {
+        86 => u16::from_be_bytes([section[at], section[at + 1]]),
+        _ => u16::from_le_bytes([section[at], section[at + 1]]),
+    };
+    let mut groups = Vec::new();
+    let mut index = 4;
+    loop {
+        let name_length = (section[index] as i8).unsigned_abs() as usize;
+        let id = section[index + 1] as i8;
+        let name = String::from_utf8(section[index + 2..index + 2 + name_length].to_vec()).unwrap();
+        let pointer_at = index + 2 + name_length;
+        let pointer = read_u16(pointer_at) as usize;
+        if id < 0 {
+            let description_length = section[pointer_at + 2] as usize;
+            assert_eq!(pointer, description_length + 3, "{}", name);
+            let description = &section[pointer_at + 3..pointer_at + 3 + description_length];
+            groups.push((name, String::from_utf8(description.to_vec()).unwrap()));
+        }
+        if pointer == 0 {
+            return groups;
+        }
+        index = pointer_at + pointer;
+    }
+}
+
+#[test]
+fn written_group_records_point_to_the_next_record() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    *c3d.parameters.get_group_description_mut("POINT").unwrap() =
+        "Changed point description".to_string();
+    c3d.parameters
+        .insert_group("CUSTOM", "Custom description".to_string())
+        .unwrap();
+    c3d.parameters
+        .insert("CUSTOM", "VALUE", Parameter::integer(1))
+        .unwrap();
+    let bytes = c3d.to_bytes().unwrap();
+    let groups = walk_group_records(parameter_section(&bytes));
+    assert!(groups.contains(&("POINT".to_string(), "Changed point description".to_string())));
+    assert!(groups.contains(&("CUSTOM".to_string(), "Custom description".to_string())));
+    let written = C3d::from_bytes(&bytes).unwrap();
+    let value = written.parameters.get("CUSTOM", "VALUE").unwrap();
+    assert_eq!(value.data, Parameter::integer(1).data);
+}
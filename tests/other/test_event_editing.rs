@@ -0,0 +1,96 @@
+use c3dio::prelude::*;
+use c3dio::Event;
+
+fn event(label: &str, context: &str) -> Event {
+    Event {
+        label: label.to_string(),
+        context: context.to_string(),
+        ..Event::new()
+    }
+}
+
+#[test]
+fn insert_in_time_order() {
+    let mut c3d = C3d::load("tests/c3d_org_samples/sample_32/vicon_zerowire.c3d").unwrap();
+    // the data starts at frame 306, which is 3.05 seconds at 100 Hz
+    let index = c3d.insert_event_at_row(event("Start", "General"), 0);
+    assert_eq!(index, 0);
+    assert_eq!(c3d.events[0].time, 3.05);
+    assert_eq!(c3d.events[0].frame(c3d.points.frame_rate), 306);
+    let index = c3d
+        .events
+        .insert_at_frame(event("Middle", "General"), 400, 100.0);
+    // after the foot strike that is already at 3.99 seconds
+    assert_eq!(index, 4);
+    assert_eq!(c3d.events[index].time, 3.99);
+    let index = c3d.events.insert_at_time(event("End", "General"), 10.0);
+    assert_eq!(index, c3d.events.len() - 1);
+    assert!(c3d
+        .events
+        .windows(2)
+        .all(|pair| pair[0].time <= pair[1].time));
+}
+
+#[test]
+fn contexts_are_added_once() {
+    let mut c3d = C3d::load("tests/c3d_org_samples/sample_32/vicon_zerowire.c3d").unwrap();
+    c3d.insert_event_at_row(event("Foot Strike", "left"), 10);
+    assert_eq!(c3d.events.event_context().used, Some(3));
+    c3d.insert_event_at_row(event("Jump", "Both"), 20);
+    let event_context = c3d.events.event_context();
+    assert_eq!(event_context.used, Some(4));
+    assert_eq!(event_context.labels.as_ref().unwrap()[3], "Both");
+    assert_eq!(event_context.descriptions.as_ref().unwrap()[3], "");
+    assert_eq!(event_context.icon_ids.as_ref().unwrap(), &vec![0, 1, 2, 0]);
+
+    let reread = C3d::from_bytes(&c3d.to_bytes().unwrap()).unwrap();
+    assert_eq!(reread.events.event_context(), c3d.events.event_context());
+    assert_eq!(reread.events.with_context("Both").len(), 1);
+
+    assert!(c3d.events.remove_context("Left"));
+    assert!(!c3d.events.remove_context("Left"));
+    let event_context = c3d.events.event_context();
+    assert_eq!(event_context.used, Some(3));
+    assert_eq!(
+        event_context.labels.as_ref().unwrap(),
+        &vec!["General", "Right", "Both"]
+    );
+    assert_eq!(event_context.icon_ids.as_ref().unwrap(), &vec![0, 2, 0]);
+    assert_eq!(event_context.descriptions.as_ref().unwrap().len(), 3);
+}
+
+#[test]
+fn query_events() {
+    let mut c3d = C3d::load("tests/c3d_org_samples/sample_32/vicon_zerowire.c3d").unwrap();
+    assert_eq!(c3d.events.with_label("foot strike").len(), 5);
+    assert_eq!(c3d.events.with_context("Right").len(), 5);
+    assert_eq!(c3d.events.with_subject("Scott Drummond").len(), 10);
+    let between = c3d.events.between(3.5, 4.59);
+    assert_eq!(between.len(), 4);
+    assert_eq!(between[0].time, 3.5);
+    assert_eq!(c3d.events.between_frames(400, 412, 100.0).len(), 2);
+    assert_eq!(c3d.events.nearest(4.0).unwrap().time, 3.99);
+
+    c3d.events.reverse();
+    let sorted = c3d.events.sorted();
+    assert_eq!(sorted[0].time, 3.34);
+    assert_eq!(c3d.events[0].time, 5.88);
+    c3d.events.sort_by_time();
+    assert_eq!(c3d.events[0].time, 3.34);
+
+    let removed = c3d.events.remove_where(|event| event.label == "Foot Off");
+    assert_eq!(removed.len(), 5);
+    assert_eq!(c3d.events.len(), 5);
+    assert!(c3d.events.iter().all(|event| event.label == "Foot Strike"));
+    assert_eq!(c3d.events.event_context().used, Some(3));
+}
+
+#[test]
+fn minutes_and_seconds() {
+    let mut event = Event::new();
+    event.time = 75.5;
+    assert_eq!(event.minutes_seconds(), [1.0, 15.5]);
+    event.set_minutes_seconds([2.0, 3.0]);
+    assert_eq!(event.time, 123.0);
+    assert_eq!(event.frame(100.0), 12301);
+}
@@ -0,0 +1,217 @@
+use c3dio::prelude::*;
+
+fn plate(plate_type: ForcePlatformType) -> ForcePlatform {
+    let mut plate = ForcePlatform::default();
+    plate.plate_type = plate_type;
+    plate.origin[2] = 40.0;
+    plate
+}
+
+fn identity(outputs: usize, channels: usize) -> Vec<Vec<f32>> {
+    (0..channels)
+        .map(|column| {
+            (0..outputs)
+                .map(|row| (row == column) as u8 as f32)
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn type_2_centre_of_pressure() {
+    // 100 N straight down at (10, -20) on the surface, 40 mm above the transducer
+    let wrench = plate(ForcePlatformType::Type2)
+        .wrench_from_channels(&[0.0, 0.0, -100.0, 2000.0, 1000.0, 5.0, 0.0, 0.0]);
+    assert_eq!(wrench.force, [0.0, 0.0, -100.0]);
+    assert_eq!(wrench.center_of_pressure, [10.0, -20.0, 0.0]);
+    assert_eq!(wrench.free_moment, 5.0);
+}
+
+#[test]
+fn type_4_applies_cal_matrix() {
+    let channels = [3.0, -2.0, -150.0, 800.0, -400.0, 12.0];
+    let mut four = plate(ForcePlatformType::Type4);
+    four.cal_matrix = Some(identity(6, 6));
+    let two = plate(ForcePlatformType::Type2).wrench_from_channels(&channels);
+    assert_eq!(four.wrench_from_channels(&channels), two);
+
+    let mut doubled = identity(6, 6);
+    doubled[0][0] = 2.0;
+    // the first column also feeds the first channel into the y force
+    doubled[0][1] = 1.0;
+    four.cal_matrix = Some(doubled);
+    let wrench = four.wrench_from_channels(&channels);
+    assert_eq!(wrench.force, [6.0, 1.0, -150.0]);
+}
+
+#[test]
+fn type_1_uses_stored_centre_of_pressure() {
+    let wrench = plate(ForcePlatformType::Type1)
+        .wrench_from_channels(&[0.0, 0.0, -100.0, 15.0, -5.0, 3.0, 0.0, 0.0]);
+    assert_eq!(wrench.center_of_pressure, [15.0, -5.0, 0.0]);
+    assert_eq!(wrench.moment, [500.0, 1500.0, 3.0]);
+}
+
+#[test]
+fn wrench_per_analog_sample() {
+    let c3d = C3d::load("tests/c3d_org_samples/sample_10/TYPE-4a.c3d").unwrap();
+    let wrenches = c3d.wrenches(1).unwrap();
+    assert_eq!(wrenches.len(), c3d.analog.rows());
+    let frame = 120;
+    let sample = frame * c3d.analog.samples_per_channel_per_frame as usize;
+    let force = c3d.force(1, frame).unwrap();
+    assert_eq!(force[2], wrenches[sample].force[2] as f32);
+    assert!(c3d.wrench(2, 0).is_none());
+    assert!(c3d.wrench(1, c3d.analog.rows()).is_none());
+}
+
+#[test]
+fn kistler_types_agree() {
+    // FX12, FX34, FY14, FY23, FZ1, FZ2, FZ3, FZ4
+    let eight = [4.0, 6.0, -1.0, 3.0, -100.0, -50.0, -30.0, -20.0];
+    let mut three = plate(ForcePlatformType::Type3);
+    three.origin[0] = 120.0;
+    three.origin[1] = 200.0;
+    three.origin[2] = -40.0;
+    let expected = three.wrench_from_channels(&eight);
+
+    let mut seven = three.clone();
+    seven.plate_type = ForcePlatformType::Type7;
+    seven.cal_matrix = Some(identity(8, 8));
+    assert_eq!(seven.wrench_from_channels(&eight), expected);
+
+    // the same loads split over the four sensors
+    let twelve = [
+        4.0, -1.0, -100.0, 0.0, 3.0, -50.0, 6.0, 0.0, -30.0, 0.0, 0.0, -20.0,
+    ];
+    let mut six = three.clone();
+    six.plate_type = ForcePlatformType::Type6;
+    six.cal_matrix = Some(identity(12, 12));
+    let wrench = six.wrench_from_channels(&twelve);
+    assert_eq!(wrench.force, expected.force);
+    for axis in 0..3 {
+        assert!((wrench.moment[axis] - expected.moment[axis]).abs() < 1e-9);
+    }
+
+    // a 6 by 8 matrix that sums the eight channels into forces
+    let mut cal_matrix = vec![vec![0.0; 6]; 8];
+    cal_matrix[0][0] = 1.0;
+    cal_matrix[1][0] = 1.0;
+    cal_matrix[2][1] = 1.0;
+    cal_matrix[3][1] = 1.0;
+    for channel in 4..8 {
+        cal_matrix[channel][2] = 1.0;
+    }
+    let mut five = three.clone();
+    five.plate_type = ForcePlatformType::Type5;
+    five.cal_matrix = Some(cal_matrix);
+    assert_eq!(five.wrench_from_channels(&eight).force, expected.force);
+}
+
+#[test]
+fn write_additional_plate_types() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    c3d.forces[0].plate_type = ForcePlatformType::Type6;
+    c3d.forces[0].channels = (1..=12).collect();
+    c3d.forces[0].cal_matrix = Some(identity(12, 12));
+    c3d.forces[1].plate_type = ForcePlatformType::Type4;
+    c3d.forces[1].cal_matrix = Some(identity(6, 6));
+    let reread = C3d::from_bytes(&c3d.to_bytes().unwrap()).unwrap();
+    assert_eq!(reread.forces, c3d.forces);
+    // the type 4 plate keeps its six channels next to the twelve of the type 6 plate
+    assert_eq!(reread.forces[1].channels.len(), 6);
+    assert_eq!(reread.wrench(0, 10), c3d.wrench(0, 10));
+}
+
+#[test]
+fn lab_frame_of_rotated_plate() {
+    // plate x along the lab y axis and plate z pointing down, centred at (100, 200, 0)
+    let mut plate = plate(ForcePlatformType::Type2);
+    plate.corners[0] = [300.0, 400.0, 0.0];
+    plate.corners[1] = [300.0, 0.0, 0.0];
+    plate.corners[2] = [-100.0, 0.0, 0.0];
+    plate.corners[3] = [-100.0, 400.0, 0.0];
+    assert_eq!(plate.center(), [100.0, 200.0, 0.0]);
+    let wrench = plate.wrench_from_channels(&[10.0, 0.0, -100.0, 2000.0, 1000.0, 5.0]);
+    let lab = plate.to_lab(&wrench);
+    assert_eq!(lab.force, [0.0, 10.0, 100.0]);
+    // the plate centre of pressure (14, -20) is (-20, 14) in the lab
+    assert!((lab.center_of_pressure[0] - 80.0).abs() < 1e-9);
+    assert!((lab.center_of_pressure[1] - 214.0).abs() < 1e-9);
+    assert_eq!(lab.moment[0..2], [0.0, 0.0]);
+    assert_eq!(lab.moment[2], -wrench.free_moment);
+}
+
+#[test]
+fn lab_wrenches_follow_the_foot() {
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let wrenches = c3d.lab_wrenches(1).unwrap();
+    assert_eq!(wrenches.len(), c3d.points.rows());
+    let (frame, peak) = wrenches
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.force[2].total_cmp(&b.1.force[2]))
+        .unwrap();
+    assert!(peak.force[2] > 500.0);
+    let ankle = c3d.points.labels.iter().position(|l| l == "RANK").unwrap();
+    let ankle = c3d.points[frame][ankle].point;
+    let dx = peak.center_of_pressure[0] - ankle[0] as f64;
+    let dy = peak.center_of_pressure[1] - ankle[1] as f64;
+    assert!((dx * dx + dy * dy).sqrt() < 150.0);
+}
+
+#[test]
+fn lab_wrenches_need_analog_for_every_frame() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    c3d.analog.analog.pop_row();
+    assert!(c3d.wrenches(1).is_some());
+    assert!(c3d.lab_wrenches(1).is_none());
+}
+
+#[test]
+fn changed_channels_keep_the_file_width() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    assert!(c3d.forces.iter().all(|plate| plate.channels.len() == 6));
+    c3d.forces[0].channels.swap(0, 1);
+    let reread = C3d::from_bytes(&c3d.to_bytes().unwrap()).unwrap();
+    // FORCE_PLATFORM:CHANNEL is written 6 rows wide, not padded to 8
+    assert!(reread.forces.iter().all(|plate| plate.channels.len() == 6));
+    assert_eq!(reread.forces[0].channels, c3d.forces[0].channels);
+}
+
+/// short.c3d with the first plate's `FORCE_PLATFORM:TYPE` set to 21.
+fn treadmill_bytes() -> Vec<u8> {
+    let mut bytes = std::fs::read("tests/data/short.c3d").unwrap();
+    let start = 512 * (bytes[0] as usize - 1);
+    // the record of FORCE_PLATFORM (group 4) TYPE, holding two integers
+    let record = [4, 4, b'T', b'Y', b'P', b'E'];
+    let index = start
+        + bytes[start..]
+            .windows(record.len())
+            .position(|window| window == record)
+            .unwrap();
+    let data = index + record.len() + 5;
+    assert_eq!(&bytes[data..data + 2], &[4, 0]);
+    bytes[data] = 21;
+    bytes
+}
+
+#[test]
+fn other_plate_types_load_and_write_back() {
+    let bytes = treadmill_bytes();
+    let c3d = C3d::from_bytes(&bytes).unwrap();
+    assert_eq!(c3d.forces[0].plate_type, ForcePlatformType::Other(21));
+    assert_eq!(c3d.forces[0].channels.len(), 6);
+    assert_eq!(c3d.forces[0].cal_matrix.as_ref().unwrap().len(), 6);
+    assert!(c3d.wrench(0, 10).is_none());
+    assert!(c3d.wrench(1, 10).is_some());
+
+    let written = c3d.to_bytes().unwrap();
+    let parameter_section = |bytes: &[u8]| {
+        let start = 512 * (bytes[0] as usize - 1);
+        bytes[start..start + 512 * bytes[start + 2] as usize].to_vec()
+    };
+    assert!(parameter_section(&written) == parameter_section(&bytes));
+    let reread = C3d::from_bytes(&written).unwrap();
+    assert_eq!(reread.forces, c3d.forces);
+}
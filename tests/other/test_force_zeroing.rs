@@ -0,0 +1,77 @@
+use c3dio::prelude::*;
+
+fn mean_vertical_force(c3d: &C3d, plate: usize, frames: std::ops::Range<usize>) -> f64 {
+    let samples = c3d.analog.samples_per_channel_per_frame as usize;
+    let wrenches = c3d.wrenches(plate).unwrap();
+    let window = &wrenches[frames.start * samples..frames.end * samples];
+    window.iter().map(|wrench| wrench.force[2]).sum::<f64>() / window.len() as f64
+}
+
+#[test]
+fn zero_removes_drift() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let fz = c3d.forces[1].channels[2] as usize - 1;
+    for sample in 0..c3d.analog.rows() {
+        c3d.analog[sample][fz] += 20.0;
+    }
+    let loaded = c3d.wrenches(1).unwrap();
+    let offsets = c3d
+        .zero_force_platforms(&ZeroOptions::new().frames(0..10))
+        .unwrap();
+    assert_eq!(offsets.len(), c3d.forces.len());
+    assert_eq!(c3d.forces[1].zero_offsets, offsets[1]);
+    assert!(mean_vertical_force(&c3d, 1, 0..10).abs() < 1e-9);
+    let zeroed = c3d.wrenches(1).unwrap();
+    // the kinetics are linear in the channels
+    let drift = c3d.forces[1].wrench_from_channels(&offsets[1]).force;
+    let (loaded, zeroed) = (loaded[500].force, zeroed[500].force);
+    for ((loaded, zeroed), drift) in loaded.iter().zip(zeroed.iter()).zip(drift.iter()) {
+        assert!((loaded - zeroed - drift).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn write_back_changes_analog() {
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let options = ZeroOptions::new().frames(0..10);
+    let mut kept = c3d.clone();
+    kept.zero_force_platforms(&options).unwrap();
+    let mut written = c3d.clone();
+    written
+        .zero_force_platforms(&options.clone().write_back(true))
+        .unwrap();
+    assert!(written.forces[1].zero_offsets.is_empty());
+    assert_ne!(written.analog, c3d.analog);
+    assert_eq!(written.wrenches(1), kept.wrenches(1));
+    // channels that no plate uses are left alone
+    let unused = (0..c3d.analog.cols())
+        .find(|column| {
+            !c3d.forces
+                .iter()
+                .any(|plate| plate.channels.contains(&(*column as u8 + 1)))
+        })
+        .unwrap();
+    assert_eq!(
+        written.analog.iter_col(unused).collect::<Vec<_>>(),
+        c3d.analog.iter_col(unused).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn zero_parameter_window() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    c3d.forces.zero = [3, 6];
+    let expected = c3d
+        .clone()
+        .zero_force_platforms(&ZeroOptions::new().frames(2..6));
+    assert_eq!(c3d.zero_force_platforms(&ZeroOptions::new()), expected);
+
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    c3d.forces.zero = [0, 0];
+    assert!(c3d.zero_force_platforms(&ZeroOptions::new()).is_none());
+    let frames = c3d.points.rows();
+    assert!(c3d
+        .zero_force_platforms(&ZeroOptions::new().frames(frames..frames + 1))
+        .is_none());
+    assert!(c3d.forces.iter().all(|plate| plate.zero_offsets.is_empty()));
+}
@@ -0,0 +1,173 @@
+use c3dio::gait;
+use c3dio::Event;
+use c3dio::prelude::*;
+
+fn zeroed(file: &str) -> C3d {
+    let mut c3d = C3d::load(file).unwrap();
+    c3d.zero_force_platforms(&ZeroOptions::new()).unwrap();
+    c3d
+}
+
+#[test]
+fn detect_walking_contacts() {
+    let mut c3d = zeroed("tests/c3d_org_samples/sample_10/TYPE-3.c3d");
+    let added = c3d.detect_force_events(&ForceEventOptions::new());
+    assert_eq!(added, 8);
+    let events = c3d
+        .events
+        .iter()
+        .map(|event| (event.label.as_str(), event.context.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        events,
+        vec![
+            ("Foot Strike", "Right"),
+            ("Foot Strike", "Left"),
+            ("Foot Off", "Right"),
+            ("Foot Strike", "Right"),
+            ("Foot Off", "Left"),
+            ("Foot Strike", "Left"),
+            ("Foot Off", "Right"),
+            ("Foot Off", "Left"),
+        ]
+    );
+    assert!(c3d
+        .events
+        .windows(2)
+        .all(|pair| pair[0].time <= pair[1].time));
+    // the right foot lands on the second plate at frame 109
+    assert_eq!(c3d.events[0].time, 1.8);
+    assert_eq!(c3d.events[0].id, ['R', 'F', 'S', ' ']);
+    assert_eq!(c3d.events[0].description, "Detected on force platform 2");
+}
+
+#[test]
+fn sides_from_mapping() {
+    let mut c3d = zeroed("tests/c3d_org_samples/sample_10/TYPE-3.c3d");
+    let options = ForceEventOptions::new()
+        .side(0, "General")
+        .side(1, "General")
+        .side(2, "General")
+        .side(3, "General");
+    c3d.detect_force_events(&options);
+    assert!(c3d.events.iter().all(|event| event.context == "General"));
+}
+
+#[test]
+fn short_contacts_are_ignored() {
+    let mut c3d = zeroed("tests/c3d_org_samples/sample_10/TYPE-3.c3d");
+    let options = ForceEventOptions::new().min_duration(2.0);
+    assert_eq!(c3d.detect_force_events(&options), 0);
+    let options = ForceEventOptions::new().threshold(1e6);
+    assert_eq!(c3d.detect_force_events(&options), 0);
+    assert!(c3d.events.is_empty());
+}
+
+#[test]
+fn detected_events_are_written() {
+    let mut c3d = C3d::load("tests/c3d_org_samples/sample_10/TYPE-2a.c3d").unwrap();
+    assert_eq!(c3d.detect_force_events(&ForceEventOptions::new()), 4);
+    let reread = C3d::from_bytes(&c3d.to_bytes().unwrap()).unwrap();
+    assert_eq!(reread.events.len(), 4);
+    for (written, read) in c3d.events.iter().zip(reread.events.iter()) {
+        assert_eq!(written.label, read.label);
+        assert_eq!(written.context, read.context);
+        assert_eq!(written.time, read.time);
+    }
+}
+
+fn matches_force_events(method: KinematicMethod, tolerance: f32) {
+    let c3d = zeroed("tests/c3d_org_samples/sample_10/TYPE-3.c3d");
+    let force_events = gait::force_events(&c3d, &ForceEventOptions::new());
+    let options = KinematicEventOptions::new().method(method);
+    let kinematic_events = gait::kinematic_events(&c3d, &options);
+    let matches = gait::compare_events(&force_events, &kinematic_events, tolerance);
+    assert_eq!(matches.len(), force_events.len());
+    assert!(matches.iter().all(|pair| pair.existing.is_some()));
+    assert!(matches
+        .iter()
+        .all(|pair| pair.difference.unwrap().abs() <= tolerance));
+    assert!(kinematic_events
+        .iter()
+        .all(|event| event.description == "Detected from marker trajectories"));
+}
+
+#[test]
+fn coordinate_events_match_force_events() {
+    matches_force_events(KinematicMethod::Coordinate, 0.05);
+}
+
+#[test]
+fn velocity_events_match_force_events() {
+    // the toe speeds up before it leaves the ground
+    matches_force_events(KinematicMethod::Velocity, 0.2);
+}
+
+#[test]
+fn coordinate_events_alternate() {
+    let c3d = C3d::load("tests/c3d_org_samples/sample_10/TYPE-3.c3d").unwrap();
+    let events = gait::kinematic_events(&c3d, &KinematicEventOptions::new());
+    for side in ["Left", "Right"] {
+        let labels = events
+            .iter()
+            .filter(|event| event.context == side)
+            .map(|event| event.label.as_str())
+            .collect::<Vec<_>>();
+        assert!(labels.len() > 4);
+        assert!(labels.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+}
+
+#[test]
+fn kinematic_events_need_markers() {
+    let mut c3d = C3d::load("tests/c3d_org_samples/sample_10/TYPE-2a.c3d").unwrap();
+    // the sacrum marker is called VSAC in this file
+    assert_eq!(
+        c3d.detect_kinematic_events(&KinematicEventOptions::new()),
+        0
+    );
+    let options = KinematicEventOptions::new().sacrum_markers(&["VSAC"]);
+    assert_eq!(c3d.detect_kinematic_events(&options), 10);
+    assert!(c3d.events.supports_events_labels);
+    assert_eq!(c3d.events[0].id, ['R', 'F', 'S', ' ']);
+    assert!(c3d
+        .events
+        .windows(2)
+        .all(|pair| pair[0].time <= pair[1].time));
+    let options = KinematicEventOptions::new().heel_markers("LHEEL", "RHEEL");
+    let events = gait::kinematic_events(&c3d, &options);
+    assert!(events.is_empty());
+}
+
+#[test]
+fn compare_events_by_label_and_context() {
+    let mut c3d = C3d::load("tests/c3d_org_samples/sample_10/TYPE-2a.c3d").unwrap();
+    c3d.detect_force_events(&ForceEventOptions::new());
+    let mut detected = c3d.events.to_vec();
+    detected[0].context = "Right".to_string();
+    detected[1].time += 0.5;
+    let matches = gait::compare_events(&detected, &c3d.events, 0.1);
+    assert_eq!(matches[0].existing, None);
+    assert_eq!(matches[1].existing, None);
+    assert_eq!(matches[2].existing, Some(2));
+    assert_eq!(matches[2].difference, Some(0.0));
+}
+
+#[test]
+fn existing_events_keep_their_order() {
+    let mut c3d = C3d::load("tests/c3d_org_samples/sample_10/TYPE-2a.c3d").unwrap();
+    for (label, time) in [("Late", 5.0), ("Early", 0.0)] {
+        let mut event = Event::new();
+        event.label = label.to_string();
+        event.time = time;
+        c3d.events.push(event);
+    }
+    assert_eq!(c3d.detect_force_events(&ForceEventOptions::new()), 4);
+    let labels = c3d
+        .events
+        .iter()
+        .map(|event| event.label.as_str())
+        .filter(|label| *label == "Late" || *label == "Early")
+        .collect::<Vec<_>>();
+    assert_eq!(labels, vec!["Late", "Early"]);
+}
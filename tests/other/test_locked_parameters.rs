@@ -0,0 +1,90 @@
+use c3dio::prelude::*;
+
+fn locked_c3d() -> C3d {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let mut parameter = Parameter::integer(7);
+    parameter.locked = true;
+    c3d.parameters
+        .insert("CUSTOM", "LOCKED", parameter)
+        .unwrap();
+    c3d.parameters
+        .insert("CUSTOM", "OPEN", Parameter::integer(3))
+        .unwrap();
+    c3d.parameters
+        .insert_group("PROTECTED", "Vendor settings".to_string())
+        .unwrap();
+    c3d.parameters
+        .insert("PROTECTED", "VALUE", Parameter::integer(1))
+        .unwrap();
+    c3d.parameters.set_group_locked("PROTECTED", true);
+    c3d
+}
+
+#[test]
+fn locked_entries_refuse_changes() {
+    let mut c3d = locked_c3d();
+    let parameters = &mut c3d.parameters;
+    assert!(parameters.get_mut("CUSTOM", "LOCKED").is_none());
+    assert!(matches!(
+        parameters.get_mut_or_err("CUSTOM", "LOCKED"),
+        Err(C3dEditError::LockedParameter(_, _))
+    ));
+    assert!(matches!(
+        parameters.insert("CUSTOM", "LOCKED", Parameter::integer(8)),
+        Err(C3dEditError::LockedParameter(_, _))
+    ));
+    assert!(matches!(
+        parameters.insert_group("PROTECTED", "Replaced".to_string()),
+        Err(C3dEditError::LockedGroup(_))
+    ));
+    assert_eq!(
+        parameters.get_group_description("PROTECTED").unwrap(),
+        "Vendor settings"
+    );
+    assert!(parameters.get("PROTECTED", "VALUE").is_some());
+    assert!(parameters.remove("CUSTOM", "LOCKED").is_none());
+    assert!(matches!(
+        parameters.remove_or_err("PROTECTED", "VALUE"),
+        Err(C3dEditError::LockedGroup(_))
+    ));
+    assert!(parameters.get_group_description_mut("PROTECTED").is_none());
+    assert_eq!(
+        parameters.get("CUSTOM", "LOCKED").unwrap().data,
+        ParameterData::Integer(vec![7])
+    );
+
+    assert!(parameters.get_mut("CUSTOM", "OPEN").is_some());
+    parameters.force_get_mut("CUSTOM", "LOCKED").unwrap().data = ParameterData::Integer(vec![8]);
+    assert_eq!(
+        parameters.get("CUSTOM", "LOCKED").unwrap().data,
+        ParameterData::Integer(vec![8])
+    );
+    assert!(parameters.force_remove("PROTECTED", "VALUE").is_some());
+    parameters.force_insert("PROTECTED", "VALUE", Parameter::integer(2));
+    assert_eq!(
+        parameters.get("PROTECTED", "VALUE").unwrap().data,
+        ParameterData::Integer(vec![2])
+    );
+    parameters.force_insert_group("PROTECTED", "Replaced".to_string());
+    assert_eq!(parameters.num_parameters("PROTECTED"), Some(0));
+
+    parameters
+        .insert_group("CUSTOM", "Custom settings".to_string())
+        .unwrap();
+    assert_eq!(
+        parameters.get_group_description("CUSTOM").unwrap(),
+        "Custom settings"
+    );
+    assert_eq!(parameters.num_parameters("CUSTOM"), Some(0));
+}
+
+#[test]
+fn locks_are_written() {
+    let c3d = locked_c3d();
+    let written = C3d::from_bytes(&c3d.to_bytes().unwrap()).unwrap();
+    assert!(written.parameters.get("CUSTOM", "LOCKED").unwrap().locked);
+    assert!(!written.parameters.get("CUSTOM", "OPEN").unwrap().locked);
+    assert!(written.parameters.is_group_locked("PROTECTED"));
+    assert!(!written.parameters.is_group_locked("CUSTOM"));
+    assert_eq!(written.parameters, c3d.parameters);
+}
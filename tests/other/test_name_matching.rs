@@ -0,0 +1,138 @@
+use c3dio::prelude::*;
+use std::io::Cursor;
+
+fn legacy_parameters() -> Parameters {
+    let mut parameters = Parameters::new();
+    parameters.set_name_matching(NameMatching::Legacy);
+    parameters
+        .insert("CUSTOM", "THRESH", Parameter::integer(5))
+        .unwrap();
+    parameters
+        .insert("CUSTOM", "COUNT", Parameter::integer(2))
+        .unwrap();
+    parameters
+}
+
+#[test]
+fn legacy_names_are_found() {
+    let parameters = legacy_parameters();
+    assert_eq!(
+        parameters.get("custom", "threshold_level").unwrap().data,
+        ParameterData::Integer(vec![5])
+    );
+    assert!(parameters.contains("Custom", "count"));
+    assert!(parameters.get_group("CUSTOMER").is_some());
+    assert!(parameters.get("CUSTOM", "COUNTER").is_none());
+    assert!(parameters.get("CUSTOM", "MISSING").is_none());
+
+    let mut exact = legacy_parameters();
+    exact.set_name_matching(NameMatching::Exact);
+    assert!(exact.get("custom", "threshold_level").is_none());
+    assert!(exact.get("CUSTOM", "THRESH").is_some());
+}
+
+#[test]
+fn legacy_insert_refuses_collisions() {
+    let mut parameters = legacy_parameters();
+    assert!(matches!(
+        parameters.insert("CUSTOM", "THRESHOLD_LEVEL", Parameter::integer(6)),
+        Err(C3dEditError::NameCollision(_, _))
+    ));
+    parameters
+        .insert("custom", "thresh", Parameter::integer(6))
+        .unwrap();
+    assert_eq!(
+        parameters.get("CUSTOM", "THRESH").unwrap().data,
+        ParameterData::Integer(vec![6])
+    );
+    assert_eq!(parameters.num_parameters("CUSTOM"), Some(2));
+
+    // continuation parameters are told apart by their full name
+    parameters
+        .insert("CUSTOM", "THRESH2", Parameter::integer(7))
+        .unwrap();
+    assert!(parameters.get("CUSTOM", "THRESH3").is_none());
+    assert_eq!(
+        parameters.get("CUSTOM", "THRESH").unwrap().data,
+        ParameterData::Integer(vec![6])
+    );
+}
+
+#[test]
+fn legacy_insert_group_replaces_the_matching_group() {
+    let mut parameters = legacy_parameters();
+    parameters
+        .insert_group("CUSTOMER", "Customer settings".to_string())
+        .unwrap();
+    assert_eq!(parameters.num_groups(), 1);
+    assert_eq!(parameters.num_parameters("CUSTOM"), Some(0));
+    assert_eq!(
+        parameters.get_group_description("CUSTOM").unwrap(),
+        "Customer settings"
+    );
+}
+
+#[test]
+fn colliding_names_are_not_written() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    c3d.parameters
+        .insert("CUSTOM", "THRESHOLD_LOW", Parameter::integer(1))
+        .unwrap();
+    c3d.parameters
+        .insert("CUSTOM", "THRESHOLD_HIGH", Parameter::integer(2))
+        .unwrap();
+    let bytes = c3d.to_bytes().unwrap();
+
+    c3d.parameters.set_name_matching(NameMatching::Legacy);
+    assert!(matches!(
+        c3d.to_bytes(),
+        Err(C3dWriteError::NameCollision(_, _))
+    ));
+
+    let options = LoadOptions::new().name_matching(NameMatching::Legacy);
+    let legacy = C3d::from_reader_with(Cursor::new(bytes), &options).unwrap();
+    assert_eq!(legacy.parameters.name_matching(), NameMatching::Legacy);
+    assert!(legacy.parameters.get("CUSTOM", "THRESHOLD_LOW").is_some());
+    assert!(legacy.parameters.get("CUSTOM", "THRESH").is_none());
+    assert_eq!(legacy.points.labels, c3d.points.labels);
+}
+
+#[test]
+fn names_differing_in_case_are_not_written() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    c3d.parameters
+        .insert("CUSTOM", "VALUE", Parameter::integer(1))
+        .unwrap();
+    c3d.parameters
+        .insert("CUSTOM", "value", Parameter::integer(2))
+        .unwrap();
+    assert!(matches!(
+        c3d.to_bytes(),
+        Err(C3dWriteError::NameCollision(_, _))
+    ));
+}
+
+#[test]
+fn legacy_long_recordings_are_written() {
+    let options = LoadOptions::new().name_matching(NameMatching::Legacy);
+    let mut c3d = C3d::load_with("tests/data/short.c3d", &options).unwrap();
+    let num_frames = c3d.points.rows() as u32;
+    c3d.points.first_frame = 70000;
+    c3d.points.last_frame = 70000 + num_frames - 1;
+    // ACTUAL_START_FIELD and ACTUAL_END_FIELD share their first six characters
+    let bytes = c3d.to_bytes().unwrap();
+
+    let written = C3d::from_reader_with(Cursor::new(bytes), &options).unwrap();
+    assert_eq!(written.points.first_frame, 70000);
+    assert_eq!(written.points.last_frame, 70000 + num_frames - 1);
+    assert!(written.to_bytes().is_ok());
+
+    let mut added = written.clone();
+    added
+        .parameters
+        .force_insert("TRIAL", "ACTUAL_RATE", Parameter::integer(1));
+    assert!(matches!(
+        added.to_bytes(),
+        Err(C3dWriteError::NameCollision(_, _))
+    ));
+}
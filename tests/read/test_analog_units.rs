@@ -0,0 +1,41 @@
+use c3dio::prelude::*;
+
+#[test]
+fn unsigned_raw_counts_and_volts() {
+    let c3d = C3d::load("tests/c3d_org_samples/sample_17/128analogchannels.c3d").unwrap();
+    let analog = &c3d.analog;
+    assert!(matches!(analog.offset, AnalogOffset::Unsigned(_)));
+    assert_eq!(analog.gains.len(), analog.cols());
+    assert_eq!(analog.voltage_range(0), Some(10.0));
+    assert_eq!(analog.unit(0), Some("N"));
+    let raw = analog.raw_counts(0).unwrap();
+    assert_eq!(&raw[..3], &[32736.0, 32734.0, 32745.0]);
+    assert!(raw
+        .iter()
+        .all(|count| *count >= 0.0 && *count <= u16::MAX as f64));
+    let volts = analog.volts(0).unwrap();
+    assert_eq!(volts[0], 10.0 / 32768.0);
+    let calibrated = analog.calibrated(0).unwrap();
+    for (raw, value) in raw.iter().zip(calibrated.iter()) {
+        assert!((analog.calibrated_value(*raw, 0) - value).abs() < 1e-9);
+    }
+    assert!(analog.raw_counts(analog.cols()).is_none());
+}
+
+#[test]
+fn volts_need_gain() {
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    assert!(c3d.analog.gains.is_empty());
+    assert!(c3d.analog.volts(0).is_none());
+    assert!(c3d.analog.raw_counts(0).is_some());
+}
+
+#[test]
+fn unsigned_write_is_not_rescaled() {
+    let c3d = C3d::load("tests/c3d_org_samples/sample_17/128analogchannels.c3d").unwrap();
+    let bytes = c3d.to_bytes().unwrap();
+    let reread = C3d::from_bytes(&bytes).unwrap();
+    assert_eq!(reread.analog.gains, c3d.analog.gains);
+    assert_eq!(reread.analog.raw_counts(5), c3d.analog.raw_counts(5));
+    assert_eq!(reread.analog.analog.flatten(), c3d.analog.analog.flatten());
+}
@@ -0,0 +1,56 @@
+use c3dio::prelude::*;
+use std::fs::File;
+use std::io::Cursor;
+use std::io::Read;
+
+#[test]
+fn frames_match_full_load() {
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let reader = C3dReader::open("tests/data/short.c3d").unwrap();
+    assert_eq!(reader.num_frames(), c3d.points.rows());
+    assert_eq!(reader.num_markers(), c3d.points.cols());
+    assert_eq!(reader.num_analog_channels(), c3d.analog.cols());
+    assert_eq!(reader.c3d().points.labels, c3d.points.labels);
+
+    let samples = c3d.analog.samples_per_channel_per_frame as usize;
+    let mut count = 0;
+    for frame in reader {
+        let frame = frame.unwrap();
+        assert_eq!(
+            frame.points,
+            c3d.points
+                .iter_row(frame.index)
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+        for sample in 0..frame.analog.rows() {
+            let row = frame.index * samples + sample;
+            assert_eq!(
+                frame.analog.iter_row(sample).collect::<Vec<_>>(),
+                c3d.analog.iter_row(row).collect::<Vec<_>>()
+            );
+        }
+        count += 1;
+    }
+    assert_eq!(count, c3d.points.rows());
+}
+
+#[test]
+fn seek_frame_from_cursor() {
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let mut bytes = Vec::new();
+    File::open("tests/data/short.c3d")
+        .unwrap()
+        .read_to_end(&mut bytes)
+        .unwrap();
+    let mut reader = C3dReader::new(Cursor::new(bytes)).unwrap();
+    let last = reader.num_frames() - 1;
+    reader.seek_frame(last).unwrap();
+    let frame = reader.read_frame().unwrap().unwrap();
+    assert_eq!(frame.index, last);
+    assert_eq!(
+        frame.points,
+        c3d.points.iter_row(last).cloned().collect::<Vec<_>>()
+    );
+    assert!(reader.read_frame().unwrap().is_none());
+}
@@ -0,0 +1,58 @@
+use c3dio::prelude::*;
+
+#[test]
+fn consistent_file_has_no_warnings() {
+    let (c3d, warnings) =
+        C3d::load_with_diagnostics("tests/data/short.c3d", &LoadOptions::new()).unwrap();
+    assert!(warnings.is_empty());
+    let loaded = C3d::load("tests/data/short.c3d").unwrap();
+    assert_eq!(c3d.points, loaded.points);
+    assert_eq!(c3d.analog, loaded.analog);
+}
+
+#[test]
+fn event_count_mismatch() {
+    let (c3d, warnings) = C3d::load_with_diagnostics(
+        "tests/c3d_org_samples/sample_11/2198928.c3d",
+        &LoadOptions::new(),
+    )
+    .unwrap();
+    let warning = warnings
+        .iter()
+        .find(|warning| warning.parameter.as_deref() == Some("USED"))
+        .unwrap();
+    assert_eq!(warning.kind, C3dWarningKind::HeaderMismatch);
+    assert_eq!(warning.group.as_deref(), Some("EVENT"));
+    assert_eq!(warning.offset, Some(17952));
+    assert_eq!(c3d.events.len(), 8);
+}
+
+#[test]
+fn lenient_fallbacks_are_reported() {
+    let options = LoadOptions::new().lenient(true);
+    let (_, warnings) =
+        C3d::load_with_diagnostics("tests/c3d_org_samples/sample_28/type1.C3D", &options).unwrap();
+    assert!(warnings.iter().any(|warning| {
+        warning.kind == C3dWarningKind::MissingParameter
+            && warning.group.as_deref() == Some("ANALOG")
+            && warning.parameter.as_deref() == Some("RATE")
+    }));
+
+    let (_, warnings) = C3d::load_with_diagnostics(
+        "tests/c3d_org_samples/sample_24/MotionMonitorC3D.c3d",
+        &options,
+    )
+    .unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, C3dWarningKind::HeaderMismatch);
+    assert_eq!(warnings[0].offset, Some(4));
+
+    let (_, warnings) =
+        C3d::load_with_diagnostics("tests/c3d_org_samples/sample_11/evart.c3d", &options).unwrap();
+    let warning = warnings
+        .iter()
+        .find(|warning| warning.parameter.as_deref() == Some("CHANNEL"))
+        .unwrap();
+    assert_eq!(warning.kind, C3dWarningKind::InvalidParameter);
+    assert_eq!(warning.offset, Some(4223));
+}
@@ -0,0 +1,74 @@
+use c3dio::prelude::*;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::PathBuf;
+
+fn short_bytes() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    File::open("tests/data/short.c3d")
+        .unwrap()
+        .read_to_end(&mut bytes)
+        .unwrap();
+    bytes
+}
+
+#[test]
+fn cursor_matches_load() {
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let from_reader = C3d::from_reader(Cursor::new(short_bytes())).unwrap();
+    assert_eq!(c3d, from_reader);
+}
+
+#[test]
+fn reads_from_current_position() {
+    let mut bytes = vec![0xFFu8; 100];
+    bytes.extend(short_bytes());
+    let mut cursor = Cursor::new(bytes);
+    cursor.seek(SeekFrom::Start(100)).unwrap();
+    let from_reader = C3d::from_reader(cursor).unwrap();
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    assert_eq!(c3d, from_reader);
+}
+
+#[test]
+fn reads_from_current_position_with_options() {
+    let mut bytes = vec![0xFFu8; 100];
+    bytes.extend(short_bytes());
+    let mut cursor = Cursor::new(bytes);
+    cursor.seek(SeekFrom::Start(100)).unwrap();
+    let options = LoadOptions::new().frames(10..20);
+    let from_reader = C3d::from_reader_with(cursor, &options).unwrap();
+    let c3d = C3d::load_with("tests/data/short.c3d", &options).unwrap();
+    assert_eq!(c3d, from_reader);
+}
+
+#[test]
+fn streams_from_current_position() {
+    let mut bytes = vec![0xFFu8; 100];
+    bytes.extend(short_bytes());
+    let mut cursor = Cursor::new(bytes);
+    cursor.seek(SeekFrom::Start(100)).unwrap();
+    let reader = C3dReader::new(cursor).unwrap();
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    assert_eq!(reader.num_frames(), c3d.points.rows());
+    for (index, frame) in reader.enumerate() {
+        assert_eq!(
+            frame.unwrap().points,
+            c3d.points
+                .points
+                .iter_row(index)
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+    }
+}
+
+#[test]
+fn header_and_parameters_from_reader() {
+    let header = C3d::header_from_reader(Cursor::new(short_bytes())).unwrap();
+    let parameters = C3d::parameters_from_reader(Cursor::new(short_bytes())).unwrap();
+    let expected_header = C3d::load_header(PathBuf::from("tests/data/short.c3d")).unwrap();
+    let expected_parameters = C3d::load_parameters(PathBuf::from("tests/data/short.c3d")).unwrap();
+    assert_eq!(header, expected_header);
+    assert_eq!(parameters, expected_parameters);
+}
@@ -0,0 +1,16 @@
+use c3dio::prelude::*;
+
+#[test]
+fn mmap_matches_load() {
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    // SAFETY: the test files are not modified while the tests run
+    let mapped = unsafe { C3d::load_mmap("tests/data/short.c3d") }.unwrap();
+    assert_eq!(c3d.points, mapped.points);
+    assert_eq!(c3d.analog, mapped.analog);
+    assert_eq!(c3d.events, mapped.events);
+}
+
+#[test]
+fn mmap_missing_file() {
+    assert!(unsafe { C3d::load_mmap("tests/data/missing.c3d") }.is_err());
+}
@@ -0,0 +1,91 @@
+use c3dio::prelude::*;
+
+#[test]
+fn default_options_match_load() {
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let loaded = C3d::load_with("tests/data/short.c3d", &LoadOptions::new()).unwrap();
+    assert_eq!(c3d.points, loaded.points);
+    assert_eq!(c3d.analog, loaded.analog);
+    assert_eq!(c3d.events, loaded.events);
+    assert_eq!(c3d.forces, loaded.forces);
+}
+
+#[test]
+fn frame_window() {
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let options = LoadOptions::new().frames(100..120);
+    let loaded = C3d::load_with("tests/data/short.c3d", &options).unwrap();
+    assert_eq!(loaded.points.size(), (20, c3d.points.cols()));
+    assert_eq!(loaded.points.first_frame, c3d.points.first_frame + 100);
+    assert_eq!(loaded.points.last_frame, c3d.points.first_frame + 119);
+    for frame in 0..20 {
+        assert_eq!(
+            loaded.points.iter_row(frame).collect::<Vec<_>>(),
+            c3d.points.iter_row(frame + 100).collect::<Vec<_>>()
+        );
+    }
+    let samples = c3d.analog.samples_per_channel_per_frame as usize;
+    assert_eq!(loaded.analog.rows(), 20 * samples);
+    for row in 0..loaded.analog.rows() {
+        assert_eq!(
+            loaded.analog.iter_row(row).collect::<Vec<_>>(),
+            c3d.analog.iter_row(row + 100 * samples).collect::<Vec<_>>()
+        );
+    }
+    assert_eq!(loaded.forces.zero, [0, 0]);
+}
+
+#[test]
+fn frame_window_is_clamped() {
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let options = LoadOptions::new().frames(140..1000);
+    let loaded = C3d::load_with("tests/data/short.c3d", &options).unwrap();
+    assert_eq!(loaded.points.rows(), c3d.points.rows() - 140);
+    assert_eq!(loaded.points.last_frame, c3d.points.last_frame);
+}
+
+#[test]
+fn point_subset() {
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let options = LoadOptions::new().point_labels(&["RASI", "LASI"]);
+    let loaded = C3d::load_with("tests/data/short.c3d", &options).unwrap();
+    let rasi = c3d.points.labels.iter().position(|l| l == "RASI").unwrap();
+    let lasi = c3d.points.labels.iter().position(|l| l == "LASI").unwrap();
+    assert_eq!(loaded.points.labels, vec!["RASI", "LASI"]);
+    assert_eq!(loaded.points.size(), (c3d.points.rows(), 2));
+    assert_eq!(
+        loaded.points.iter_col(0).collect::<Vec<_>>(),
+        c3d.points.iter_col(rasi).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        loaded.points.iter_col(1).collect::<Vec<_>>(),
+        c3d.points.iter_col(lasi).collect::<Vec<_>>()
+    );
+    assert_eq!(c3d.analog, loaded.analog);
+}
+
+#[test]
+fn analog_subset() {
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let options = LoadOptions::new().analog_labels(&["F2X", "F2Y", "F2Z"]);
+    let loaded = C3d::load_with("tests/data/short.c3d", &options).unwrap();
+    assert_eq!(loaded.analog.labels, vec!["F2X", "F2Y", "F2Z"]);
+    assert_eq!(loaded.analog.scales.len(), 3);
+    assert_eq!(loaded.analog.size(), (c3d.analog.rows(), 3));
+    for (column, original) in (6..9).enumerate() {
+        assert_eq!(
+            loaded.analog.iter_col(column).collect::<Vec<_>>(),
+            c3d.analog.iter_col(original).collect::<Vec<_>>()
+        );
+    }
+    assert_eq!(loaded.forces[0].channels, [0; 6]);
+    assert_eq!(loaded.forces[1].channels, [1, 2, 3, 0, 0, 0]);
+    assert_eq!(c3d.points, loaded.points);
+}
+
+#[test]
+fn missing_label() {
+    let options = LoadOptions::new().point_labels(&["NOT_A_MARKER"]);
+    let result = C3d::load_with("tests/data/short.c3d", &options);
+    assert!(matches!(result, Err(C3dParseError::MissingLabel(_))));
+}
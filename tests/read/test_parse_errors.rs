@@ -0,0 +1,66 @@
+use c3dio::prelude::*;
+use std::error::Error;
+
+fn short_bytes() -> Vec<u8> {
+    std::fs::read("tests/data/short.c3d").unwrap()
+}
+
+fn find(bytes: &[u8], name: &[u8]) -> usize {
+    bytes
+        .windows(name.len())
+        .position(|window| window == name)
+        .unwrap()
+}
+
+#[test]
+fn invalid_processor_type() {
+    let mut bytes = short_bytes();
+    bytes[515] = 0x20;
+    let error = C3d::from_bytes(&bytes).unwrap_err();
+    assert!(matches!(error, C3dParseError::InvalidProcessorType(_)));
+    let location = error.location().unwrap();
+    assert_eq!(location.offset, 515);
+    assert_eq!(location.block, 2);
+    assert_eq!(
+        error.to_string(),
+        "unknown processor type at byte 515 (block 2)"
+    );
+}
+
+#[test]
+fn invalid_parameter_record() {
+    let mut bytes = short_bytes();
+    // the name of a parameter record starts two bytes after the record
+    let record = find(&bytes, b"LABELS") - 2;
+    bytes[record + 10] = 0x07;
+    let error = C3d::from_bytes(&bytes).unwrap_err();
+    assert!(matches!(error, C3dParseError::InvalidDataType(_)));
+    let location = error.location().unwrap();
+    assert_eq!(location.offset, record);
+    assert_eq!(location.block, record / 512 + 1);
+    assert_eq!(location.group.as_deref(), Some("POINT"));
+    assert_eq!(location.parameter.as_deref(), Some("LABELS"));
+    assert!(error.to_string().ends_with("in POINT:LABELS"));
+}
+
+#[test]
+fn invalid_parameter_value() {
+    let mut bytes = short_bytes();
+    let record = find(&bytes, b"GEN_SCALE") - 2;
+    // store ANALOG:GEN_SCALE as a single character instead of a float
+    bytes[record + 13] = 0xff;
+    let error = C3d::from_bytes(&bytes).unwrap_err();
+    assert!(matches!(error, C3dParseError::InvalidData(..)));
+    let location = error.location().unwrap();
+    assert_eq!(location.offset, record);
+    assert_eq!(location.group.as_deref(), Some("ANALOG"));
+    assert_eq!(location.parameter.as_deref(), Some("GEN_SCALE"));
+}
+
+#[test]
+fn read_error_source() {
+    let error = C3d::load("tests/data/missing.c3d").unwrap_err();
+    assert!(error.location().is_none());
+    let source = error.source().unwrap();
+    assert!(source.downcast_ref::<std::io::Error>().is_some());
+}
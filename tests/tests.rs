@@ -1,9 +1,33 @@
 mod write {
+    mod test_c3d_writer;
+    mod test_continuation_parameters;
+    mod test_integer_format;
+    mod test_long_recordings;
+    mod test_many_events;
     mod test_write_c3d;
+    mod test_write_processor;
+    mod test_write_to;
+}
+
+mod read {
+    mod test_analog_units;
+    mod test_c3d_reader;
+    mod test_diagnostics;
+    mod test_from_reader;
+    #[cfg(feature = "mmap")]
+    mod test_load_mmap;
+    mod test_load_options;
+    mod test_parse_errors;
 }
 
 mod other {
     mod test_byte_and_file_parity;
+    mod test_event_editing;
+    mod test_force_wrench;
+    mod test_force_zeroing;
+    mod test_gait_events;
+    mod test_locked_parameters;
+    mod test_name_matching;
 }
 
 mod c3d_org_samples {
@@ -0,0 +1,87 @@
+use c3dio::prelude::*;
+use grid::Grid;
+use std::io::Cursor;
+
+fn stream_copy(file_name: &str) -> (C3d, Vec<u8>) {
+    let original = C3d::load(file_name).unwrap();
+    let reader = C3dReader::open(file_name).unwrap();
+    let mut writer = C3dWriter::new(Cursor::new(Vec::new()), reader.c3d().clone()).unwrap();
+    for frame in reader {
+        let frame = frame.unwrap();
+        writer.write_frame(&frame.points, &frame.analog).unwrap();
+    }
+    assert_eq!(writer.num_frames(), original.points.rows());
+    (original, writer.finish().unwrap().into_inner())
+}
+
+#[test]
+fn streamed_frames_match_full_write() {
+    let (original, bytes) = stream_copy("tests/data/short.c3d");
+    let streamed = C3d::from_bytes(&bytes).unwrap();
+    assert_eq!(original.points, streamed.points);
+    assert_eq!(original.analog, streamed.analog);
+    assert_eq!(original.points.first_frame, streamed.points.first_frame);
+    assert_eq!(original.points.last_frame, streamed.points.last_frame);
+}
+
+#[test]
+fn frame_count_patched_on_finish() {
+    let original = C3d::load("tests/data/short.c3d").unwrap();
+    let reader = C3dReader::open("tests/data/short.c3d").unwrap();
+    let mut writer = C3dWriter::new(Cursor::new(Vec::new()), reader.c3d().clone()).unwrap();
+    for frame in reader.take(3) {
+        let frame = frame.unwrap();
+        writer.write_frame(&frame.points, &frame.analog).unwrap();
+    }
+    let bytes = writer.finish().unwrap().into_inner();
+    let streamed = C3d::from_bytes(&bytes).unwrap();
+    assert_eq!(streamed.points.rows(), 3);
+    assert_eq!(streamed.points.last_frame, original.points.first_frame + 2);
+    assert_eq!(
+        streamed.analog.rows(),
+        3 * original.analog.samples_per_channel_per_frame as usize
+    );
+}
+
+#[test]
+fn empty_recording_has_an_empty_header_range() {
+    let reader = C3dReader::open("tests/data/short.c3d").unwrap();
+    let first_frame = reader.c3d().points.first_frame;
+    let writer = C3dWriter::new(Cursor::new(Vec::new()), reader.c3d().clone()).unwrap();
+    let bytes = writer.finish().unwrap().into_inner();
+    assert_eq!(u16::from_le_bytes([bytes[6], bytes[7]]) as u32, first_frame);
+    assert_eq!(
+        u16::from_le_bytes([bytes[8], bytes[9]]) as u32,
+        first_frame - 1
+    );
+    let empty = C3d::from_reader(Cursor::new(bytes)).unwrap();
+    assert_eq!(empty.points.rows(), 0);
+    assert_eq!(empty.analog.rows(), 0);
+    assert_eq!(empty.points.last_frame, first_frame - 1);
+}
+
+#[test]
+fn wrong_frame_size_is_rejected() {
+    let reader = C3dReader::open("tests/data/short.c3d").unwrap();
+    let mut writer = C3dWriter::new(Cursor::new(Vec::new()), reader.c3d().clone()).unwrap();
+    let result = writer.write_frame(&[MarkerPoint::default()], &Grid::new(0, 0));
+    assert!(matches!(result, Err(C3dWriteError::InvalidFrameSize(_))));
+    assert_eq!(writer.num_frames(), 0);
+}
+
+#[test]
+fn create_file() {
+    let file_name = std::env::temp_dir().join("c3dio-test-c3d-writer.c3d");
+    let original = C3d::load("tests/data/short.c3d").unwrap();
+    let reader = C3dReader::open("tests/data/short.c3d").unwrap();
+    let mut writer = C3dWriter::create_path(file_name.clone(), reader.c3d().clone()).unwrap();
+    for frame in reader {
+        let frame = frame.unwrap();
+        writer.write_frame(&frame.points, &frame.analog).unwrap();
+    }
+    writer.finish().unwrap();
+    let streamed = C3d::load_path(file_name.clone()).unwrap();
+    std::fs::remove_file(file_name).unwrap();
+    assert_eq!(original.points, streamed.points);
+    assert!(C3dWriter::create("tests/data/short.txt", C3d::new()).is_err());
+}
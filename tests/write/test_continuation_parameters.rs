@@ -0,0 +1,53 @@
+use c3dio::prelude::*;
+use grid::Grid;
+
+const CHANNELS: usize = 300;
+
+fn many_channels() -> C3d {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let frames = c3d.points.rows();
+    c3d.points.points = Grid::new(frames, CHANNELS);
+    c3d.points.labels = (0..CHANNELS).map(|i| format!("M{}", i)).collect();
+    c3d.points.descriptions = (0..CHANNELS).map(|i| format!("Marker {}", i)).collect();
+
+    let samples = c3d.analog.rows();
+    c3d.analog.analog = Grid::new(samples, CHANNELS);
+    c3d.analog.samples_per_frame = CHANNELS as u16 * c3d.analog.samples_per_channel_per_frame;
+    c3d.analog.labels = (0..CHANNELS).map(|i| format!("EMG{}", i)).collect();
+    c3d.analog.descriptions = (0..CHANNELS).map(|i| format!("Channel {}", i)).collect();
+    c3d.analog.units = vec!["V".to_string(); CHANNELS];
+    c3d.analog.scales = (0..CHANNELS).map(|i| i as f32 + 1.0).collect();
+    c3d.analog.offset = AnalogOffset::Signed((0..CHANNELS).map(|i| i as i16).collect());
+    c3d
+}
+
+#[test]
+fn more_than_255_channels_round_trip() {
+    let c3d = many_channels();
+    let written = C3d::from_bytes(&c3d.to_bytes().unwrap()).unwrap();
+    assert_eq!(written.points.labels, c3d.points.labels);
+    assert_eq!(written.points.descriptions, c3d.points.descriptions);
+    assert_eq!(written.points.cols(), CHANNELS);
+    assert_eq!(written.analog.labels, c3d.analog.labels);
+    assert_eq!(written.analog.descriptions, c3d.analog.descriptions);
+    assert_eq!(written.analog.units, c3d.analog.units);
+    assert_eq!(written.analog.scales, c3d.analog.scales);
+    assert_eq!(written.analog.offset, c3d.analog.offset);
+    assert_eq!(written.analog.cols(), CHANNELS);
+    assert!(!written.parameters.contains("POINT", "LABELS2"));
+}
+
+#[test]
+fn continuation_parameters_are_written() {
+    let c3d = many_channels();
+    let bytes = c3d.to_bytes().unwrap();
+    let has_name = |name: &str| {
+        bytes
+            .windows(name.len())
+            .any(|window| window == name.as_bytes())
+    };
+    for name in ["LABELS2", "DESCRIPTIONS2", "UNITS2", "SCALE2", "OFFSET2"] {
+        assert!(has_name(name), "{}", name);
+    }
+    assert!(!has_name("LABELS3"));
+}
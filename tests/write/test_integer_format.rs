@@ -0,0 +1,83 @@
+use c3dio::prelude::*;
+use c3dio::DataFormat;
+
+#[test]
+fn convert_to_integer_round_trip() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let original = c3d.clone();
+    let loss = c3d.convert_to_integer();
+    assert_eq!(c3d.points.format, DataFormat::Integer);
+    assert!(loss.points <= c3d.points.scale_factor / 2.0 * 1.01);
+    assert_eq!(loss.analog.len(), c3d.analog.cols());
+    for (channel, loss) in loss.analog.iter().enumerate() {
+        let step = c3d.analog.scales[channel] as f64 * c3d.analog.gen_scale as f64;
+        assert!(*loss <= step / 2.0 * 1.01);
+    }
+    let max_coordinate = original
+        .points
+        .iter()
+        .flat_map(|point| point.point)
+        .fold(0.0f32, |max, coordinate| max.max(coordinate.abs()));
+    assert_eq!(c3d.points.scale_factor, max_coordinate / 32000.0);
+
+    let written = C3d::from_bytes(&c3d.to_bytes().unwrap()).unwrap();
+    assert_eq!(written.points.format, DataFormat::Integer);
+    assert_eq!(written.points.scale_factor, c3d.points.scale_factor);
+    assert_eq!(written.points, c3d.points);
+    assert_eq!(written.analog.scales, c3d.analog.scales);
+    for (written, converted) in written.analog.iter().zip(c3d.analog.iter()) {
+        assert!((written - converted).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn convert_to_float_round_trip() {
+    let mut c3d = C3d::load("tests/c3d_org_samples/sample_01/Eb015pi.c3d").unwrap();
+    assert_eq!(c3d.points.format, DataFormat::Integer);
+    let original = c3d.clone();
+    c3d.convert_to_float();
+    assert_eq!(c3d.points.scale_factor, original.points.scale_factor);
+    assert!(c3d.analog.iter().eq(original.analog.iter()));
+    assert!(c3d.analog.scales.iter().all(|scale| *scale == 1.0));
+    assert_eq!(c3d.analog.gen_scale, 1.0);
+
+    let bytes = c3d.to_bytes().unwrap();
+    let header_scale = f32::from_le_bytes(bytes[12..16].try_into().unwrap());
+    assert_eq!(header_scale, -original.points.scale_factor);
+    let written = C3d::from_bytes(&bytes).unwrap();
+    assert_eq!(written.points.format, DataFormat::Float);
+    assert_eq!(written.points, c3d.points);
+    assert_eq!(written.analog.scales, c3d.analog.scales);
+    for (written, converted) in written.analog.iter().zip(c3d.analog.iter()) {
+        assert!((written - converted).abs() <= converted.abs() * 1e-6);
+    }
+}
+
+#[test]
+fn convert_to_float_undoes_convert_to_integer() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    c3d.convert_to_integer();
+    let integer = c3d.clone();
+    c3d.convert_to_float();
+    c3d.convert_to_integer();
+    assert_eq!(c3d.points, integer.points);
+    for (channel, scale) in c3d.analog.scales.iter().enumerate() {
+        let step = *scale as f64 * c3d.analog.gen_scale as f64;
+        let integer_step = integer.analog.scales[channel] as f64 * integer.analog.gen_scale as f64;
+        assert!((step - integer_step).abs() <= integer_step * 1e-6);
+    }
+    for (converted, integer) in c3d.analog.iter().zip(integer.analog.iter()) {
+        assert!((converted - integer).abs() <= integer.abs() * 1e-6);
+    }
+}
+
+#[test]
+fn integer_overflow_is_an_error() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    c3d.convert_to_integer();
+    c3d.points.scale_factor /= 2.0;
+    assert!(matches!(
+        c3d.to_bytes(),
+        Err(C3dWriteError::IntegerOverflow(_))
+    ));
+}
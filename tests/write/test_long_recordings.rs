@@ -0,0 +1,109 @@
+use c3dio::prelude::*;
+use grid::Grid;
+
+fn with_frames(first_frame: u32, num_frames: usize) -> C3d {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let markers = c3d.points.cols();
+    c3d.points.points = Grid::new(num_frames, markers);
+    let channels = c3d.analog.cols();
+    let samples = num_frames * c3d.analog.samples_per_channel_per_frame as usize;
+    c3d.analog.analog = Grid::new(samples, channels);
+    c3d.points.first_frame = first_frame;
+    c3d.points.last_frame = first_frame + num_frames as u32 - 1;
+    c3d
+}
+
+fn contains(bytes: &[u8], name: &str) -> bool {
+    bytes
+        .windows(name.len())
+        .any(|window| window == name.as_bytes())
+}
+
+#[test]
+fn more_than_65535_frames_round_trip() {
+    let c3d = with_frames(10, 70000);
+    let bytes = c3d.to_bytes().unwrap();
+    assert!(contains(&bytes, "LONG_FRAMES"));
+    assert!(contains(&bytes, "ACTUAL_END_FIELD"));
+    assert_eq!(u16::from_le_bytes([bytes[8], bytes[9]]), u16::MAX);
+
+    let written = C3d::from_bytes(&bytes).unwrap();
+    assert_eq!(written.points.rows(), 70000);
+    assert_eq!(written.points.first_frame, 10);
+    assert_eq!(written.points.last_frame, 70009);
+    assert_eq!(written.analog.rows(), c3d.analog.rows());
+}
+
+#[test]
+fn frame_numbers_above_65535_round_trip() {
+    let c3d = with_frames(100_000, 5);
+    let bytes = c3d.to_bytes().unwrap();
+    assert!(!contains(&bytes, "LONG_FRAMES"));
+
+    let written = C3d::from_bytes(&bytes).unwrap();
+    assert_eq!(written.points.rows(), 5);
+    assert_eq!(written.points.first_frame, 100_000);
+    assert_eq!(written.points.last_frame, 100_004);
+}
+
+#[test]
+fn short_recordings_have_no_long_frames() {
+    let c3d = with_frames(1, 20);
+    let bytes = c3d.to_bytes().unwrap();
+    assert!(!contains(&bytes, "LONG_FRAMES"));
+    assert!(!contains(&bytes, "ACTUAL_START_FIELD"));
+}
+
+#[test]
+fn header_frame_range_spans_the_frame_count() {
+    for (first_frame, num_frames) in [(100_000, 5), (60_000, 10_000), (65_000, 100)] {
+        let c3d = with_frames(first_frame, num_frames);
+        let bytes = c3d.to_bytes().unwrap();
+        let header_first = u16::from_le_bytes([bytes[6], bytes[7]]) as usize;
+        let header_last = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!(header_last - header_first + 1, num_frames);
+
+        let written = C3d::from_bytes(&bytes).unwrap();
+        assert_eq!(written.points.rows(), num_frames);
+        assert_eq!(written.points.first_frame, first_frame);
+        assert_eq!(
+            written.points.last_frame,
+            first_frame + num_frames as u32 - 1
+        );
+    }
+}
+
+/// The index of the name of the parameter record `name`.
+fn record_name(bytes: &[u8], name: &str) -> usize {
+    (2..bytes.len() - name.len())
+        .find(|&index| {
+            bytes[index - 2] as usize == name.len()
+                && (bytes[index - 1] as i8) > 0
+                && &bytes[index..index + name.len()] == name.as_bytes()
+        })
+        .unwrap()
+}
+
+/// The index of the first data byte of the parameter record `name`.
+fn record_data(bytes: &[u8], name: &str) -> usize {
+    let index = record_name(bytes, name) + name.len();
+    index + 4 + bytes[index + 3] as usize
+}
+
+#[test]
+fn unknown_end_field_counts_the_data_section() {
+    let c3d = with_frames(1, 70000);
+    let mut bytes = c3d.to_bytes().unwrap();
+    // a writer without LONG_FRAMES whose 16-bit frame counts stop at 65535
+    let long_frames = record_name(&bytes, "LONG_FRAMES");
+    bytes[long_frames..long_frames + 11].copy_from_slice(b"LONG_FRAMEX");
+    let frames = record_data(&bytes, "FRAMES");
+    bytes[frames..frames + 4].copy_from_slice(&65535.0f32.to_le_bytes());
+    let end_field = record_data(&bytes, "ACTUAL_END_FIELD");
+    bytes[end_field..end_field + 4].copy_from_slice(&[0xFF, 0xFF, 0, 0]);
+
+    let written = C3d::from_bytes(&bytes).unwrap();
+    assert_eq!(written.points.rows(), 70000);
+    assert_eq!(written.points.first_frame, 1);
+    assert_eq!(written.points.last_frame, 70000);
+}
@@ -0,0 +1,101 @@
+use c3dio::prelude::*;
+use c3dio::Event;
+
+fn numbered_events(count: usize) -> Vec<Event> {
+    (0..count)
+        .map(|index| Event {
+            id: ['E', 'V', ' ', ' '],
+            label: format!("Event {}", index),
+            display_flag: true,
+            time: 0.5 + index as f32 * 0.25,
+            context: "General".to_string(),
+            ..Event::default()
+        })
+        .collect()
+}
+
+#[test]
+fn more_than_eighteen_events_round_trip() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    c3d.events.clear();
+    c3d.events.supports_events_labels = true;
+    c3d.events.extend(numbered_events(300));
+    let bytes = c3d.to_bytes().unwrap();
+    // the header only holds the first 18 events
+    assert_eq!(i16::from_le_bytes([bytes[300], bytes[301]]), 18);
+    let reread = C3d::from_bytes(&bytes).unwrap();
+    assert_eq!(reread.events.len(), 300);
+    for (written, read) in c3d.events.iter().zip(reread.events.iter()) {
+        assert_eq!(written.label, read.label);
+        assert_eq!(written.context, read.context);
+        assert!((written.time - read.time).abs() < 1e-4);
+    }
+    assert_eq!(reread.events[17].id, ['E', 'V', ' ', ' ']);
+    assert_eq!(reread.events[18].id, ['\0'; 4]);
+    assert!(reread.events[299].display_flag);
+}
+
+#[test]
+fn events_only_in_parameters() {
+    let c3d = C3d::load("tests/c3d_org_samples/sample_09/PlugInC3D.c3d").unwrap();
+    assert_eq!(c3d.events.len(), 8);
+    assert_eq!(c3d.events[0].label, "Foot Strike");
+    assert!((c3d.events[0].time - 1.1166667).abs() < 1e-6);
+    assert!(c3d.events.iter().all(|event| event.time > 0.0));
+}
+
+#[test]
+fn events_only_in_header() {
+    let c3d = C3d::load("tests/c3d_org_samples/sample_01/Eb015pr.c3d").unwrap();
+    let times = c3d
+        .events
+        .iter()
+        .map(|event| event.time)
+        .collect::<Vec<_>>();
+    assert_eq!(times, vec![2.72, 5.4, 7.32]);
+    assert_eq!(c3d.events[1].id, ['R', 'H', 'S', ' ']);
+    let reread = C3d::from_bytes(&c3d.to_bytes().unwrap()).unwrap();
+    assert_eq!(reread.events, c3d.events);
+}
+
+/// Finds the first parameter record named `name` in the parameter section
+/// of `bytes` and returns its dimensions and float data.
+fn float_record(bytes: &[u8], name: &str) -> (Vec<u8>, Vec<f32>) {
+    let start = 512 * (bytes[0] as usize - 1);
+    assert_eq!(bytes[start + 3], 84, "expected an Intel file");
+    let section = &bytes[start..start + 512 * bytes[start + 2] as usize];
+    let index = (0..section.len() - name.len())
+        .find(|&index| {
+            section[index] == name.len() as u8
+                && (section[index + 1] as i8) > 0
+                && &section[index + 2..index + 2 + name.len()] == name.as_bytes()
+        })
+        .unwrap();
+    // a float record with its dimensions after the pointer
+    assert_eq!(section[index + 4 + name.len()], 4);
+    let num_dimensions = section[index + 5 + name.len()] as usize;
+    let data_start = index + 6 + name.len() + num_dimensions;
+    let dimensions = section[data_start - num_dimensions..data_start].to_vec();
+    let count = dimensions.iter().map(|&d| d as usize).product::<usize>();
+    let data = section[data_start..data_start + 4 * count]
+        .chunks(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect();
+    (dimensions, data)
+}
+
+#[test]
+fn event_times_written_as_minutes_and_seconds() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    c3d.events.clear();
+    c3d.events.supports_events_labels = true;
+    c3d.events.extend(numbered_events(3));
+    c3d.events[2].time = 70.5;
+    let bytes = c3d.to_bytes().unwrap();
+    // EVENT:TIMES is a 2 by n array of [minutes, seconds]
+    let (dimensions, data) = float_record(&bytes, "TIMES");
+    assert_eq!(dimensions, vec![2, 3]);
+    assert_eq!(data, vec![0.0, 0.5, 0.0, 0.75, 1.0, 10.5]);
+    let reread = C3d::from_bytes(&bytes).unwrap();
+    assert_eq!(reread.events[2].time, 70.5);
+}
@@ -7,3 +7,20 @@ fn test() {
     let c3d2 = C3d::load("tests/data/short-copy.c3d").unwrap();
     assert_eq!(c3d, &c3d2);
 }
+
+#[test]
+fn changed_rate_and_scale_are_written() {
+    let mut c3d = C3d::load("tests/data/short.c3d").unwrap();
+    for integer in [false, true] {
+        if integer {
+            c3d.convert_to_integer();
+        }
+        c3d.points.frame_rate *= 2.0;
+        c3d.points.scale_factor *= 2.0;
+        let written = C3d::from_bytes(&c3d.to_bytes().unwrap()).unwrap();
+        // POINT:RATE and POINT:SCALE must agree with the header to be read back
+        assert_eq!(written.points.frame_rate, c3d.points.frame_rate);
+        assert_eq!(written.points.scale_factor, c3d.points.scale_factor);
+        assert_eq!(written.points.format, c3d.points.format);
+    }
+}
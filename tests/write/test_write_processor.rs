@@ -0,0 +1,64 @@
+use c3dio::prelude::*;
+
+#[test]
+fn processor_of_loaded_files() {
+    for (file, processor) in [
+        ("dec_real", Processor::Dec),
+        ("pc_real", Processor::Intel),
+        ("sgi_real", Processor::SgiMips),
+    ] {
+        let c3d = C3d::load(&format!("tests/c3d_org_samples/sample_02/{}.c3d", file)).unwrap();
+        assert_eq!(c3d.processor(), processor);
+    }
+}
+
+#[test]
+fn written_for_each_processor() {
+    let original = C3d::load("tests/data/short.c3d").unwrap();
+    for processor in [Processor::Intel, Processor::Dec, Processor::SgiMips] {
+        let options = WriteOptions::new().processor(processor);
+        let written = C3d::from_bytes(&original.to_bytes_with(&options).unwrap()).unwrap();
+        assert_eq!(written.processor(), processor);
+        assert_eq!(written.points, original.points);
+        assert_eq!(written.analog, original.analog);
+        assert_eq!(written.forces, original.forces);
+        assert_eq!(written.parameters, original.parameters);
+    }
+}
+
+#[test]
+fn cross_endian_round_trip() {
+    let sgi = C3d::load("tests/c3d_org_samples/sample_02/sgi_int.c3d").unwrap();
+    let options = WriteOptions::new().processor(Processor::Intel);
+    let intel = C3d::from_bytes(&sgi.to_bytes_with(&options).unwrap()).unwrap();
+    let options = WriteOptions::new().processor(Processor::SgiMips);
+    let bytes = intel.to_bytes_with(&options).unwrap();
+    let sgi_again = C3d::from_bytes(&bytes).unwrap();
+    assert_eq!(sgi_again.processor(), Processor::SgiMips);
+    assert_eq!(sgi_again.points, sgi.points);
+    assert_eq!(sgi_again.analog, sgi.analog);
+    assert_eq!(sgi_again.parameters, sgi.parameters);
+    assert_eq!(bytes, sgi_again.to_bytes().unwrap());
+}
+
+#[test]
+fn writer_with_processor() {
+    let reader = C3dReader::open("tests/data/short.c3d").unwrap();
+    let options = WriteOptions::new().processor(Processor::Dec);
+    let mut writer = C3dWriter::new_with(
+        std::io::Cursor::new(Vec::new()),
+        reader.c3d().clone(),
+        &options,
+    )
+    .unwrap();
+    for frame in reader {
+        let frame = frame.unwrap();
+        writer.write_frame(&frame.points, &frame.analog).unwrap();
+    }
+    let written = C3d::from_bytes(&writer.finish().unwrap().into_inner()).unwrap();
+    assert_eq!(written.processor(), Processor::Dec);
+    assert_eq!(
+        written.points,
+        C3d::load("tests/data/short.c3d").unwrap().points
+    );
+}
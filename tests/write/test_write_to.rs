@@ -0,0 +1,20 @@
+use c3dio::prelude::*;
+use std::io::Cursor;
+
+#[test]
+fn to_bytes_round_trip() {
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let bytes = c3d.to_bytes().unwrap();
+    let c3d2 = C3d::from_bytes(&bytes).unwrap();
+    assert_eq!(c3d.points, c3d2.points);
+    assert_eq!(c3d.analog, c3d2.analog);
+    assert_eq!(c3d.events, c3d2.events);
+}
+
+#[test]
+fn write_to_matches_to_bytes() {
+    let c3d = C3d::load("tests/data/short.c3d").unwrap();
+    let mut cursor = Cursor::new(Vec::new());
+    c3d.write_to(&mut cursor).unwrap();
+    assert_eq!(cursor.into_inner(), c3d.to_bytes().unwrap());
+}